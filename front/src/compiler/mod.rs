@@ -2,11 +2,12 @@ use std::collections::HashMap;
 
 use vm::{
     gc::GcRef, stackvec, Bytecode, EnvTable, Fn, FnKind, List, OpCode, OpCodeMetadata, Symbol,
-    Value, YexType,
+    Trait, Value, YexType,
 };
 
 use crate::parser::ast::{
-    BinOp, Bind, Def, Expr, ExprKind, Literal, Location, Stmt, StmtKind, VarDecl,
+    BinOp, Bind, Def, Expr, ExprKind, Literal, Location, Stmt, StmtKind, TraitMethod,
+    TypeAnnotation, VarDecl,
 };
 
 #[derive(Default)]
@@ -51,6 +52,7 @@ impl Compiler {
             opcode: op,
             line: loc.line,
             column: loc.column,
+            span: loc.span,
         })
     }
 
@@ -108,7 +110,14 @@ impl Compiler {
         self.scope_mut().opcodes[else_label].opcode = OpCode::Jmp(self.scope().opcodes.len());
     }
 
-    fn lambda_expr(&mut self, args: &[VarDecl], body: &Expr, loc: &Location) -> GcRef<Fn> {
+    fn lambda_expr(
+        &mut self,
+        args: &[VarDecl],
+        ret: Option<TypeAnnotation>,
+        body: &Expr,
+        doc: Option<&String>,
+        loc: &Location,
+    ) -> GcRef<Fn> {
         // creates the lambda scope
         let mut scope = Scope {
             opcodes: Vec::new(),
@@ -119,8 +128,15 @@ impl Compiler {
             // insert the argument into the scope
             scope.locals.insert(arg.name, idx);
 
+            // the annotated arguments are checked before being saved
+            if let Some(ty) = arg.ty {
+                let op =
+                    OpCodeMetadata::new(loc.line, loc.column, ty.span, OpCode::Expect(ty.name));
+                scope.opcodes.push(op);
+            }
+
             // pushes the opcode to save the argument
-            let op = OpCodeMetadata::new(loc.line, loc.column, OpCode::Save(idx));
+            let op = OpCodeMetadata::new(loc.line, loc.column, arg.span, OpCode::Save(idx));
             scope.opcodes.push(op);
         }
 
@@ -129,6 +145,11 @@ impl Compiler {
         // compiles the body
         self.expr(body);
 
+        if let Some(ty) = ret {
+            let op = OpCodeMetadata::new(loc.line, loc.column, ty.span, OpCode::Expect(ty.name));
+            self.scope_mut().opcodes.push(op);
+        }
+
         // pops the lambda scope
         let Scope { opcodes, .. } = self.scope_stack.pop().unwrap();
 
@@ -136,7 +157,9 @@ impl Compiler {
         let func = Fn {
             body: GcRef::new(FnKind::Bytecode(opcodes)),
             arity: args.len(),
+            optional: 0,
             args: stackvec![],
+            doc: doc.map(|doc| doc.clone().into()),
         };
 
         // push the function onto the stack
@@ -151,8 +174,8 @@ impl Compiler {
             ExprKind::Lit(lit) => self.emit_lit(lit, loc),
 
             // compiles a lambda expression
-            ExprKind::Lambda { args, body } => {
-                let func = self.lambda_expr(args, body, loc);
+            ExprKind::Lambda { args, ret, body } => {
+                let func = self.lambda_expr(args, *ret, body, None, loc);
                 self.emit_const(Value::Fn(func), loc);
             }
 
@@ -170,20 +193,25 @@ impl Compiler {
                 self.emit_op(OpCode::Call(args.len()), loc);
             }
 
-            ExprKind::Var(name) => {
-                // get the local index
-                let pred = self.scope().locals.get(name).copied();
-
-                if let Some(idx) = pred {
-                    // if the variable is in the current scope
-                    // emit the `Load` opcode, which loads a local
-                    self.emit_op(OpCode::Load(idx), loc);
-                } else {
-                    // otherwise emit the `Loag` opcode, which loads a global
-                    self.emit_op(OpCode::Loag(*name), loc);
+            ExprKind::Interpolation(parts) => {
+                for (idx, part) in parts.iter().enumerate() {
+                    self.expr(part);
+
+                    // embedded expressions are converted with the global `str`, even if a
+                    // local shadows it
+                    if !matches!(part.kind, ExprKind::Lit(Literal::Str(_))) {
+                        self.emit_op(OpCode::Loag(Symbol::from_static("str")), &part.location);
+                        self.emit_op(OpCode::Call(1), &part.location);
+                    }
+
+                    if idx > 0 {
+                        self.emit_op(OpCode::Add, &part.location);
+                    }
                 }
             }
 
+            ExprKind::Var(name) => self.load(*name, loc),
+
             ExprKind::If { cond, then, else_ } => self.if_expr(cond, then, else_, loc),
 
             ExprKind::Let { binds, body } => {
@@ -304,66 +332,207 @@ impl Compiler {
                 self.expr(obj);
                 self.emit_op(OpCode::Invk(field.name, args.len()), &node.location);
             }
+
+            ExprKind::Super {
+                parent,
+                field,
+                args,
+            } => {
+                for arg in args.iter().rev() {
+                    self.expr(arg);
+                }
+
+                self.load(Symbol::from_static("this"), loc);
+                self.emit_op(OpCode::Loag(*parent), loc);
+                self.emit_op(OpCode::Super(field.name, args.len()), loc);
+            }
+
+            // compiles a tail call, which jumps back to the start of the current function
+            ExprKind::Become(call) => match &call.kind {
+                ExprKind::App { callee, args } => {
+                    for arg in args.iter().rev() {
+                        self.expr(arg);
+                    }
+
+                    self.expr(callee);
+                    self.emit_op(OpCode::TCall(args.len()), loc);
+                }
+                _ => unreachable!(),
+            },
+
+            ExprKind::Error => unreachable!("sources with syntax errors aren't compiled"),
         }
     }
 
     fn stmt(&mut self, node: &Stmt) {
         match &node.kind {
             // compiles a `def` statement into a `Savg` instruction
-            StmtKind::Def(Def { bind, value, .. }) => {
-                self.expr(value);
+            StmtKind::Def(Def {
+                bind, value, doc, ..
+            }) => {
+                // documented functions keep their doc comment
+                match &value.kind {
+                    ExprKind::Lambda { args, ret, body } if doc.is_some() => {
+                        let func =
+                            self.lambda_expr(args, *ret, body, doc.as_ref(), &value.location);
+                        self.emit_const(Value::Fn(func), &value.location);
+                    }
+                    _ => self.expr(value),
+                }
                 self.emit_op(OpCode::Savg(bind.name), &node.location);
             }
             // compiles a `type` declaration into YexType and save the type to a global name
-            StmtKind::Type {
-                name,
+            StmtKind::Type { .. } => self.typedef(node),
+            // compiles a `trait` declaration and save the trait to a global name
+            StmtKind::Trait { name, methods, doc } => {
+                self.trait_def(name, methods, doc, &node.location);
+            }
+            // compiles an `impl` block, which adds its methods to the type when it runs
+            StmtKind::Impl {
+                trait_,
+                ty,
                 methods,
-                params,
-                init,
             } => {
-                self.typedef(
-                    name,
-                    methods,
-                    init,
-                    &params.iter().map(|x| x.name).collect::<Vec<_>>(),
-                    &node.location,
-                );
+                for m in methods {
+                    let func = match &m.value.kind {
+                        ExprKind::Lambda { args, ret, body } => {
+                            self.lambda_expr(args, *ret, body, m.doc.as_ref(), &m.value.location)
+                        }
+                        _ => unreachable!(),
+                    };
+                    self.emit_const(Value::Fn(func), &node.location);
+                    self.emit_const(Value::Sym(m.bind.name), &node.location);
+                }
+
+                // the errors of the implementation point to its header
+                let header = Location {
+                    span: trait_.span.to(ty.span),
+                    ..node.location
+                };
+                self.emit_op(OpCode::Loag(ty.name), &header);
+                self.emit_op(OpCode::Loag(trait_.name), &header);
+                self.emit_op(OpCode::Impl(methods.len()), &header);
             }
             // compiles a expression statement
             StmtKind::Expr(expr) => self.expr(expr),
         }
     }
 
-    fn typedef(
-        &mut self,
-        decl: &VarDecl,
-        methods: &[Def],
-        init: &Option<Def>,
-        params: &[Symbol],
-        loc: &Location,
-    ) {
+    /// Loads a variable, which is a local if it's in the current scope or a global otherwise
+    fn load(&mut self, name: Symbol, loc: &Location) {
+        match self.scope().locals.get(&name).copied() {
+            Some(idx) => self.emit_op(OpCode::Load(idx), loc),
+            None => self.emit_op(OpCode::Loag(name), loc),
+        }
+    }
+
+    fn typedef(&mut self, node: &Stmt) {
+        let StmtKind::Type {
+            name: decl,
+            params,
+            parent,
+            variants,
+            methods,
+            init,
+            doc,
+        } = &node.kind
+        else {
+            unreachable!()
+        };
+        let loc = &node.location;
+
         let mut table = EnvTable::new();
         for m in methods {
             let func = match &m.value.kind {
-                ExprKind::Lambda { args, body } => Value::Fn(self.lambda_expr(args, body, loc)),
+                ExprKind::Lambda { args, ret, body } => {
+                    Value::Fn(self.lambda_expr(args, *ret, body, m.doc.as_ref(), loc))
+                }
                 _ => unreachable!(),
             };
 
             table.insert(m.bind.name, func);
         }
 
-        let mut ty = YexType::new(decl.name, table, params.to_vec());
+        let init = init.as_ref().map(|init| match &init.value.kind {
+            ExprKind::Lambda { args, ret, body } => {
+                self.lambda_expr(args, *ret, body, init.doc.as_ref(), loc)
+            }
+            _ => unreachable!(),
+        });
 
-        if let Some(init) = init {
-            let func = match &init.value.kind {
-                ExprKind::Lambda { args, body } => self.lambda_expr(args, body, loc),
-                _ => unreachable!(),
+        // every variant is a type of its own, with the methods and the initializer of the type
+        let shape = |name: Symbol, params: &[VarDecl], methods: EnvTable| {
+            let names = params.iter().map(|it| it.name).collect();
+            let annotations = params.iter().map(|it| it.ty.map(|ty| ty.name)).collect();
+            let mut ty = YexType::new(name, methods, names).with_annotations(annotations);
+
+            if let Some(doc) = doc {
+                ty = ty.with_doc(doc.clone());
+            }
+            if let Some(init) = &init {
+                ty = ty.with_initializer(init.clone());
+            }
+            ty
+        };
+
+        let variants = variants
+            .iter()
+            .map(|it| {
+                let ty = shape(it.name.name, &it.params, table.clone());
+                GcRef::new(ty.with_variant_of(decl.name))
+            })
+            .collect::<Vec<_>>();
+
+        let ty = shape(decl.name, params, table).with_variants(variants.clone());
+        self.emit_const(Value::Type(GcRef::new(ty)), loc);
+        // the extended type is only known when the program runs, its errors point to its name
+        if let Some(parent) = parent {
+            let header = Location {
+                span: parent.span,
+                ..*loc
             };
+            self.emit_op(OpCode::Loag(parent.name), &header);
+            self.emit_op(OpCode::Extend, &header);
+        }
+        self.emit_op(OpCode::Savg(decl.name), loc);
 
-            ty = ty.with_initializer(func);
+        for variant in variants {
+            let name = variant.name;
+            let empty = variant.params.is_empty();
+            self.emit_const(Value::Type(variant), loc);
+            // the variants without fields are values, like `None`, instead of constructors
+            if empty {
+                self.emit_op(OpCode::New(0), loc);
+            }
+            self.emit_op(OpCode::Savg(name), loc);
         }
+    }
 
-        self.emit_const(Value::Type(GcRef::new(ty)), loc);
+    fn trait_def(
+        &mut self,
+        decl: &VarDecl,
+        methods: &[TraitMethod],
+        doc: &Option<String>,
+        loc: &Location,
+    ) {
+        let mut defaults = EnvTable::new();
+        for m in methods {
+            if let Some(body) = &m.body {
+                let func = self.lambda_expr(&m.args, m.ret, body, m.doc.as_ref(), &m.location);
+                defaults.insert(m.bind.name, Value::Fn(func));
+            }
+        }
+
+        let arities = methods
+            .iter()
+            .map(|it| (it.bind.name, it.args.len()))
+            .collect();
+        let mut tr = Trait::new(decl.name, arities).with_defaults(defaults);
+        if let Some(doc) = doc {
+            tr = tr.with_doc(doc.clone());
+        }
+
+        self.emit_const(Value::Trait(GcRef::new(tr)), loc);
         self.emit_op(OpCode::Savg(decl.name), loc);
     }
 
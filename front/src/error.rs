@@ -1,25 +1,78 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+use vm::{Diagnostic, Label, Span, Symbol};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// Parse errors
 pub struct ParseError {
-    line: usize,
-    column: usize,
-    message: &'static str,
+    diagnostic: Box<Diagnostic>,
 }
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[{}:{}] {}", self.line, self.column, self.message)
+        let diagnostic = &self.diagnostic;
+        write!(
+            f,
+            "[{}:{}] {}",
+            diagnostic.line, diagnostic.column, diagnostic.message
+        )
     }
 }
 
 impl ParseError {
-    pub(crate) fn throw<T>(line: usize, column: usize, message: String) -> Result<T, Self> {
-        Err(Self {
-            line,
-            column,
-            message: Box::leak(message.into_boxed_str()),
-        })
+    pub(crate) fn new(
+        code: &'static str,
+        line: usize,
+        column: usize,
+        span: Span,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            diagnostic: Box::new(Diagnostic::new(code, message, span, line, column)),
+        }
+    }
+
+    /// Adds a secondary label to the error
+    #[must_use]
+    pub(crate) fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.diagnostic.labels.push(Label::new(span, message));
+        self
+    }
+
+    /// Adds a help note to the error
+    #[must_use]
+    pub(crate) fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.diagnostic.help.push(help.into());
+        self
+    }
+
+    /// Sets the file that the error points to, for errors of sources that were parsed without
+    /// a file name
+    #[must_use]
+    pub fn with_file(mut self, file: Symbol) -> Self {
+        self.diagnostic.span.file = file;
+        for label in &mut self.diagnostic.labels {
+            label.span.file = file;
+        }
+        self
+    }
+
+    /// Returns the error as a diagnostic, which can be rendered with its source code
+    pub fn diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic
+    }
+}
+
+impl From<Diagnostic> for ParseError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostic: Box::new(diagnostic),
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(err: ParseError) -> Self {
+        *err.diagnostic
     }
 }
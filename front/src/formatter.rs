@@ -0,0 +1,990 @@
+//! The canonical formatter, which pretty-prints the AST and puts back the comments around
+//! the lines they were written on, or next to the names and the literals they were written
+//! between
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    error::ParseError,
+    lexer::Lexer,
+    parser::{
+        ast::{
+            BinOp, Bind, BindType, Def, Expr, ExprKind, Stmt, StmtKind, TypeAnnotation, UnOp,
+            VarDecl,
+        },
+        Parser,
+    },
+    tokens::{Comment, Token, TokenType},
+};
+
+/// The maximum width of a line, longer expressions are broken into multiple lines
+const WIDTH: usize = 80;
+const INDENT: usize = 2;
+
+// the precedence of the expressions, from the loosest to the tightest
+const OPEN: u8 = 0; // `let`, `if` and `fn`, they extend as far to the right as possible
+const SEQ: u8 = 1;
+const OR: u8 = 2;
+const AND: u8 = 3;
+const EQ: u8 = 4;
+const CMP: u8 = 5;
+const CONS: u8 = 6;
+const BITWISE: u8 = 7;
+const TERM: u8 = 8;
+const FACT: u8 = 9;
+const PREFIX: u8 = 10;
+const POW: u8 = 11;
+const NEW: u8 = 12;
+const POSTFIX: u8 = 13;
+const ATOM: u8 = 14;
+
+fn prec(expr: &Expr) -> u8 {
+    match &expr.kind {
+        ExprKind::Let { .. }
+        | ExprKind::If { .. }
+        | ExprKind::Lambda { .. }
+        | ExprKind::Become(_) => OPEN,
+        ExprKind::Seq { .. } => SEQ,
+        ExprKind::Binary { op, .. } => binary_prec(*op),
+        ExprKind::Cons { .. } => CONS,
+        ExprKind::UnOp(..) => PREFIX,
+        ExprKind::New { .. } => NEW,
+        ExprKind::App { .. }
+        | ExprKind::Invoke { .. }
+        | ExprKind::Super { .. }
+        | ExprKind::Field { .. } => POSTFIX,
+        ExprKind::Lit(_)
+        | ExprKind::Var(_)
+        | ExprKind::List(_)
+        | ExprKind::Interpolation(_)
+        | ExprKind::Error => ATOM,
+    }
+}
+
+fn binary_prec(op: BinOp) -> u8 {
+    match op {
+        BinOp::Or => OR,
+        BinOp::And => AND,
+        BinOp::Eq | BinOp::Ne => EQ,
+        BinOp::Less | BinOp::LessEq | BinOp::Greater | BinOp::GreaterEq => CMP,
+        BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shr | BinOp::Shl => BITWISE,
+        BinOp::Add | BinOp::Sub => TERM,
+        BinOp::Mul | BinOp::Div | BinOp::Rem | BinOp::FloorDiv => FACT,
+        BinOp::Pow => POW,
+    }
+}
+
+/// Returns the minimum precedence of the left and right operands of a binary operator
+fn operands_prec(op: BinOp) -> (u8, u8) {
+    match op {
+        // `**` is right-associative, and the unary operators can only appear on its right
+        BinOp::Pow => (NEW, PREFIX),
+        op => (binary_prec(op), binary_prec(op) + 1),
+    }
+}
+
+fn binary_op(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Less => "<",
+        BinOp::LessEq => "<=",
+        BinOp::Greater => ">",
+        BinOp::GreaterEq => ">=",
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Rem => "%",
+        BinOp::Pow => "**",
+        BinOp::FloorDiv => "~/",
+        BinOp::BitAnd => "&&&",
+        BinOp::BitOr => "|||",
+        BinOp::BitXor => "^^^",
+        BinOp::Shr => ">>>",
+        BinOp::Shl => "<<<",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::And => "and",
+        BinOp::Or => "or",
+    }
+}
+
+fn unary_op(op: UnOp) -> &'static str {
+    match op {
+        UnOp::Not => "not ",
+        UnOp::Neg => "-",
+        UnOp::BitNot => "~~~",
+    }
+}
+
+/// The first and the last source positions of an expression, as `(line, column)`
+fn span(expr: &Expr) -> ((usize, usize), (usize, usize)) {
+    let mut span = ((usize::MAX, usize::MAX), (0, 0));
+    visit(expr, &mut |pos| {
+        span.0 = span.0.min(pos);
+        span.1 = span.1.max(pos);
+    });
+    span
+}
+
+fn visit(expr: &Expr, f: &mut impl FnMut((usize, usize))) {
+    // the position of a sequence is the one of the token after it
+    if !matches!(expr.kind, ExprKind::Seq { .. }) {
+        f((expr.location.line, expr.location.column));
+    }
+
+    match &expr.kind {
+        ExprKind::If { cond, then, else_ } => {
+            visit(cond, f);
+            visit(then, f);
+            visit(else_, f);
+        }
+        ExprKind::Let { binds, body } => {
+            for bind in binds {
+                f((bind.location.line, bind.location.column));
+                visit(&bind.value, f);
+            }
+            visit(body, f);
+        }
+        ExprKind::Lambda { body, .. } => visit(body, f),
+        ExprKind::App { callee, args } => {
+            visit(callee, f);
+            args.iter().for_each(|it| visit(it, f));
+        }
+        ExprKind::Field { obj, .. } => visit(obj, f),
+        ExprKind::List(xs) | ExprKind::New { args: xs, .. } | ExprKind::Super { args: xs, .. } => {
+            xs.iter().for_each(|it| visit(it, f));
+        }
+        ExprKind::Binary { left, right, .. }
+        | ExprKind::Cons {
+            head: left,
+            tail: right,
+        }
+        | ExprKind::Seq { left, right } => {
+            visit(left, f);
+            visit(right, f);
+        }
+        ExprKind::Invoke { obj, args, .. } => {
+            visit(obj, f);
+            args.iter().for_each(|it| visit(it, f));
+        }
+        ExprKind::UnOp(_, right) | ExprKind::Become(right) => visit(right, f),
+        // the parts of interpolated strings are printed from the source
+        ExprKind::Var(_) | ExprKind::Lit(_) | ExprKind::Interpolation(_) | ExprKind::Error => {}
+    }
+}
+
+/// The expressions of a statement, the values of its definitions and the bodies of its methods
+fn stmt_exprs(stmt: &Stmt) -> Vec<&Expr> {
+    match &stmt.kind {
+        StmtKind::Def(def) => vec![&def.value],
+        StmtKind::Type { methods, init, .. } => init
+            .as_deref()
+            .into_iter()
+            .chain(methods)
+            .map(|it| &it.value)
+            .collect(),
+        StmtKind::Trait { methods, .. } => {
+            methods.iter().filter_map(|it| it.body.as_ref()).collect()
+        }
+        StmtKind::Impl { methods, .. } => methods.iter().map(|it| &it.value).collect(),
+        StmtKind::Expr(expr) => vec![expr],
+    }
+}
+
+/// The elements of a chain of `>>`, which is parsed as right-associative
+fn seq_items(expr: &Expr) -> Vec<&Expr> {
+    match &expr.kind {
+        ExprKind::Seq { left, right } => {
+            let mut items = vec![&**left];
+            items.extend(seq_items(right));
+            items
+        }
+        _ => vec![expr],
+    }
+}
+
+/// Writes the parameters of a function or of a type, with their type annotations
+fn parameters(decls: &[VarDecl]) -> String {
+    decls
+        .iter()
+        .map(|it| match it.ty {
+            Some(ty) => format!("{}: {}", it.name, ty.name),
+            None => it.name.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writes the parameters of a function followed by its annotated return type
+fn signature(args: &[VarDecl], ret: Option<TypeAnnotation>) -> String {
+    match ret {
+        Some(ret) => format!("({}) -> {}", parameters(args), ret.name),
+        None => format!("({})", parameters(args)),
+    }
+}
+
+fn width(str: &str) -> usize {
+    str.chars().count()
+}
+
+struct Line {
+    indent: usize,
+    /// The indentation of the lines broken from this one, if it's not `indent`
+    hang: Option<usize>,
+    text: String,
+    /// The source line of the first node printed on the line, comments written before it are
+    /// printed before the line
+    anchor: Option<usize>,
+    /// Whether a blank line before it in the source should be kept
+    item: bool,
+    /// Whether the line is part of a multi-line string, which must be kept as it is
+    verbatim: bool,
+    /// Whether the line ends inside a multi-line string
+    open: bool,
+}
+
+impl Line {
+    fn new(indent: usize) -> Self {
+        Self {
+            indent,
+            hang: None,
+            text: String::new(),
+            anchor: None,
+            item: false,
+            verbatim: false,
+            open: false,
+        }
+    }
+}
+
+struct Printer {
+    lines: Vec<Line>,
+    /// The source text of the literals, by the position of their last character
+    texts: HashMap<(usize, usize), String>,
+    /// The positions of the `in`, `else` and `end` keywords, in order
+    keywords: Vec<(TokenType, (usize, usize))>,
+    /// The lines where comments start, used to keep the commented expressions broken
+    comment_lines: Vec<usize>,
+    /// The block comments written next to a name or a literal, with code on both sides, by the
+    /// position of the name or the literal
+    inline: HashMap<(usize, usize), Inline>,
+}
+
+/// The block comments printed around a name or a literal
+#[derive(Default)]
+struct Inline {
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+impl Printer {
+    fn line(&mut self) -> &mut Line {
+        self.lines.last_mut().unwrap()
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.lines.push(Line::new(indent));
+    }
+
+    /// Starts a new line holding an item, like a statement or a method
+    fn item(&mut self, indent: usize) {
+        self.newline(indent);
+        self.line().item = true;
+    }
+
+    fn anchor(&mut self, line: usize) {
+        let current = self.line();
+        if current.anchor.is_none() && !current.verbatim {
+            current.anchor = Some(line);
+        }
+    }
+
+    fn indent(&self) -> usize {
+        let line = self.lines.last().unwrap();
+        line.hang.unwrap_or(line.indent)
+    }
+
+    fn column(&self) -> usize {
+        let line = self.lines.last().unwrap();
+        line.indent + width(&line.text)
+    }
+
+    fn fits(&self, str: &str) -> bool {
+        self.column() + width(str) <= WIDTH
+    }
+
+    fn write(&mut self, str: &str) {
+        let mut lines = str.split('\n');
+        self.line().text.push_str(lines.next().unwrap());
+
+        // the rest of a multi-line string is kept untouched
+        for text in lines {
+            self.line().open = true;
+            self.lines.push(Line {
+                text: text.to_string(),
+                verbatim: true,
+                ..Line::new(0)
+            });
+        }
+    }
+
+    /// Returns the line of the first keyword of the given type written after `pos`
+    fn keyword_after(&self, token: TokenType, pos: (usize, usize)) -> Option<usize> {
+        self.keywords
+            .iter()
+            .find(|(it, at)| *it == token && *at > pos)
+            .map(|(_, (line, _))| *line)
+    }
+
+    fn has_comments(&self, expr: &Expr) -> bool {
+        let ((start, _), (end, _)) = span(expr);
+        self.comment_lines
+            .iter()
+            .any(|line| *line >= start && *line < end)
+    }
+
+    fn literal(&self, expr: &Expr) -> String {
+        self.texts
+            .get(&(expr.location.line, expr.location.column))
+            .cloned()
+            .unwrap_or_else(|| match &expr.kind {
+                ExprKind::Lit(lit) => format!("{:?}", lit),
+                _ => String::new(),
+            })
+    }
+
+    /// Returns the name or the literal printed at `pos`, with the comments written next to it
+    fn leaf(&self, pos: (usize, usize), text: String) -> String {
+        match self.inline.get(&pos) {
+            Some(inline) => inline
+                .before
+                .iter()
+                .chain(Some(&text))
+                .chain(&inline.after)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => text,
+        }
+    }
+
+    fn args_flat(&self, args: &[Expr]) -> Option<String> {
+        let args = args
+            .iter()
+            .map(|it| self.flat(it, OPEN))
+            .collect::<Option<Vec<_>>>()?;
+        Some(args.join(", "))
+    }
+
+    /// Renders an expression in a single line, returns `None` if it must be broken
+    fn flat(&self, expr: &Expr, min: u8) -> Option<String> {
+        let str = match &expr.kind {
+            ExprKind::Let { .. } => return None,
+            ExprKind::Lit(_) | ExprKind::Interpolation(_) => self.leaf(
+                (expr.location.line, expr.location.column),
+                self.literal(expr),
+            ),
+            ExprKind::Var(name) => {
+                self.leaf((expr.location.line, expr.location.column), name.to_string())
+            }
+            ExprKind::List(xs) => format!("[{}]", self.args_flat(xs)?),
+            ExprKind::App { callee, args } => format!(
+                "{}({})",
+                self.flat(callee, callee_prec(callee))?,
+                self.args_flat(args)?
+            ),
+            ExprKind::Invoke { obj, field, args } => format!(
+                "{}.{}({})",
+                self.flat(obj, POSTFIX)?,
+                field.name,
+                self.args_flat(args)?
+            ),
+            ExprKind::Super { field, args, .. } => {
+                format!("super.{}({})", field.name, self.args_flat(args)?)
+            }
+            ExprKind::Field { obj, field } => {
+                format!("{}.{}", self.flat(obj, POSTFIX)?, field.name)
+            }
+            ExprKind::New { ty, args } => {
+                format!("new {}({})", self.flat(ty, ATOM)?, self.args_flat(args)?)
+            }
+            ExprKind::Binary { left, op, right } => {
+                let (lp, rp) = operands_prec(*op);
+                format!(
+                    "{} {} {}",
+                    self.flat(left, lp)?,
+                    binary_op(*op),
+                    self.flat(right, rp)?
+                )
+            }
+            ExprKind::Cons { head, tail } => format!(
+                "{} :: {}",
+                self.flat(head, CONS + 1)?,
+                self.flat(tail, CONS)?
+            ),
+            ExprKind::UnOp(op, right) => {
+                format!("{}{}", unary_op(*op), self.flat(right, PREFIX)?)
+            }
+            ExprKind::Seq { left, right } => {
+                format!("{} >> {}", self.flat(left, OR)?, self.flat(right, OPEN)?)
+            }
+            ExprKind::If { cond, then, else_ } => format!(
+                "if {} then {} else {}",
+                self.flat(cond, OPEN)?,
+                self.flat(then, OPEN)?,
+                self.flat(else_, OPEN)?
+            ),
+            ExprKind::Lambda { args, ret, body } => {
+                format!("fn{} = {}", signature(args, *ret), self.flat(body, OPEN)?)
+            }
+            ExprKind::Become(call) => format!("become {}", self.flat(call, POSTFIX)?),
+            ExprKind::Error => unreachable!("sources with syntax errors aren't formatted"),
+        };
+
+        if str.contains('\n') || self.has_comments(expr) {
+            return None;
+        }
+
+        if prec(expr) < min {
+            Some(format!("({})", str))
+        } else {
+            Some(str)
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, min: u8) {
+        let ((line, _), _) = span(expr);
+        self.anchor(line);
+
+        if let Some(flat) = self.flat(expr, min) {
+            if self.fits(&flat) {
+                return self.write(&flat);
+            }
+        }
+
+        if prec(expr) < min {
+            self.write("(");
+            self.broken(expr);
+            self.write(")");
+        } else {
+            self.broken(expr);
+        }
+    }
+
+    /// Prints the elements of `owner` between delimiters, one per line if they don't fit or
+    /// if there are comments between them
+    fn list(&mut self, owner: &Expr, open: &str, xs: &[Expr], close: &str) {
+        self.write(open);
+
+        if let Some(flat) = self.args_flat(xs).filter(|_| !self.has_comments(owner)) {
+            if self.fits(&format!("{}{}", flat, close)) {
+                self.write(&flat);
+                return self.write(close);
+            }
+        }
+
+        // a single list or function argument hugs the parentheses
+        if let [x @ Expr {
+            kind: ExprKind::List(_) | ExprKind::Lambda { .. },
+            ..
+        }] = xs
+        {
+            self.expr(x, OPEN);
+            return self.write(close);
+        }
+
+        let indent = self.indent();
+        for x in xs {
+            self.newline(indent + INDENT);
+            self.expr(x, OPEN);
+            self.write(",");
+        }
+        self.newline(indent);
+        self.write(close);
+    }
+
+    /// Prints the ` = value` of a definition, a binding or a function
+    fn assign(&mut self, value: &Expr) {
+        self.write(" =");
+
+        if let Some(flat) = self.flat(value, OPEN) {
+            if self.fits(&format!(" {}", flat)) {
+                return self.write(&format!(" {}", flat));
+            }
+        }
+
+        match &value.kind {
+            ExprKind::Let { .. }
+            | ExprKind::If { .. }
+            | ExprKind::Seq { .. }
+            | ExprKind::Binary { .. }
+            | ExprKind::Cons { .. }
+            | ExprKind::UnOp(..) => {
+                let indent = self.indent();
+                self.newline(indent + INDENT);
+                self.expr(value, OPEN);
+            }
+            _ => {
+                self.write(" ");
+                self.expr(value, OPEN);
+            }
+        }
+    }
+
+    fn bind(&mut self, bind: &Bind) {
+        self.anchor(bind.location.line);
+        let pos = (bind.location.line, bind.location.column);
+        self.write(&self.leaf(pos, bind.bind.name.to_string()));
+
+        match &bind.value.kind {
+            ExprKind::Lambda { args, ret, body } => {
+                self.write(&signature(args, *ret));
+                self.assign(body);
+            }
+            _ => self.assign(&bind.value),
+        }
+    }
+
+    fn broken(&mut self, expr: &Expr) {
+        let indent = self.indent();
+
+        match &expr.kind {
+            ExprKind::Let { binds, body } => {
+                self.write("let ");
+
+                // the bindings are aligned after the `let`
+                let column = self.column();
+                for (idx, bind) in binds.iter().enumerate() {
+                    if idx > 0 {
+                        self.newline(column);
+                    } else {
+                        self.line().hang = Some(column);
+                    }
+                    self.bind(bind);
+                }
+
+                self.newline(indent);
+                let last = binds.last().map_or((0, 0), |it| span(&it.value).1);
+                if let Some(line) = self.keyword_after(TokenType::In, last) {
+                    self.anchor(line);
+                }
+                self.write("in ");
+                self.expr(body, OPEN);
+            }
+
+            ExprKind::If { cond, then, else_ } => {
+                self.write("if ");
+                self.expr(cond, OPEN);
+                self.write(" then");
+                self.newline(indent + INDENT);
+                self.expr(then, OPEN);
+
+                self.newline(indent);
+                if let Some(line) = self.keyword_after(TokenType::Else, span(then).1) {
+                    self.anchor(line);
+                }
+                self.write("else");
+
+                if let ExprKind::If { .. } = else_.kind {
+                    self.write(" ");
+                } else {
+                    self.newline(indent + INDENT);
+                }
+                self.expr(else_, OPEN);
+            }
+
+            ExprKind::Seq { .. } => {
+                let items = seq_items(expr);
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        self.newline(indent);
+                        self.write(">> ");
+                    }
+
+                    let min = if idx == items.len() - 1 { OPEN } else { OR };
+                    self.expr(item, min);
+                }
+            }
+
+            ExprKind::Lambda { args, ret, body } => {
+                self.write(&format!("fn{}", signature(args, *ret)));
+                self.assign(body);
+            }
+
+            ExprKind::App { callee, args } => {
+                self.expr(callee, callee_prec(callee));
+                self.list(expr, "(", args, ")");
+            }
+
+            ExprKind::Invoke { obj, field, args } => {
+                self.expr(obj, POSTFIX);
+                self.write(&format!(".{}", field.name));
+                self.list(expr, "(", args, ")");
+            }
+
+            ExprKind::Super { field, args, .. } => {
+                self.write(&format!("super.{}", field.name));
+                self.list(expr, "(", args, ")");
+            }
+
+            ExprKind::Field { obj, field } => {
+                self.expr(obj, POSTFIX);
+                self.write(&format!(".{}", field.name));
+            }
+
+            ExprKind::New { ty, args } => {
+                self.write("new ");
+                self.expr(ty, ATOM);
+                self.list(expr, "(", args, ")");
+            }
+
+            ExprKind::List(xs) => self.list(expr, "[", xs, "]"),
+
+            ExprKind::Binary { left, op, right } => {
+                let (lp, rp) = operands_prec(*op);
+                self.expr(left, lp);
+                self.write(&format!(" {} ", binary_op(*op)));
+                self.expr(right, rp);
+            }
+
+            ExprKind::Cons { head, tail } => {
+                self.expr(head, CONS + 1);
+                self.write(" :: ");
+                self.expr(tail, CONS);
+            }
+
+            ExprKind::UnOp(op, right) => {
+                self.write(unary_op(*op));
+                self.expr(right, PREFIX);
+            }
+
+            ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Interpolation(_) => {
+                let text = match &expr.kind {
+                    ExprKind::Var(name) => name.to_string(),
+                    _ => self.literal(expr),
+                };
+                self.write(&self.leaf((expr.location.line, expr.location.column), text));
+            }
+
+            ExprKind::Become(call) => {
+                self.write("become ");
+                self.expr(call, POSTFIX);
+            }
+
+            ExprKind::Error => unreachable!("sources with syntax errors aren't formatted"),
+        }
+    }
+
+    fn def(&mut self, def: &Def) {
+        self.write("def ");
+        self.write(def.bind.name.as_str());
+
+        match &def.value.kind {
+            ExprKind::Lambda { args, ret, body } if def.bind_type == BindType::Fn => {
+                self.write(&signature(args, *ret));
+                self.assign(body);
+            }
+            _ => self.assign(&def.value),
+        }
+    }
+
+    /// Writes the methods of a `type` or `impl` block, and the `end` that closes it
+    fn methods(&mut self, stmt: &Stmt, defs: &[&Def]) {
+        for def in defs {
+            self.item(INDENT);
+            self.anchor(def.value.location.line);
+            self.def(def);
+        }
+
+        self.newline(0);
+        let last = defs
+            .last()
+            .map_or((stmt.location.line, stmt.location.column), |it| {
+                span(&it.value).1
+            });
+        self.end(last);
+    }
+
+    /// Writes the `end` of a block whose last node ends at `last`
+    fn end(&mut self, last: (usize, usize)) {
+        if let Some(line) = self.keyword_after(TokenType::End, last) {
+            self.anchor(line);
+        }
+        self.write("end");
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        self.item(0);
+        self.anchor(stmt.location.line);
+
+        match &stmt.kind {
+            StmtKind::Def(def) => self.def(def),
+
+            StmtKind::Type {
+                name,
+                params,
+                parent,
+                variants,
+                methods,
+                init,
+                ..
+            } => {
+                if let Some(parent) = parent {
+                    self.write(&format!(
+                        "type {}({}) extends {}",
+                        name.name,
+                        parameters(params),
+                        parent.name
+                    ));
+                } else if variants.is_empty() {
+                    self.write(&format!("type {}({})", name.name, parameters(params)));
+                } else {
+                    let variants = variants
+                        .iter()
+                        .map(|it| match it.params.as_slice() {
+                            [] => it.name.name.to_string(),
+                            params => format!("{}({})", it.name.name, parameters(params)),
+                        })
+                        .collect::<Vec<_>>();
+                    self.write(&format!("type {} = {}", name.name, variants.join(" | ")));
+                }
+
+                // `init` is kept where it was written
+                let mut defs = init
+                    .as_deref()
+                    .into_iter()
+                    .chain(methods)
+                    .collect::<Vec<_>>();
+                defs.sort_by_key(|it| (it.value.location.line, it.value.location.column));
+
+                self.methods(stmt, &defs);
+            }
+
+            StmtKind::Trait { name, methods, .. } => {
+                self.write(&format!("trait {} =", name.name));
+
+                for method in methods {
+                    self.item(INDENT);
+                    self.anchor(method.location.line);
+                    self.write(&format!(
+                        "def {}{}",
+                        method.bind.name,
+                        signature(&method.args, method.ret)
+                    ));
+                    if let Some(body) = &method.body {
+                        self.assign(body);
+                    }
+                }
+
+                self.newline(0);
+                let last = methods.last().map_or(
+                    (stmt.location.line, stmt.location.column),
+                    |it| match &it.body {
+                        Some(body) => span(body).1,
+                        None => (it.location.line, it.location.column),
+                    },
+                );
+                self.end(last);
+            }
+
+            StmtKind::Impl {
+                trait_,
+                ty,
+                methods,
+            } => {
+                self.write(&format!("impl {} for {}", trait_.name, ty.name));
+                self.methods(stmt, &methods.iter().collect::<Vec<_>>());
+            }
+
+            StmtKind::Expr(expr) => self.expr(expr, OPEN),
+        }
+    }
+}
+
+/// The callee of a call is a primary expression, or another call
+fn callee_prec(callee: &Expr) -> u8 {
+    match callee.kind {
+        ExprKind::App { .. } => POSTFIX,
+        _ => ATOM,
+    }
+}
+
+/// Whether a token is printed on its own as a name or a literal
+fn is_leaf(token: &TokenType) -> bool {
+    matches!(
+        token,
+        TokenType::Name(_)
+            | TokenType::Int(_)
+            | TokenType::BigInt(_)
+            | TokenType::Num(_)
+            | TokenType::Str(_)
+            | TokenType::Interpolated(_)
+            | TokenType::Sym(_)
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+    )
+}
+
+/// Joins the printed lines, putting back the comments: the ones that start their lines are
+/// printed before the first line that comes after them in the source, and the trailing ones
+/// at the end of the last line that comes before them
+fn render(lines: Vec<Line>, comments: Vec<Comment>, blank: &[bool]) -> String {
+    let mut comments = VecDeque::from(comments);
+    let mut out: Vec<(usize, String, bool)> = Vec::new();
+    let mut last = None;
+
+    let is_blank = |line: usize| line >= 2 && blank.get(line - 2).copied().unwrap_or(false);
+
+    let flush = |out: &mut Vec<(usize, String, bool)>,
+                 last: Option<usize>,
+                 comment: Comment,
+                 indent: usize| {
+        match last {
+            Some(idx) if !comment.own_line => {
+                let line: &mut (usize, String, bool) = &mut out[idx];
+                line.1.push(' ');
+                line.1.push_str(&comment.text);
+            }
+            _ => {
+                let prev_blank = out.last().is_none_or(|it| it.1.is_empty());
+                if is_blank(comment.line) && !prev_blank {
+                    out.push((0, String::new(), false));
+                }
+
+                let mut text = comment.text.split('\n');
+                out.push((indent, text.next().unwrap().to_string(), false));
+                for text in text {
+                    out.push((0, text.to_string(), true));
+                }
+            }
+        }
+    };
+
+    for line in lines {
+        if let Some(anchor) = line.anchor {
+            // comments before the closing keywords belong to the block they close
+            let indent = match line.text.split(' ').next() {
+                Some("end" | "in" | "else") => out.last().map_or(0, |it| it.0).max(line.indent),
+                _ => line.indent,
+            };
+
+            // a comment starting the line of the node comes before it
+            while comments
+                .front()
+                .is_some_and(|it| it.line < anchor || (it.line == anchor && it.own_line))
+            {
+                let comment = comments.pop_front().unwrap();
+                flush(&mut out, last, comment, indent);
+            }
+
+            let prev_blank = out.last().is_none_or(|it| it.1.is_empty());
+            if line.item && is_blank(anchor) && !prev_blank {
+                out.push((0, String::new(), false));
+            }
+        }
+
+        let (anchored, verbatim, open) = (line.anchor.is_some(), line.verbatim, line.open);
+        out.push((line.indent, line.text, verbatim));
+
+        // trailing comments can't be written inside a string, but they can after its end
+        if open {
+            last = None;
+        } else if anchored || verbatim {
+            last = Some(out.len() - 1);
+        }
+    }
+
+    for comment in comments {
+        flush(&mut out, last, comment, 0);
+    }
+
+    let mut res = String::new();
+    for (indent, text, verbatim) in out {
+        if verbatim {
+            res.push_str(&text);
+        } else if !text.is_empty() {
+            res.push_str(&" ".repeat(indent));
+            res.push_str(text.trim_end());
+        }
+        res.push('\n');
+    }
+    res
+}
+
+/// Formats a source file, keeping its comments
+pub fn format(src: &str) -> Result<String, Vec<ParseError>> {
+    let (tokens, comments) = Lexer::new(src).trivia().map_err(|e| vec![e])?;
+    let (stmts, errors) = Parser::new(Lexer::new(src)).map_err(|e| vec![e])?.parse();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // the names and the literals are printed at the positions of their nodes, or of bindings
+    let mut nodes = HashSet::new();
+    for expr in stmts.iter().flat_map(stmt_exprs) {
+        visit(expr, &mut |pos| {
+            nodes.insert(pos);
+        });
+    }
+
+    // block comments between two tokens of a line stay next to the name or the literal before
+    // them, or else after them, the other ones are put back around the lines
+    let is_node =
+        |token: &Token| is_leaf(&token.token) && nodes.contains(&(token.line, token.column));
+    let mut inline: HashMap<_, Inline> = HashMap::new();
+    let mut rest = Vec::new();
+    for comment in comments {
+        let pos = (comment.line, comment.column);
+        let idx = tokens.partition_point(|(it, _)| (it.line, it.column) < pos);
+        let prev = idx.checked_sub(1).map(|idx| &tokens[idx].0);
+        let next = tokens.get(idx).map(|(it, _)| it);
+
+        let inside = comment.text.starts_with("/*")
+            && !comment.text.contains('\n')
+            && next.is_some_and(|it| it.line == comment.line);
+        match (prev, next) {
+            (Some(prev), _) if inside && prev.line == comment.line && is_node(prev) => inline
+                .entry((prev.line, prev.column))
+                .or_default()
+                .after
+                .push(comment.text),
+            (_, Some(next)) if inside && is_node(next) => inline
+                .entry((next.line, next.column))
+                .or_default()
+                .before
+                .push(comment.text),
+            _ => rest.push(comment),
+        }
+    }
+    let comments = rest;
+
+    let mut printer = Printer {
+        lines: Vec::new(),
+        texts: HashMap::new(),
+        keywords: Vec::new(),
+        comment_lines: comments.iter().map(|it| it.line).collect(),
+        inline,
+    };
+
+    for (token, text) in tokens {
+        let pos = (token.line, token.column);
+        match token.token {
+            TokenType::In | TokenType::Else | TokenType::End => {
+                printer.keywords.push((token.token, pos))
+            }
+            _ => {
+                printer.texts.insert(pos, text);
+            }
+        }
+    }
+
+    for stmt in &stmts {
+        printer.stmt(stmt);
+    }
+
+    let blank = src
+        .lines()
+        .map(|it| it.trim().is_empty())
+        .collect::<Vec<_>>();
+    Ok(render(printer.lines, comments, &blank))
+}
@@ -1,7 +1,7 @@
-use vm::Symbol;
+use vm::{BigInt, Span, Symbol};
 
 use crate::error::ParseError;
-use crate::tokens::{fetch_keyword, Token, TokenType};
+use crate::tokens::{fetch_keyword, Comment, StrPart, Token, TokenType};
 
 const EOF: char = '\0';
 
@@ -9,23 +9,137 @@ pub struct Lexer {
     line: usize,
     column: usize,
     tokens: Vec<char>,
+    /// The byte offset of every character, followed by the length of the source
+    offsets: Vec<usize>,
+    /// The byte offset of the source in its file, which isn't zero for interpolated expressions
+    base: usize,
+    /// The file the source comes from
+    file: Symbol,
     idx: usize,
+    /// The index where the last token starts
+    start: usize,
+    /// The comments skipped so far
+    comments: Vec<Comment>,
+    /// The errors that didn't stop the current token from being lexed, yielded before the next
+    /// token
+    recovered: Vec<ParseError>,
 }
 
 type Tk = Result<Token, ParseError>;
+/// The tokens of a source, with their source text, and its comments
+type Trivia = (Vec<(Token, String)>, Vec<Comment>);
 
 impl Lexer {
     pub fn new<T: Into<String>>(t: T) -> Self {
+        let src = t.into();
+        let offsets = src
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain(std::iter::once(src.len()))
+            .collect();
+
         Self {
-            tokens: t.into().chars().collect(),
+            tokens: src.chars().collect(),
+            offsets,
+            base: 0,
+            file: Span::unnamed(),
             line: 1,
             column: 1,
             idx: 0,
+            start: 0,
+            comments: Vec::new(),
+            recovered: Vec::new(),
+        }
+    }
+
+    /// Lexes the whole source, returning every token along with its source text, and the
+    /// comments, which the formatter needs to keep
+    pub fn trivia(mut self) -> Result<Trivia, ParseError> {
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = self.get()?;
+            if token.token == TokenType::Eof {
+                return Ok((tokens, self.comments));
+            }
+
+            let text = self.tokens[self.start..=self.idx].iter().collect();
+            tokens.push((token, text));
+            self.next();
+        }
+    }
+
+    /// Records the comment that starts at `self.start` and ends right before `end`
+    fn comment(&mut self, line: usize, end: usize) {
+        let before = self.tokens[..self.start]
+            .iter()
+            .rev()
+            .take_while(|c| **c != '\n');
+        let own_line = before.clone().all(|c| c.is_whitespace());
+        let column = before.count() + 1;
+
+        self.comments.push(Comment {
+            text: self.tokens[self.start..end.min(self.tokens.len())]
+                .iter()
+                .collect(),
+            line,
+            column,
+            own_line,
+        });
+    }
+
+    /// Creates a lexer whose first character is at the given position and byte offset, used to
+    /// lex the expressions embedded in interpolated strings
+    pub fn with_position<T: Into<String>>(t: T, line: usize, column: usize, offset: usize) -> Self {
+        Self {
+            line,
+            column,
+            base: offset,
+            ..Self::new(t)
         }
     }
 
-    fn throw<A, T: Into<String>>(&self, str: T) -> Result<A, ParseError> {
-        ParseError::throw(self.line, self.column, str.into())
+    /// Sets the file that the spans of the tokens point to
+    #[must_use]
+    pub fn with_file(mut self, file: Symbol) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// The file the source comes from
+    pub fn file(&self) -> Symbol {
+        self.file
+    }
+
+    /// Returns the byte offset of the character at `idx`
+    fn offset(&self, idx: usize) -> usize {
+        self.base + self.offsets[idx.min(self.tokens.len())]
+    }
+
+    /// Returns the span of `len` characters starting at `idx`
+    fn span(&self, idx: usize, len: usize) -> Span {
+        Span::new(self.file, self.offset(idx), self.offset(idx + len))
+    }
+
+    fn error<T: Into<String>>(&self, code: &'static str, str: T) -> ParseError {
+        ParseError::new(code, self.line, self.column, self.span(self.idx, 1), str)
+    }
+
+    fn throw<A, T: Into<String>>(&self, code: &'static str, str: T) -> Result<A, ParseError> {
+        Err(self.error(code, str))
+    }
+
+    /// Records an error that the lexer can carry on from, without stopping the current token
+    fn recover(&mut self, err: ParseError) {
+        self.recovered.push(err);
+    }
+
+    /// Raises an error for a delimiter of `len` characters, opened at the start of the current
+    /// token, that reached the end of the source without being closed
+    fn unclosed<A>(&self, len: usize) -> Result<A, ParseError> {
+        Err(self
+            .error("E0002", "unclosed delimiter")
+            .with_label(self.span(self.start, len), "delimiter opened here"))
     }
 
     fn get_char(&self, idx: usize) -> char {
@@ -37,7 +151,7 @@ impl Lexer {
     }
 
     fn next(&mut self) -> char {
-        self.idx += 1;
+        // a newline belongs to the end of its line, the next character starts a new one
         match self.current() {
             '\n' => {
                 self.column = 1;
@@ -45,11 +159,23 @@ impl Lexer {
             }
             _ => self.column += 1,
         };
+        self.idx += 1;
         self.current()
     }
 
     fn back(&mut self) -> char {
         self.idx -= 1;
+        match self.current() {
+            '\n' => {
+                let start = self.tokens[..self.idx]
+                    .iter()
+                    .rposition(|c| *c == '\n')
+                    .map_or(0, |idx| idx + 1);
+                self.column = self.idx - start + 1;
+                self.line -= 1;
+            }
+            _ => self.column -= 1,
+        }
         self.current()
     }
 
@@ -58,7 +184,7 @@ impl Lexer {
 
         while cond(self.get_char(self.idx + 1)) {
             if self.get_char(self.idx + 1) == '\0' {
-                self.throw("Unclosed delimiter opened here")?;
+                self.unclosed(1)?;
             }
             self.next();
             item.push(self.current());
@@ -67,19 +193,44 @@ impl Lexer {
     }
 
     fn take_unicode(&mut self, len: usize) -> Result<char, ParseError> {
+        let kind = self.current();
         self.next();
         let mut unicode = String::new();
         while unicode.len() < len {
             if !self.current().is_ascii_hexdigit() {
-                return self.throw("malformed Unicode character escape sequence");
+                let err = self
+                    .error("E0003", "malformed Unicode character escape sequence")
+                    .with_help(format!(
+                        "`\\{}` must be followed by {} hex digits",
+                        kind, len
+                    ));
+                self.recover(err);
+                break;
             }
             unicode.push(self.current());
             self.next();
         }
         self.back();
+
+        if unicode.len() < len {
+            return Ok(char::REPLACEMENT_CHARACTER);
+        }
+
         let unicode = u32::from_str_radix(&unicode, 16).unwrap();
-        let unicode = char::from_u32(unicode).unwrap();
-        Ok(unicode)
+        match char::from_u32(unicode) {
+            Some(unicode) => Ok(unicode),
+            None => {
+                let err = self.error(
+                    "E0003",
+                    format!(
+                        "invalid Unicode character `{:X}` in escape sequence",
+                        unicode
+                    ),
+                );
+                self.recover(err);
+                Ok(char::REPLACEMENT_CHARACTER)
+            }
+        }
     }
 
     fn escape_char(&mut self) -> Result<String, ParseError> {
@@ -92,23 +243,58 @@ impl Lexer {
             '0' => EOF,
             '\\' => '\\',
             '"' => '"',
+            '$' => '$',
             'r' => '\r',
-            other => self.throw(format!("Unknow escape char `{}`", other))?,
+            // the character is kept as if it wasn't escaped, so the rest of the string is
+            // still lexed
+            other => {
+                let err = self
+                    .error("E0003", format!("unknown escape character `{}`", other))
+                    .with_help(
+                        "the valid escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\$`, \
+                         `\\xNN`, `\\uNNNN` and `\\UNNNNNNNN`",
+                    );
+                self.recover(err);
+                other
+            }
         };
         self.next();
         Ok(char.into())
     }
 
-    fn take_str(&mut self) -> Result<String, ParseError> {
+    /// Checks if the current `r` starts a raw string with hashes, like `r#"`
+    fn raw_hashes(&self) -> bool {
+        let hashes = (1..).take_while(|n| self.peek_at(*n) == '#').count();
+        hashes > 0 && self.peek_at(hashes + 1) == '"'
+    }
+
+    fn at_str_end(&self, triple: bool) -> bool {
+        self.current() == '"' && (!triple || (self.peek_at(1) == '"' && self.peek_at(2) == '"'))
+    }
+
+    /// Takes the contents of a string literal, stopping before the closing quote, `indent` is
+    /// the amount of indentation stripped from every line of a triple-quoted string
+    fn take_str(&mut self, indent: Option<usize>) -> Result<TokenType, ParseError> {
+        let mut parts = Vec::new();
         let mut item = String::new();
 
-        while self.current() != '"' {
+        while !self.at_str_end(indent.is_some()) {
             let chr = match self.current() {
                 '\\' => {
                     self.next();
                     self.escape_char()?
                 }
-                EOF => self.throw("Unclosed delimiter opened here")?,
+                '$' if self.peek_at(1) == '{' => {
+                    parts.push(StrPart::Lit(std::mem::take(&mut item)));
+                    parts.push(self.take_interpolation()?);
+                    continue;
+                }
+                EOF => self.unclosed(if indent.is_some() { 3 } else { 1 })?,
+                '\n' if indent.is_some() => {
+                    self.next();
+                    self.skip_indent(indent.unwrap());
+                    '\n'.to_string()
+                }
                 other => {
                     let other = other.to_string();
                     self.next();
@@ -119,7 +305,255 @@ impl Lexer {
             item.push_str(&chr);
         }
         self.back();
-        Ok(item)
+
+        if parts.is_empty() {
+            return Ok(TokenType::Str(item));
+        }
+
+        parts.push(StrPart::Lit(item));
+        parts.retain(|part| !matches!(part, StrPart::Lit(s) if s.is_empty()));
+        Ok(TokenType::Interpolated(parts))
+    }
+
+    /// Skips up to `indent` whitespace characters at the start of a line, and the whole line if
+    /// it only holds the indentation of the closing `"""`
+    fn skip_indent(&mut self, indent: usize) {
+        for _ in 0..indent {
+            if !matches!(self.current(), ' ' | '\t') {
+                break;
+            }
+            self.next();
+        }
+
+        let rest = self.blank_len();
+        if self.tokens[self.idx + rest..].starts_with(&['"', '"', '"']) {
+            for _ in 0..rest {
+                self.next();
+            }
+        }
+    }
+
+    /// Counts the spaces and tabs starting at the current character
+    fn blank_len(&self) -> usize {
+        self.tokens[self.idx.min(self.tokens.len())..]
+            .iter()
+            .take_while(|c| matches!(c, ' ' | '\t'))
+            .count()
+    }
+
+    /// Computes the indentation stripped from a triple-quoted string starting at the current
+    /// character: the smallest indentation of its non-blank lines and of the closing `"""`
+    fn triple_indent(&self) -> usize {
+        let mut text = String::new();
+        let mut idx = self.idx;
+
+        loop {
+            match self.get_char(idx) {
+                EOF => break,
+                '"' if self.get_char(idx + 1) == '"' && self.get_char(idx + 2) == '"' => break,
+                '\\' => {
+                    text.push(' ');
+                    idx += 1;
+                }
+                c => text.push(c),
+            }
+            idx += 1;
+        }
+
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let indent = |line: &str| line.chars().take_while(|c| matches!(c, ' ' | '\t')).count();
+
+        lines
+            .iter()
+            .enumerate()
+            // the first line is the one with the opening `"""`
+            .skip(1)
+            .filter(|(n, line)| !line.trim().is_empty() || *n == lines.len() - 1)
+            .map(|(_, line)| indent(line))
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn triple_str(&mut self) -> Result<TokenType, ParseError> {
+        // skips the opening `"""`
+        self.next();
+        self.next();
+        self.next();
+
+        let indent = self.triple_indent();
+
+        // the line break right after the opening `"""` isn't part of the string
+        let rest = self.blank_len();
+        if self.peek_at(rest) == '\n' {
+            for _ in 0..=rest {
+                self.next();
+            }
+            self.skip_indent(indent);
+        }
+
+        let str = self.take_str(Some(indent))?;
+
+        // leaves the lexer at the last quote of the closing `"""`
+        self.next();
+        self.next();
+        self.next();
+        Ok(str)
+    }
+
+    /// Takes a raw string literal, like `r"..."` or `r#"..."#`, where escapes aren't processed
+    fn raw_str(&mut self) -> Result<TokenType, ParseError> {
+        // skips the `r` and the hashes
+        let mut hashes = 0;
+        while self.next() == '#' {
+            hashes += 1;
+        }
+
+        let mut item = String::new();
+        loop {
+            match self.next() {
+                EOF => self.unclosed(hashes + 2)?,
+                '"' if (1..=hashes).all(|n| self.peek_at(n) == '#') => break,
+                c => item.push(c),
+            }
+        }
+
+        for _ in 0..hashes {
+            self.next();
+        }
+        Ok(TokenType::Str(item))
+    }
+
+    /// Takes the source of a `${...}` expression, leaving the lexer after the closing brace
+    fn take_interpolation(&mut self) -> Result<StrPart, ParseError> {
+        let open = self.span(self.idx, 2);
+
+        // skips the `${`
+        self.next();
+        self.next();
+
+        let (line, column, offset) = (self.line, self.column, self.offset(self.idx));
+        let mut src = String::new();
+        let mut depth = 0;
+        let mut in_str = false;
+
+        loop {
+            match self.current() {
+                EOF => Err(self
+                    .error("E0002", "unclosed `${` in string interpolation")
+                    .with_label(open, "interpolation opened here"))?,
+                '}' if !in_str && depth == 0 => break,
+                '{' if !in_str => depth += 1,
+                '}' if !in_str => depth -= 1,
+                '"' => in_str = !in_str,
+                '\\' if in_str => {
+                    src.push('\\');
+                    self.next();
+                }
+                _ => {}
+            }
+            src.push(self.current());
+            self.next();
+        }
+
+        // skips the `}`
+        self.next();
+
+        if src.trim().is_empty() {
+            let err = ParseError::new(
+                "E0005",
+                line,
+                column,
+                open.to(self.span(self.idx - 1, 1)),
+                "empty expression in string interpolation",
+            )
+            .with_help("use `$` without braces, or escape it as `\\$`, to write a literal `${`");
+            self.recover(err);
+            return Ok(StrPart::Lit(String::new()));
+        }
+
+        Ok(StrPart::Expr {
+            src,
+            line,
+            column,
+            offset,
+        })
+    }
+
+    fn radix_number(&mut self, radix: u32) -> Result<TokenType, ParseError> {
+        // skips the radix prefix
+        self.next();
+
+        let mut digits = String::new();
+        while self.peek_at(1).is_digit(radix) || self.peek_at(1) == '_' {
+            self.next();
+            if self.current() != '_' {
+                digits.push(self.current());
+            }
+        }
+
+        if digits.is_empty() {
+            let err = self.error("E0004", "expected digits after the number prefix");
+            self.recover(err);
+            return Ok(TokenType::Int(0));
+        }
+
+        match i64::from_str_radix(&digits, radix) {
+            Ok(n) => Ok(TokenType::Int(n)),
+            Err(_) => match BigInt::parse_bytes(digits.as_bytes(), radix) {
+                Some(n) => Ok(TokenType::BigInt(n)),
+                None => self.throw("E0004", format!("can't parse number {}", digits)),
+            },
+        }
+    }
+
+    fn number(&mut self) -> Result<TokenType, ParseError> {
+        match (self.current(), self.peek_at(1)) {
+            ('0', 'x' | 'X') => return self.radix_number(16),
+            ('0', 'o' | 'O') => return self.radix_number(8),
+            ('0', 'b' | 'B') => return self.radix_number(2),
+            _ => {}
+        }
+
+        let mut n = String::from(self.current());
+        let mut float = false;
+
+        loop {
+            match self.peek_at(1) {
+                c if c.is_ascii_digit() => n.push(c),
+                '_' => {}
+                '.' if !float && self.peek_at(2).is_ascii_digit() => {
+                    float = true;
+                    n.push('.');
+                }
+                'e' | 'E'
+                    if self.peek_at(2).is_ascii_digit()
+                        || (matches!(self.peek_at(2), '+' | '-')
+                            && self.peek_at(3).is_ascii_digit()) =>
+                {
+                    float = true;
+                    self.next();
+                    n.push('e');
+                    n.push(self.peek_at(1));
+                }
+                _ => break,
+            }
+            self.next();
+        }
+
+        if float {
+            match n.parse::<f64>() {
+                Ok(n) => Ok(TokenType::Num(n)),
+                Err(_) => self.throw("E0004", format!("can't parse number {}", n)),
+            }
+        } else {
+            match n.parse::<i64>() {
+                Ok(n) => Ok(TokenType::Int(n)),
+                Err(_) => match n.parse::<BigInt>() {
+                    Ok(n) => Ok(TokenType::BigInt(n)),
+                    Err(_) => self.throw("E0004", format!("can't parse number {}", n)),
+                },
+            }
+        }
     }
 
     fn peek_at(&self, n: usize) -> char {
@@ -127,12 +561,66 @@ impl Lexer {
     }
 
     fn get(&mut self) -> Tk {
+        self.start = self.idx;
+
         let tk = match self.current() {
+            // doc comments, `////` starts a normal comment
+            '/' if self.peek_at(1) == '/' && self.peek_at(2) == '/' && self.peek_at(3) != '/' => {
+                let line = self.line;
+                self.next();
+                self.next();
+
+                let mut text = String::new();
+                while !matches!(self.peek_at(1), '\n' | EOF) {
+                    text.push(self.next());
+                }
+                self.comment(line, self.idx + 1);
+
+                match text.strip_prefix(' ') {
+                    Some(text) => TokenType::Doc(text.to_string()),
+                    None => TokenType::Doc(text),
+                }
+            }
+
+            // block comments, which can be nested
+            '/' if self.peek_at(1) == '*' => {
+                let line = self.line;
+                let mut depth = 0;
+
+                loop {
+                    match (self.current(), self.peek_at(1)) {
+                        ('/', '*') => {
+                            depth += 1;
+                            self.next();
+                        }
+                        ('*', '/') => {
+                            depth -= 1;
+                            self.next();
+                            if depth == 0 {
+                                self.next();
+                                break;
+                            }
+                        }
+                        (EOF, _) => {
+                            return Err(self
+                                .error("E0002", "unclosed block comment")
+                                .with_label(self.span(self.start, 2), "comment opened here"))
+                        }
+                        _ => {}
+                    }
+                    self.next();
+                }
+                self.comment(line, self.idx);
+                return self.get();
+            }
+
             // comments
             '/' if self.peek_at(1) == '/' => {
+                let line = self.line;
                 while !matches!(self.current(), '\n' | EOF) {
                     self.next();
                 }
+                self.comment(line, self.idx);
                 return self.get();
             }
 
@@ -144,7 +632,21 @@ impl Lexer {
             }
             '-' => TokenType::Sub,
             '/' => TokenType::Div,
+            '*' if self.peek_at(1) == '*' => {
+                self.next();
+                TokenType::Pow
+            }
             '*' => TokenType::Mul,
+            // `//` starts a line comment, so floor division is `~/`
+            '~' if self.peek_at(1) == '/' => {
+                self.next();
+                TokenType::FloorDiv
+            }
+            '~' if self.peek_at(1) == '~' && self.peek_at(2) == '~' => {
+                self.next();
+                self.next();
+                TokenType::BitNot
+            }
             '%' => TokenType::Rem,
             '=' if self.peek_at(1) == '=' => {
                 self.next();
@@ -178,28 +680,25 @@ impl Lexer {
                 let sym = self.take_while(|c| c.is_alphanumeric() || c == '_')?;
 
                 match sym.as_str() {
-                    "\0" => self.throw("expected symbol string after `:`, found <eof>")?,
+                    "\0" => self.throw("E0001", "expected symbol string after `:`, found <eof>")?,
                     _ => TokenType::Sym(Symbol::new(sym)),
                 }
             }
+            ':' => TokenType::Colon,
             '=' => TokenType::Assign,
+            '"' if self.peek_at(1) == '"' && self.peek_at(2) == '"' => self.triple_str()?,
             '"' if self.peek_at(1) == '"' => {
                 self.next();
                 TokenType::Str(String::new())
             }
             '"' => {
                 self.next();
-                let a = TokenType::Str(self.take_str()?);
+                let a = self.take_str(None)?;
                 self.next();
                 a
             }
-            c if c.is_numeric() => {
-                let n = self.take_while(|c| c.is_numeric() || c == '.')?;
-                match n.parse::<f64>() {
-                    Ok(n) => TokenType::Num(n),
-                    Err(_) => self.throw(format!("Can't parse number {}", n))?,
-                }
-            }
+            'r' if self.peek_at(1) == '"' || self.raw_hashes() => self.raw_str()?,
+            c if c.is_ascii_digit() => self.number()?,
             c if c.is_alphabetic() || c == '_' => {
                 let mut tk = self.take_while(|c| c.is_alphanumeric() || c == '_')?;
                 while matches!(self.peek_at(1), '?' | '!' | '\'') {
@@ -225,10 +724,11 @@ impl Lexer {
                 self.next();
                 TokenType::BitOr
             }
+            '|' => TokenType::Bar,
             '>' if self.peek_at(1) == '>' && self.peek_at(2) == '>' => {
                 self.next();
                 self.next();
-                TokenType::Shl
+                TokenType::Shr
             }
 
             '>' if self.peek_at(1) == '>' => {
@@ -239,7 +739,7 @@ impl Lexer {
             '<' if self.peek_at(1) == '<' && self.peek_at(2) == '<' => {
                 self.next();
                 self.next();
-                TokenType::Shr
+                TokenType::Shl
             }
             '^' if self.peek_at(1) == '^' && self.peek_at(2) == '^' => {
                 self.next();
@@ -266,12 +766,17 @@ impl Lexer {
                 return self.get();
             }
 
-            c => self.throw(format!("Unknown start of token `{}`", c))?,
+            c => self.throw("E0001", format!("unknown start of token `{}`", c))?,
         };
 
         Ok(Token {
             line: self.line,
             column: self.column,
+            span: Span::new(
+                self.file,
+                self.offset(self.start),
+                self.offset(self.idx + 1),
+            ),
             token: tk,
         })
     }
@@ -280,6 +785,10 @@ impl Lexer {
 impl Iterator for Lexer {
     type Item = Result<Token, ParseError>;
     fn next(&mut self) -> Option<Self::Item> {
+        if !self.recovered.is_empty() {
+            return Some(Err(self.recovered.remove(0)));
+        }
+
         let x = self.get();
         self.next();
         Some(x)
@@ -2,35 +2,171 @@
 //! Compiler for the yex language
 mod compiler;
 mod error;
+mod formatter;
 mod lexer;
+pub mod lint;
+pub mod outline;
 mod parser;
+mod resolver;
 mod tokens;
+mod typeck;
 
 use compiler::Compiler;
 pub use error::ParseError;
+pub use formatter::format;
 
-use error::ParseResult;
 use lexer::Lexer;
 use parser::Parser;
-use vm::{Bytecode, Value};
+use resolver::Resolver;
+use tokens::TokenType;
+use typeck::TypeChecker;
+use vm::{Bytecode, Diagnostic, EnvTable, Symbol, Value};
 
-/// Parses a given string into an AST
-pub fn parse<T: Into<String>>(str: T) -> ParseResult<(Bytecode, Vec<Value>)> {
-    let lexer = Lexer::new(str);
-    let parser = Parser::new(lexer)?;
-    let ast = parser.parse()?;
+/// The result of compiling a source, which has every syntax error found when it fails
+pub type CompileResult = Result<(Bytecode, Vec<Value>), Vec<ParseError>>;
+
+/// Parses a given string into an AST.
+///
+/// The names and the types the source doesn't define are taken as globals set before it runs,
+/// so they are only reported when it runs. Use [`parse_file_with_globals`] to report them
+pub fn parse<T: Into<String>>(str: T) -> CompileResult {
+    compile(Lexer::new(str), None)
+}
+
+/// Parses the contents of a file, whose path is used in the spans of the bytecode. The names it
+/// doesn't define aren't reported, see [`parse`]
+pub fn parse_file<T: Into<String>>(path: &str, str: T) -> CompileResult {
+    compile(Lexer::new(str).with_file(Symbol::new(path)), None)
+}
+
+/// Parses the contents of a file that uses globals defined outside of it, like the ones set by
+/// an embedder or defined by the previous entries of a REPL. The names and the types that
+/// aren't defined by the file, the globals or the builtins are errors
+pub fn parse_file_with_globals<T: Into<String>>(
+    path: &str,
+    str: T,
+    globals: &EnvTable,
+) -> CompileResult {
+    compile(Lexer::new(str).with_file(Symbol::new(path)), Some(globals))
+}
+
+/// Keeps the errors of the diagnostics, the warnings don't stop a program from compiling
+fn reject_errors(diagnostics: Vec<Diagnostic>) -> Result<(), Vec<ParseError>> {
+    let errors = diagnostics
+        .into_iter()
+        .filter(Diagnostic::is_error)
+        .map(ParseError::from)
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Creates the resolver and the type checker of a source, which take the names it doesn't define
+/// as globals set before it runs when the globals aren't known
+fn checkers(globals: Option<&EnvTable>) -> (Resolver, TypeChecker) {
+    match globals {
+        Some(globals) => (
+            Resolver::new().with_globals(globals),
+            TypeChecker::new().with_globals(globals),
+        ),
+        None => (
+            Resolver::new().allow_external(),
+            TypeChecker::new().allow_external(),
+        ),
+    }
+}
+
+fn compile(lexer: Lexer, globals: Option<&EnvTable>) -> CompileResult {
+    let parser = Parser::new(lexer).map_err(|e| vec![e])?;
+    let (ast, errors) = parser.parse();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let (resolver, checker) = checkers(globals);
+    reject_errors(resolver.resolve(&ast))?;
+    reject_errors(checker.check(&ast))?;
 
     let compiler = Compiler::new();
     Ok(compiler.compile_stmts(&ast))
 }
 
-/// Parses the given string in a single expression
-pub fn parse_expr<T: Into<String>>(str: T) -> ParseResult<(Bytecode, Vec<Value>)> {
-    let lexer = Lexer::new(str);
+/// Parses the given string in a single expression, the names it doesn't define aren't reported,
+/// see [`parse`]
+pub fn parse_expr<T: Into<String>>(str: T) -> CompileResult {
+    compile_expr(Lexer::new(str), None)
+}
+
+/// Parses the given string in a single expression, whose file name is used in the spans of the
+/// bytecode. The names it doesn't define aren't reported, see [`parse`]
+pub fn parse_expr_file<T: Into<String>>(path: &str, str: T) -> CompileResult {
+    compile_expr(Lexer::new(str).with_file(Symbol::new(path)), None)
+}
 
-    let parser = Parser::new(lexer)?;
-    let ast = parser.parse_expr()?;
+/// Parses the given string in a single expression that uses globals defined outside of it, see
+/// [`parse_file_with_globals`]
+pub fn parse_expr_file_with_globals<T: Into<String>>(
+    path: &str,
+    str: T,
+    globals: &EnvTable,
+) -> CompileResult {
+    compile_expr(Lexer::new(str).with_file(Symbol::new(path)), Some(globals))
+}
+
+fn compile_expr(lexer: Lexer, globals: Option<&EnvTable>) -> CompileResult {
+    let parser = Parser::new(lexer).map_err(|e| vec![e])?;
+    let (ast, errors) = parser.parse_expr();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let (resolver, checker) = checkers(globals);
+    reject_errors(resolver.resolve_expr(&ast))?;
+    reject_errors(checker.check_expr(&ast))?;
 
     let compiler = Compiler::new();
     Ok(compiler.compile_expr(&ast))
 }
+
+/// Checks if a source starts with a declaration, a `def`, a `type`, a `trait` or an `impl`,
+/// which is compiled with [`parse_file`], while the rest of the sources are expressions
+pub fn is_declaration(src: &str) -> bool {
+    let first = Lexer::new(src).next().and_then(Result::ok);
+    matches!(
+        first.map(|it| it.token),
+        Some(TokenType::Def | TokenType::Type | TokenType::Trait | TokenType::Impl)
+    )
+}
+
+/// Checks the contents of a file without running it, returning its syntax errors along with
+/// the undefined names, the unused or shadowed locals and the type errors, in the order they
+/// appear in it. The parts of the file with syntax errors are skipped, the rest is still checked
+pub fn check<T: Into<String>>(path: &str, str: T) -> Vec<Diagnostic> {
+    let lexer = Lexer::new(str).with_file(Symbol::new(path));
+    let parser = match Parser::new(lexer) {
+        Ok(parser) => parser,
+        Err(e) => return vec![e.into()],
+    };
+
+    let (ast, errors) = parser.parse();
+    let mut diagnostics = errors
+        .into_iter()
+        .map(Diagnostic::from)
+        .chain(Resolver::new().resolve(&ast))
+        .chain(TypeChecker::new().check(&ast))
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|it| it.span.start);
+    diagnostics
+}
+
+/// Writes a count followed by a noun, in plural unless the count is one, like `2 arguments`
+pub fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
@@ -0,0 +1,591 @@
+//! The lint engine, which finds code that runs but is probably wrong or could be simpler. The
+//! level of every rule can be configured, and a `// yex-lint: allow(rule)` comment silences
+//! rules on the line it trails, or on the next line of code if it's on a line of its own
+use std::collections::{HashMap, HashSet};
+
+use vm::{Diagnostic, Span, Symbol};
+
+use crate::{
+    error::ParseError,
+    lexer::Lexer,
+    parser::{
+        ast::{BinOp, Bind, Def, Expr, ExprKind, Literal, Stmt, StmtKind, VarDecl},
+        Parser,
+    },
+    plural,
+};
+
+/// A lint rule, whose name is also the code of the diagnostics it reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A recursive call in tail position that could reuse the frame with `become`
+    MissedTailCall,
+    /// A comparison with `nil` through `==` or `!=`
+    NilComparison,
+    /// A method that never uses `this`
+    UnusedThis,
+    /// An `if` whose condition always has the same truthiness
+    ConstantCondition,
+    /// A value on the left of `>>` that is discarded without doing anything
+    UnusedValue,
+    /// A call to a function defined with `def` with more arguments than it takes, calls with
+    /// fewer arguments are partial applications
+    ArityMismatch,
+}
+
+impl Rule {
+    /// Every rule
+    pub const ALL: [Rule; 6] = [
+        Rule::MissedTailCall,
+        Rule::NilComparison,
+        Rule::UnusedThis,
+        Rule::ConstantCondition,
+        Rule::UnusedValue,
+        Rule::ArityMismatch,
+    ];
+
+    /// Returns the name of the rule, used in the project file and in the suppressions
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::MissedTailCall => "missed_tail_call",
+            Rule::NilComparison => "nil_comparison",
+            Rule::UnusedThis => "unused_this",
+            Rule::ConstantCondition => "constant_condition",
+            Rule::UnusedValue => "unused_value",
+            Rule::ArityMismatch => "arity_mismatch",
+        }
+    }
+
+    /// Finds a rule by its name
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|it| it.name() == name)
+    }
+
+    /// Returns the level of the rule when the project file doesn't change it, calls with the
+    /// wrong number of arguments always fail, so they are errors
+    pub fn default_level(&self) -> Level {
+        match self {
+            Rule::ArityMismatch => Level::Deny,
+            _ => Level::Warn,
+        }
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What happens when a rule finds something
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// The rule is disabled
+    Allow,
+    /// The rule reports warnings
+    Warn,
+    /// The rule reports errors
+    Deny,
+}
+
+impl Level {
+    /// Finds a level by its name, `allow`, `warn` or `deny`
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// The levels of the rules, read from a project file
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    levels: HashMap<Rule, Level>,
+}
+
+impl Config {
+    /// Parses a project file, where each line sets the level of a rule, like
+    /// `unused_this = allow`, and comments start with `#`
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+
+        for (idx, line) in src.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (rule, level) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `rule = level`", idx + 1))?;
+
+            let rule = Rule::from_name(rule.trim())
+                .ok_or_else(|| format!("line {}: unknown rule `{}`", idx + 1, rule.trim()))?;
+            let level = Level::from_name(level.trim()).ok_or_else(|| {
+                format!(
+                    "line {}: unknown level `{}`, expected `allow`, `warn` or `deny`",
+                    idx + 1,
+                    level.trim()
+                )
+            })?;
+
+            config = config.with_level(rule, level);
+        }
+        Ok(config)
+    }
+
+    /// Sets the level of a rule
+    #[must_use]
+    pub fn with_level(mut self, rule: Rule, level: Level) -> Self {
+        self.levels.insert(rule, level);
+        self
+    }
+
+    /// Returns the level of a rule
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+}
+
+/// Lints the contents of a file, returning the diagnostics of the rules that aren't allowed,
+/// or the syntax errors of the file
+pub fn lint(path: &str, src: &str, config: &Config) -> Result<Vec<Diagnostic>, Vec<ParseError>> {
+    let file = Symbol::new(path);
+    let parser = Parser::new(Lexer::new(src).with_file(file)).map_err(|e| vec![e])?;
+    let (stmts, errors) = parser.parse();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let (tokens, comments) = Lexer::new(src).trivia().map_err(|e| vec![e])?;
+
+    let mut linter = Linter {
+        config,
+        file,
+        src,
+        functions: HashMap::new(),
+        locals: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    linter.program(&stmts);
+
+    // the lines that have code, where the suppressions on their own lines apply
+    let code_lines = tokens
+        .iter()
+        .map(|(token, _)| position(src, token.span.start).0)
+        .collect::<Vec<_>>();
+
+    let mut allowed = HashSet::new();
+    for comment in &comments {
+        let rules = match suppression(&comment.text) {
+            Some(rules) => rules,
+            None => continue,
+        };
+
+        let line = if comment.own_line {
+            match code_lines.iter().find(|it| **it > comment.line) {
+                Some(line) => *line,
+                None => continue,
+            }
+        } else {
+            comment.line
+        };
+
+        for name in rules {
+            match Rule::from_name(name) {
+                Some(rule) => {
+                    allowed.insert((rule.name(), line));
+                }
+                None => linter.unknown_rule(name, comment.line),
+            }
+        }
+    }
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.retain(|it| !allowed.contains(&(it.code, it.line)));
+    diagnostics.sort_by_key(|it| it.span.start);
+    Ok(diagnostics)
+}
+
+/// Returns the names of the rules allowed by a `// yex-lint: allow(...)` comment
+fn suppression(comment: &str) -> Option<Vec<&str>> {
+    let rest = comment.strip_prefix("//")?.trim_start();
+    let rest = rest.strip_prefix("yex-lint:")?.trim_start();
+    let rules = rest.strip_prefix("allow(")?.split(')').next()?;
+    Some(rules.split(',').map(str::trim).collect())
+}
+
+/// Returns the line and the column of a byte offset of the source
+fn position(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+/// Returns the truthiness of an expression, if it's always the same
+fn truthiness(expr: &Expr) -> Option<bool> {
+    match &expr.kind {
+        ExprKind::Lit(lit) => Some(match lit {
+            Literal::Bool(b) => *b,
+            Literal::Int(n) => *n != 0,
+            Literal::Num(n) => *n != 0.0,
+            Literal::Str(s) => !s.is_empty(),
+            Literal::BigInt(_) | Literal::Sym(_) => true,
+            Literal::Unit => false,
+        }),
+        ExprKind::List(xs) => Some(!xs.is_empty()),
+        ExprKind::Lambda { .. } => Some(true),
+        _ => None,
+    }
+}
+
+/// Checks if evaluating an expression can't do anything besides producing its value
+fn is_pure(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Lambda { .. } => true,
+        ExprKind::List(xs) => xs.iter().all(is_pure),
+        ExprKind::Binary { left, right, .. }
+        | ExprKind::Cons {
+            head: left,
+            tail: right,
+        } => is_pure(left) && is_pure(right),
+        ExprKind::UnOp(_, right) => is_pure(right),
+        _ => false,
+    }
+}
+
+/// Checks if an expression uses `this`, without looking into the functions declared inside
+/// of it, which can't see it
+fn uses_this(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Var(name) => name.as_str() == "this",
+        ExprKind::Super { .. } => true,
+        ExprKind::Lambda { .. } => false,
+        _ => {
+            let mut found = false;
+            children(expr, &mut |it| found |= uses_this(it));
+            found
+        }
+    }
+}
+
+/// Calls `f` on the direct children of an expression
+fn children<'a>(expr: &'a Expr, f: &mut impl FnMut(&'a Expr)) {
+    match &expr.kind {
+        ExprKind::If { cond, then, else_ } => {
+            f(cond);
+            f(then);
+            f(else_);
+        }
+        ExprKind::Let { binds, body } => {
+            binds.iter().for_each(|it| f(&it.value));
+            f(body);
+        }
+        ExprKind::Lambda { body, .. } => f(body),
+        ExprKind::App { callee: obj, args }
+        | ExprKind::New { ty: obj, args }
+        | ExprKind::Invoke { obj, args, .. } => {
+            f(obj);
+            args.iter().for_each(f);
+        }
+        ExprKind::Field { obj, .. } => f(obj),
+        ExprKind::List(xs) | ExprKind::Interpolation(xs) | ExprKind::Super { args: xs, .. } => {
+            xs.iter().for_each(f)
+        }
+        ExprKind::Binary { left, right, .. }
+        | ExprKind::Cons {
+            head: left,
+            tail: right,
+        }
+        | ExprKind::Seq { left, right } => {
+            f(left);
+            f(right);
+        }
+        ExprKind::UnOp(_, right) | ExprKind::Become(right) => f(right),
+        ExprKind::Var(_) | ExprKind::Lit(_) | ExprKind::Error => {}
+    }
+}
+
+struct Linter<'a> {
+    config: &'a Config,
+    file: Symbol,
+    src: &'a str,
+    /// The arity of the functions defined with `def`, and the span of their names
+    functions: HashMap<Symbol, (usize, Span)>,
+    /// The locals of the function being linted, which hide the functions with the same name
+    locals: Vec<Symbol>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn program(&mut self, stmts: &[Stmt]) {
+        // names defined more than once can't be known statically
+        let mut defined = HashSet::new();
+        let mut redefined = HashSet::new();
+        for stmt in stmts {
+            let names = match &stmt.kind {
+                StmtKind::Def(def) => vec![def.bind.name],
+                StmtKind::Type { name, variants, .. } => std::iter::once(name)
+                    .chain(variants.iter().map(|it| &it.name))
+                    .map(|it| it.name)
+                    .collect(),
+                StmtKind::Trait { name, .. } => vec![name.name],
+                StmtKind::Impl { .. } | StmtKind::Expr(_) => continue,
+            };
+            for name in names {
+                if !defined.insert(name) {
+                    redefined.insert(name);
+                }
+            }
+
+            if let StmtKind::Def(Def {
+                bind,
+                value:
+                    Expr {
+                        kind: ExprKind::Lambda { args, .. },
+                        ..
+                    },
+                ..
+            }) = &stmt.kind
+            {
+                self.functions.insert(bind.name, (args.len(), bind.span));
+            }
+        }
+        self.functions.retain(|name, _| !redefined.contains(name));
+
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Def(def) => self.def(def),
+                StmtKind::Type {
+                    name,
+                    parent,
+                    methods,
+                    init,
+                    ..
+                } => {
+                    for method in methods {
+                        // the methods of a type that extends another can override its methods,
+                        // which receive `this` even if they don't need it
+                        if parent.is_some() {
+                            self.expr(&method.value);
+                        } else {
+                            self.method(name, method);
+                        }
+                    }
+                    if let Some(init) = init {
+                        self.expr(&init.value);
+                    }
+                }
+                StmtKind::Trait { methods, .. } => {
+                    for body in methods.iter().filter_map(|it| it.body.as_ref()) {
+                        self.expr(body);
+                    }
+                }
+                // the methods of a trait receive `this` even if they don't need it
+                StmtKind::Impl { methods, .. } => {
+                    for method in methods {
+                        self.expr(&method.value);
+                    }
+                }
+                StmtKind::Expr(expr) => self.expr(expr),
+            }
+        }
+    }
+
+    fn def(&mut self, def: &Def) {
+        if let ExprKind::Lambda { args, body, .. } = &def.value.kind {
+            if !args.iter().any(|it| it.name == def.bind.name) {
+                self.tail_calls(def.bind.name, args.len(), body);
+            }
+        }
+        self.expr(&def.value);
+    }
+
+    fn method(&mut self, ty: &VarDecl, method: &Def) {
+        if let ExprKind::Lambda { body, .. } = &method.value.kind {
+            if !uses_this(body) {
+                let message = format!("method `{}` never uses `this`", method.bind.name);
+                let diagnostic = self
+                    .diagnostic(Rule::UnusedThis, method.bind.span, message)
+                    .with_help(format!(
+                        "define it with `def` outside of `{}`, since it doesn't need an instance",
+                        ty.name
+                    ));
+                self.report(Rule::UnusedThis, diagnostic);
+            }
+        }
+        self.expr(&method.value);
+    }
+
+    /// Reports the recursive calls to `name` in the tail positions of `body`
+    fn tail_calls(&mut self, name: Symbol, arity: usize, body: &Expr) {
+        match &body.kind {
+            ExprKind::If { then, else_, .. } => {
+                self.tail_calls(name, arity, then);
+                self.tail_calls(name, arity, else_);
+            }
+            ExprKind::Let { binds, body } if !binds.iter().any(|it| it.bind.name == name) => {
+                self.tail_calls(name, arity, body);
+            }
+            ExprKind::Seq { right, .. } => self.tail_calls(name, arity, right),
+            ExprKind::App { callee, args } if args.len() == arity => match callee.kind {
+                ExprKind::Var(callee) if callee == name => {
+                    let message = format!("recursive call to `{}` in tail position", name);
+                    let mut diagnostic =
+                        self.diagnostic(Rule::MissedTailCall, body.span(), message);
+                    if let Some(call) = body.span().text(self.src) {
+                        diagnostic = diagnostic.with_help(format!(
+                            "write it as `become {}` to reuse the frame of the function",
+                            call
+                        ));
+                    }
+                    self.report(Rule::MissedTailCall, diagnostic);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Lambda { args, body, .. } => {
+                // functions only see their own locals
+                let outer =
+                    std::mem::replace(&mut self.locals, args.iter().map(|it| it.name).collect());
+                self.expr(body);
+                self.locals = outer;
+                return;
+            }
+            ExprKind::Let { binds, body } => {
+                let len = self.locals.len();
+                for Bind { bind, value, .. } in binds {
+                    self.expr(value);
+                    self.locals.push(bind.name);
+                }
+                self.expr(body);
+                self.locals.truncate(len);
+                return;
+            }
+            ExprKind::If { cond, .. } => {
+                if let Some(truthy) = truthiness(cond) {
+                    let (value, branch) = match truthy {
+                        true => ("true", "then"),
+                        false => ("false", "else"),
+                    };
+                    let message = format!("this condition is always {}", value);
+                    let diagnostic = self
+                        .diagnostic(Rule::ConstantCondition, cond.span(), message)
+                        .with_help(format!("remove the `if` and keep its `{}` branch", branch));
+                    self.report(Rule::ConstantCondition, diagnostic);
+                }
+            }
+            ExprKind::Binary {
+                left,
+                op: op @ (BinOp::Eq | BinOp::Ne),
+                right,
+            } => {
+                let other = match (&left.kind, &right.kind) {
+                    (ExprKind::Lit(Literal::Unit), _) => Some(right),
+                    (_, ExprKind::Lit(Literal::Unit)) => Some(left),
+                    _ => None,
+                };
+
+                if let Some(other) = other {
+                    let op = if *op == BinOp::Eq { "==" } else { "!=" };
+                    let message = format!("comparison with `nil` using `{}`", op);
+                    let mut diagnostic = self.diagnostic(Rule::NilComparison, expr.span(), message);
+                    if let Some(other) = other.span().text(self.src) {
+                        diagnostic = diagnostic.with_help(format!(
+                            "check its type instead: `typeof({}) {} Nil`",
+                            other, op
+                        ));
+                    }
+                    self.report(Rule::NilComparison, diagnostic);
+                }
+            }
+            ExprKind::Seq { left, .. } if is_pure(left) => {
+                let diagnostic = self
+                    .diagnostic(Rule::UnusedValue, left.span(), "this value is discarded")
+                    .with_help("`>>` only keeps the value on its right, remove the left side");
+                self.report(Rule::UnusedValue, diagnostic);
+            }
+            ExprKind::App { callee, args } => {
+                if let ExprKind::Var(name) = &callee.kind {
+                    self.arity(*name, args.len(), expr);
+                }
+            }
+            _ => {}
+        }
+
+        children(expr, &mut |it| self.expr(it));
+    }
+
+    /// Reports a call with too many arguments to a known function
+    fn arity(&mut self, name: Symbol, given: usize, call: &Expr) {
+        if self.locals.contains(&name) {
+            return;
+        }
+
+        if let Some(&(arity, span)) = self.functions.get(&name) {
+            if given > arity {
+                let message = format!(
+                    "`{}` takes {} but {} {} given",
+                    name,
+                    plural(arity, "argument"),
+                    given,
+                    if given == 1 { "was" } else { "were" }
+                );
+                let diagnostic = self
+                    .diagnostic(Rule::ArityMismatch, call.span(), message)
+                    .with_label(span, "defined here");
+                self.report(Rule::ArityMismatch, diagnostic);
+            }
+        }
+    }
+
+    /// Creates the diagnostic of a rule, as an error
+    fn diagnostic(&self, rule: Rule, span: Span, message: impl Into<String>) -> Diagnostic {
+        let (line, column) = position(self.src, span.start);
+        Diagnostic::new(rule.name(), message, span, line, column)
+    }
+
+    /// Reports the diagnostic of a rule with the level set in the configuration
+    fn report(&mut self, rule: Rule, diagnostic: Diagnostic) {
+        match self.config.level(rule) {
+            Level::Allow => {}
+            Level::Warn => self.diagnostics.push(diagnostic.warning()),
+            Level::Deny => self.diagnostics.push(diagnostic),
+        }
+    }
+
+    /// Reports a suppression of a rule that doesn't exist
+    fn unknown_rule(&mut self, name: &str, line: usize) {
+        let offset = self
+            .src
+            .split_inclusive('\n')
+            .take(line - 1)
+            .map(str::len)
+            .sum::<usize>();
+        let span = Span::new(self.file, offset, offset);
+
+        let rules = Rule::ALL.map(|it| format!("`{}`", it)).join(", ");
+        let diagnostic = Diagnostic::new(
+            "unknown_rule",
+            format!("unknown lint rule `{}`", name),
+            span,
+            line,
+            1,
+        )
+        .warning()
+        .with_help(format!("the rules are {}", rules));
+        self.diagnostics.push(diagnostic);
+    }
+}
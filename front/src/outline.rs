@@ -0,0 +1,175 @@
+//! A summary of the top-level definitions of a source file, used to generate documentation
+use crate::{
+    error::ParseError,
+    lexer::Lexer,
+    parser::{
+        ast::{Def, Expr, ExprKind, StmtKind, VarDecl, Variant},
+        Parser,
+    },
+};
+
+/// A function or value defined with `def`, or a method of a type
+#[derive(Debug, Clone)]
+pub struct DefItem {
+    /// The name of the definition
+    pub name: String,
+    /// The parameter names with their type annotations, `None` if the definition isn't a
+    /// function
+    pub params: Option<Vec<String>>,
+    /// The annotated return type of the function
+    pub ret: Option<String>,
+    /// The text of the `///` doc comment
+    pub doc: Option<String>,
+    /// The line where the definition starts
+    pub line: usize,
+}
+
+/// A type declared with `type`
+#[derive(Debug, Clone)]
+pub struct TypeItem {
+    /// The name of the type
+    pub name: String,
+    /// The parameters that the type needs to be instantiated, with their type annotations
+    pub params: Vec<String>,
+    /// The name of the type that this one extends
+    pub parent: Option<String>,
+    /// The variants of the type, empty if it has a single shape
+    pub variants: Vec<VariantItem>,
+    /// The methods of the type, without the `init` method
+    pub methods: Vec<DefItem>,
+    /// The `init` method, if the type has one
+    pub init: Option<DefItem>,
+    /// The text of the `///` doc comment
+    pub doc: Option<String>,
+    /// The line where the declaration starts
+    pub line: usize,
+}
+
+/// A variant of a type
+#[derive(Debug, Clone)]
+pub struct VariantItem {
+    /// The name of the variant
+    pub name: String,
+    /// The fields of the variant, with their type annotations
+    pub params: Vec<String>,
+}
+
+/// A trait declared with `trait`
+#[derive(Debug, Clone)]
+pub struct TraitItem {
+    /// The name of the trait
+    pub name: String,
+    /// The methods of the trait, the ones with a default implementation included
+    pub methods: Vec<DefItem>,
+    /// The text of the `///` doc comment
+    pub doc: Option<String>,
+    /// The line where the declaration starts
+    pub line: usize,
+}
+
+/// A top-level item of a source file
+#[derive(Debug, Clone)]
+pub enum Item {
+    /// A `def`
+    Def(DefItem),
+    /// A `type`
+    Type(TypeItem),
+    /// A `trait`
+    Trait(TraitItem),
+}
+
+fn names(decls: &[VarDecl]) -> Vec<String> {
+    decls
+        .iter()
+        .map(|it| match it.ty {
+            Some(ty) => format!("{}: {}", it.name, ty.name),
+            None => it.name.to_string(),
+        })
+        .collect()
+}
+
+fn params(value: &Expr) -> Option<Vec<String>> {
+    match &value.kind {
+        ExprKind::Lambda { args, .. } => Some(names(args)),
+        _ => None,
+    }
+}
+
+fn def_item(def: &Def, line: usize) -> DefItem {
+    DefItem {
+        name: def.bind.name.to_string(),
+        params: params(&def.value),
+        ret: match &def.value.kind {
+            ExprKind::Lambda { ret, .. } => ret.map(|it| it.name.to_string()),
+            _ => None,
+        },
+        doc: def.doc.clone(),
+        line,
+    }
+}
+
+/// Parses a source file, returning its top-level `def`s and `type`s in order. The `def _`
+/// entry points aren't part of it, a file can have many of them and they can't be referenced
+pub fn outline<T: Into<String>>(str: T) -> Result<Vec<Item>, Vec<ParseError>> {
+    let parser = Parser::new(Lexer::new(str)).map_err(|e| vec![e])?;
+    let (stmts, errors) = parser.parse();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let items = stmts
+        .into_iter()
+        .filter_map(|stmt| {
+            let line = stmt.location.line;
+            match stmt.kind {
+                StmtKind::Def(def) if def.bind.name.as_str() == "_" => None,
+                StmtKind::Def(def) => Some(Item::Def(def_item(&def, line))),
+                StmtKind::Type {
+                    name,
+                    params,
+                    parent,
+                    variants,
+                    methods,
+                    init,
+                    doc,
+                } => Some(Item::Type(TypeItem {
+                    name: name.name.to_string(),
+                    params: names(&params),
+                    parent: parent.map(|it| it.name.to_string()),
+                    variants: variants
+                        .iter()
+                        .map(|Variant { name, params }| VariantItem {
+                            name: name.name.to_string(),
+                            params: names(params),
+                        })
+                        .collect(),
+                    methods: methods
+                        .iter()
+                        .map(|it| def_item(it, it.value.location.line))
+                        .collect(),
+                    init: init.as_ref().map(|it| def_item(it, it.value.location.line)),
+                    doc,
+                    line,
+                })),
+                StmtKind::Trait { name, methods, doc } => Some(Item::Trait(TraitItem {
+                    name: name.name.to_string(),
+                    methods: methods
+                        .iter()
+                        .map(|it| DefItem {
+                            name: it.bind.name.to_string(),
+                            params: Some(names(&it.args)),
+                            ret: it.ret.map(|it| it.name.to_string()),
+                            doc: it.doc.clone(),
+                            line: it.location.line,
+                        })
+                        .collect(),
+                    doc,
+                    line,
+                })),
+                StmtKind::Impl { .. } | StmtKind::Expr(_) => None,
+            }
+        })
+        .collect();
+
+    Ok(items)
+}
@@ -1,21 +1,46 @@
-use vm::{gc::GcRef, OpCode, Symbol, Value};
+use vm::{gc::GcRef, OpCode, Span, Symbol, Value};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Location {
     pub line: usize,
     pub column: usize,
+    /// The bytes of the source that the whole node covers
+    pub span: Span,
 }
 
 use crate::tokens::TokenType;
 
+/// The type written after a parameter, `x: Num`, or after the parameters of a function,
+/// `-> Str`
+#[derive(Debug, Clone, Copy)]
+pub struct TypeAnnotation {
+    pub name: Symbol,
+    /// The bytes of the source that the name of the type covers
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct VarDecl {
     pub name: Symbol,
+    /// The bytes of the source that the name covers
+    pub span: Span,
+    /// The annotated type, only parameters can have one
+    pub ty: Option<TypeAnnotation>,
 }
 
 impl VarDecl {
-    pub fn new(name: Symbol) -> Self {
-        Self { name }
+    pub fn new(name: Symbol, span: Span) -> Self {
+        Self {
+            name,
+            span,
+            ty: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_type(mut self, ty: TypeAnnotation) -> Self {
+        self.ty = Some(ty);
+        self
     }
 }
 
@@ -29,6 +54,9 @@ pub enum BinOp {
     Sub,
     Mul,
     Div,
+    Rem,
+    Pow,
+    FloorDiv,
     BitAnd,
     BitOr,
     BitXor,
@@ -51,6 +79,9 @@ impl<'a> From<BinOp> for &'a [OpCode] {
             BinOp::Sub => &[OpCode::Sub],
             BinOp::Mul => &[OpCode::Mul],
             BinOp::Div => &[OpCode::Div],
+            BinOp::Rem => &[OpCode::Rem],
+            BinOp::Pow => &[OpCode::Pow],
+            BinOp::FloorDiv => &[OpCode::FloorDiv],
             BinOp::BitAnd => &[OpCode::BitAnd],
             BinOp::BitOr => &[OpCode::BitOr],
             BinOp::BitXor => &[OpCode::Xor],
@@ -77,6 +108,9 @@ impl TryFrom<TokenType> for BinOp {
             TokenType::Sub => Ok(BinOp::Sub),
             TokenType::Mul => Ok(BinOp::Mul),
             TokenType::Div => Ok(BinOp::Div),
+            TokenType::Rem => Ok(BinOp::Rem),
+            TokenType::Pow => Ok(BinOp::Pow),
+            TokenType::FloorDiv => Ok(BinOp::FloorDiv),
             TokenType::BitAnd => Ok(BinOp::BitAnd),
             TokenType::BitOr => Ok(BinOp::BitOr),
             TokenType::BitXor => Ok(BinOp::BitXor),
@@ -95,6 +129,7 @@ impl TryFrom<TokenType> for BinOp {
 pub enum UnOp {
     Not,
     Neg,
+    BitNot,
 }
 
 impl TryFrom<TokenType> for UnOp {
@@ -104,6 +139,7 @@ impl TryFrom<TokenType> for UnOp {
         match t {
             TokenType::Not => Ok(UnOp::Not),
             TokenType::Sub => Ok(UnOp::Neg),
+            TokenType::BitNot => Ok(UnOp::BitNot),
             _ => Err(()),
         }
     }
@@ -114,6 +150,7 @@ impl<'a> From<UnOp> for &'a [OpCode] {
         match op {
             UnOp::Not => &[OpCode::Not],
             UnOp::Neg => &[OpCode::Neg],
+            UnOp::BitNot => &[OpCode::BitNot],
         }
     }
 }
@@ -126,11 +163,11 @@ pub struct Bind {
 }
 
 impl Bind {
-    pub fn new(bind: VarDecl, value: Box<Expr>, line: usize, column: usize) -> Self {
+    pub fn new(bind: VarDecl, value: Box<Expr>, line: usize, column: usize, span: Span) -> Self {
         Self {
             bind,
             value,
-            location: Location { line, column },
+            location: Location { line, column, span },
         }
     }
 }
@@ -147,8 +184,9 @@ pub enum ExprKind {
         body: Box<Expr>,
     },
     Lambda {
-        args: Vec<VarDecl>, // specifies the arguments name and types
-        body: Box<Expr>,    // the function body
+        args: Vec<VarDecl>,          // specifies the arguments name and types
+        ret: Option<TypeAnnotation>, // the annotated return type
+        body: Box<Expr>,             // the function body
     },
     App {
         callee: Box<Expr>,
@@ -163,6 +201,8 @@ pub enum ExprKind {
     Var(Symbol),
     Lit(Literal),
     List(Vec<Expr>),
+    /// An interpolated string, the parts are concatenated after being converted with `str`
+    Interpolation(Vec<Expr>),
 
     Binary {
         left: Box<Expr>,
@@ -182,6 +222,14 @@ pub enum ExprKind {
         field: VarDecl,
         args: Vec<Expr>,
     },
+    /// A call to a method of the type that the type of the method extends,
+    /// `super.field(args)`, the receiver is `this`
+    Super {
+        /// The type extended by the type of the method, where the lookup starts
+        parent: Symbol,
+        field: VarDecl,
+        args: Vec<Expr>,
+    },
 
     UnOp(UnOp, Box<Expr>),
 
@@ -189,10 +237,16 @@ pub enum ExprKind {
         ty: Box<Expr>,
         args: Vec<Expr>,
     }, // types are just values, so I can't really do compile time checking
+    /// A tail call, `become f(args)`, the call is always an [`ExprKind::App`]
+    Become(Box<Expr>),
+    /// A piece of code that failed to parse, kept so the rest of the tree can still be analyzed
+    Error,
 }
 
 #[derive(Debug, Clone)]
 pub enum Literal {
+    Int(i64),
+    BigInt(vm::BigInt),
     Num(f64),
     Str(String),
     Bool(bool),
@@ -203,6 +257,8 @@ pub enum Literal {
 impl PartialEq<Value> for Literal {
     fn eq(&self, other: &Value) -> bool {
         match (self, other) {
+            (Literal::Int(a), Value::Int(b)) => a == b,
+            (Literal::BigInt(a), Value::BigInt(b)) => a == &**b,
             (Literal::Num(a), Value::Num(b)) => a == b,
             (Literal::Str(a), Value::Str(b)) => a == &**b,
             (Literal::Bool(a), Value::Bool(b)) => a == b,
@@ -216,6 +272,8 @@ impl PartialEq<Value> for Literal {
 impl From<Literal> for Value {
     fn from(lit: Literal) -> Value {
         match lit {
+            Literal::Int(n) => Value::Int(n),
+            Literal::BigInt(n) => Value::BigInt(GcRef::new(n)),
             Literal::Num(n) => Value::Num(n),
             Literal::Str(s) => Value::Str(GcRef::new(s)),
             Literal::Bool(b) => Value::Bool(b),
@@ -232,10 +290,10 @@ pub struct Expr {
 }
 
 impl Expr {
-    pub fn new(kind: ExprKind, line: usize, column: usize) -> Self {
+    pub fn new(kind: ExprKind, line: usize, column: usize, span: Span) -> Self {
         Expr {
             kind,
-            location: Location { line, column },
+            location: Location { line, column, span },
         }
     }
 
@@ -246,13 +304,17 @@ impl Expr {
     pub fn column(&self) -> usize {
         self.location.column
     }
+
+    pub fn span(&self) -> Span {
+        self.location.span
+    }
 }
 
 impl Default for Expr {
     fn default() -> Self {
         Expr {
             kind: ExprKind::Lit(Literal::Unit),
-            location: Location { line: 0, column: 0 },
+            location: Location::default(),
         }
     }
 }
@@ -264,10 +326,10 @@ pub struct Stmt {
 }
 
 impl Stmt {
-    pub fn new(kind: StmtKind, line: usize, column: usize) -> Self {
+    pub fn new(kind: StmtKind, line: usize, column: usize, span: Span) -> Self {
         Stmt {
             kind,
-            location: Location { line, column },
+            location: Location { line, column, span },
         }
     }
 }
@@ -283,6 +345,27 @@ pub struct Def {
     pub value: Expr,
     pub bind: VarDecl,
     pub bind_type: BindType,
+    /// The `///` doc comment written before the definition
+    pub doc: Option<String>,
+}
+
+/// A variant of a type, `Circle(r)` in `type Shape = Circle(r) | Rect(w, h) end`
+#[derive(Debug)]
+pub struct Variant {
+    pub name: VarDecl,
+    pub params: Vec<VarDecl>,
+}
+
+/// A method of a trait, `def show(this)`, the body is its default implementation
+#[derive(Debug)]
+pub struct TraitMethod {
+    pub bind: VarDecl,
+    pub args: Vec<VarDecl>,
+    pub ret: Option<TypeAnnotation>,
+    pub body: Option<Expr>,
+    /// The `///` doc comment written before the method
+    pub doc: Option<String>,
+    pub location: Location,
 }
 
 #[derive(Debug)]
@@ -290,9 +373,25 @@ pub enum StmtKind {
     Def(Def),
     Type {
         name: VarDecl,
+        /// The parameters of a single shape type, empty if the type has variants
         params: Vec<VarDecl>,
+        /// The type that this one extends, `User` in `type Admin(name) extends User`
+        parent: Option<VarDecl>,
+        variants: Vec<Variant>,
+        methods: Vec<Def>,
+        init: Option<Box<Def>>,
+        doc: Option<String>,
+    },
+    Trait {
+        name: VarDecl,
+        methods: Vec<TraitMethod>,
+        doc: Option<String>,
+    },
+    /// An `impl` block, which adds the methods of a trait to a type
+    Impl {
+        trait_: VarDecl,
+        ty: VarDecl,
         methods: Vec<Def>,
-        init: Option<Def>,
     },
     Expr(Expr),
 }
@@ -1,83 +1,316 @@
 use std::{iter::Peekable, mem::take};
 
+use vm::{Span, Symbol};
+
 use crate::{
     error::{ParseError, ParseResult},
     lexer::Lexer,
-    tokens::{Token, TokenType as Tkt},
+    tokens::{StrPart, Token, TokenType as Tkt},
 };
 
-use self::ast::{Bind, BindType, Def, Expr, ExprKind, Literal, Location, Stmt, StmtKind, VarDecl};
+use self::ast::{
+    Bind, BindType, Def, Expr, ExprKind, Literal, Location, Stmt, StmtKind, TraitMethod,
+    TypeAnnotation, VarDecl, Variant,
+};
 
 pub mod ast;
 
 pub struct Parser {
     lexer: Peekable<Lexer>,
+    /// The file being parsed, used in the spans of the nodes
+    file: Symbol,
     current: Token,
+    /// The byte offset right after the last token consumed
+    end: usize,
+    /// The doc comments written right before the current token
+    docs: Vec<String>,
+    /// The doc comments skipped while peeking, they belong to the token after the current one
+    peeked_docs: Vec<String>,
+    /// The errors the parser recovered from
+    errors: Vec<ParseError>,
+    /// The type extended by the type whose methods are being parsed, which `super` calls
+    parent: Option<Symbol>,
+    /// The error of a delimiter that the lexer found unclosed, like a string, which runs to the
+    /// end of the source
+    unclosed: Option<ParseError>,
 }
 
 impl Parser {
     pub fn new(lexer: Lexer) -> ParseResult<Self> {
         let mut this = Parser {
+            file: lexer.file(),
             lexer: lexer.peekable(),
             current: Token::default(),
+            end: 0,
+            docs: Vec::new(),
+            peeked_docs: Vec::new(),
+            errors: Vec::new(),
+            parent: None,
+            unclosed: None,
         };
         this.next()?;
         Ok(this)
     }
 
-    pub fn parse(mut self) -> ParseResult<Vec<Stmt>> {
+    /// Parses the whole source, recovering from the syntax errors to find all of them. The
+    /// statements that failed to parse are kept as [`ExprKind::Error`] nodes
+    pub fn parse(mut self) -> (Vec<Stmt>, Vec<ParseError>) {
         let mut stmts = Vec::new();
         while self.current.token != Tkt::Eof {
-            match self.current.token {
-                Tkt::Type => {
-                    stmts.push(self.type_bind()?);
+            let start = self.current.span.start;
+            let stmt = match self.current.token {
+                Tkt::Type => self.type_bind(),
+                Tkt::Trait => self.trait_bind(),
+                Tkt::Impl => self.impl_bind(),
+                Tkt::Def => self.def_bind(),
+                _ => self.expr().map(Stmt::from),
+            };
+
+            match stmt {
+                Ok(stmt) => stmts.push(stmt),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize(&[Tkt::Def, Tkt::Type, Tkt::Trait, Tkt::Impl]);
+                    stmts.push(self.error_node(start).into());
                 }
-                Tkt::Def => stmts.push(self.def_bind()?),
-                _ => stmts.push(self.expr()?.into()),
             }
         }
-        Ok(stmts)
+        (stmts, self.finish())
+    }
+
+    /// Parses a single expression, which is an [`ExprKind::Error`] node if it failed to parse
+    pub fn parse_expr(mut self) -> (Expr, Vec<ParseError>) {
+        let start = self.current.span.start;
+        let expr = match self.expr() {
+            Ok(expr) => expr,
+            Err(e) => {
+                self.errors.push(e);
+                self.error_node(start)
+            }
+        };
+        (expr, self.finish())
     }
 
-    pub fn parse_expr(mut self) -> ParseResult<Expr> {
-        self.expr()
+    /// Returns the errors found, in the order they appear in the source
+    fn finish(mut self) -> Vec<ParseError> {
+        // the rest of the source is in the unclosed delimiter, so the errors at its end are
+        // caused by it
+        if let Some(unclosed) = self.unclosed.take() {
+            let end = unclosed.diagnostic().span.start;
+            self.errors.retain(|it| it.diagnostic().span.start < end);
+            self.errors.push(unclosed);
+        }
+        self.errors.sort_by_key(|it| it.diagnostic().span.start);
+        self.errors
+    }
+
+    /// Creates a node for the code that failed to parse, from `start` to the last token
+    /// consumed
+    fn error_node(&self, start: usize) -> Expr {
+        let Token { line, column, .. } = self.current;
+        Expr::new(
+            ExprKind::Error,
+            line,
+            column,
+            Span::new(self.file, start, self.end.max(start)),
+        )
+    }
+
+    /// Skips tokens until one of `stops` is found outside of any delimiters, always skipping
+    /// at least the current token if it isn't one of them. Unbalanced closing delimiters are
+    /// skipped unless they are in `stops`
+    fn synchronize(&mut self, stops: &[Tkt]) {
+        let mut depth = 0usize;
+
+        while self.current.token != Tkt::Eof {
+            match self.current.token {
+                ref tk if depth == 0 && stops.contains(tk) => return,
+                Tkt::Lparen | Tkt::Lbrack | Tkt::Lbrace => depth += 1,
+                Tkt::Rparen | Tkt::Rbrack | Tkt::Rbrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            // `next` can't fail, the errors of the lexer are recorded instead
+            let _ = self.next();
+        }
     }
 
     fn type_bind(&mut self) -> ParseResult<Stmt> {
+        let doc = self.take_doc();
+        let start = self.current.span.start;
         self.expect(Tkt::Type)?;
         let line = self.current.line;
         let column = self.current.column;
 
         let name = self.var_decl()?;
-        let mut params = Vec::new();
+        let (params, variants) = if self.current.token == Tkt::Assign {
+            self.next()?;
+            (Vec::new(), self.variants()?)
+        } else {
+            (self.fields()?, Vec::new())
+        };
 
-        self.expect(Tkt::Lparen)?;
-        while self.current.token != Tkt::Rparen {
-            params.push(self.var_decl()?);
+        // only the types with fields can extend another
+        let parent = if variants.is_empty() && self.current.token == Tkt::Extends {
+            self.next()?;
+            Some(self.var_decl()?)
+        } else {
+            None
+        };
 
-            if self.current.token == Tkt::Rparen {
-                break;
+        self.parent = parent.as_ref().map(|it| it.name);
+        let methods = self.methods(start, "type");
+        self.parent = None;
+
+        // `init` is kept apart from the other methods, the last one is used if there are many
+        let (init, methods): (Vec<_>, Vec<_>) = methods?
+            .into_iter()
+            .partition(|it| it.bind.name.as_str() == "init");
+        let init = init.into_iter().last().map(Box::new);
+
+        Ok(Stmt::new(
+            StmtKind::Type {
+                name,
+                params,
+                parent,
+                variants,
+                methods,
+                init,
+                doc,
+            },
+            line,
+            column,
+            self.span(start),
+        ))
+    }
+
+    fn trait_bind(&mut self) -> ParseResult<Stmt> {
+        let doc = self.take_doc();
+        let start = self.current.span.start;
+        self.expect(Tkt::Trait)?;
+        let line = self.current.line;
+        let column = self.current.column;
+
+        let name = self.var_decl()?;
+        self.expect(Tkt::Assign)?;
+
+        let mut methods = Vec::new();
+        while !matches!(self.current.token, Tkt::End | Tkt::Eof) {
+            match self.trait_method() {
+                Ok(method) => methods.push(method),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize(&[Tkt::Def, Tkt::End]);
+                }
             }
+        }
+        self.close(start, "trait")?;
 
-            self.expect_and_skip(Tkt::Comma)?;
+        Ok(Stmt::new(
+            StmtKind::Trait { name, methods, doc },
+            line,
+            column,
+            self.span(start),
+        ))
+    }
+
+    /// Parses a method of a trait, whose body is its default implementation and can be left
+    /// out, `def show(this)`
+    fn trait_method(&mut self) -> ParseResult<TraitMethod> {
+        let doc = self.take_doc();
+        let start = self.current.span.start;
+        self.expect(Tkt::Def)?;
+        let line = self.current.line;
+        let column = self.current.column;
+
+        let bind = self.var_decl()?;
+        let args = self.args()?;
+        let ret = self.return_type()?;
+
+        let body = if self.current.token == Tkt::Assign {
+            self.next()?;
+            Some(self.expr()?)
+        } else {
+            None
+        };
+
+        if args.first().is_none_or(|it| it.name.as_str() != "this") {
+            return Err(ParseError::new(
+                "E0102",
+                line,
+                column,
+                bind.span,
+                "methods should receive `this` as a parameter",
+            )
+            .with_help(format!(
+                "methods are written as `def {}(this, ...)`",
+                bind.name
+            )));
         }
 
-        self.next()?;
+        Ok(TraitMethod {
+            bind,
+            args,
+            ret,
+            body,
+            doc,
+            location: Location {
+                line,
+                column,
+                span: self.span(start),
+            },
+        })
+    }
+
+    fn impl_bind(&mut self) -> ParseResult<Stmt> {
+        let start = self.current.span.start;
+        self.expect(Tkt::Impl)?;
+        let line = self.current.line;
+        let column = self.current.column;
+
+        let trait_ = self.var_decl()?;
+        self.expect(Tkt::For)?;
+        let ty = self.var_decl()?;
+        let methods = self.methods(start, "impl")?;
+
+        Ok(Stmt::new(
+            StmtKind::Impl {
+                trait_,
+                ty,
+                methods,
+            },
+            line,
+            column,
+            self.span(start),
+        ))
+    }
 
+    /// Parses the methods of a `type` or of an `impl` until their `end`, every method but
+    /// `init` receives `this`. `start` is where the `keyword` that opened them starts
+    fn methods(&mut self, start: usize, keyword: &str) -> ParseResult<Vec<Def>> {
         let mut methods = Vec::new();
-        let mut init = None;
 
-        while self.current.token != Tkt::End {
-            let def = match self.def_bind()?.kind {
-                StmtKind::Def(def) => def,
-                _ => unreachable!(),
+        while !matches!(self.current.token, Tkt::End | Tkt::Eof) {
+            let def = match self.def_bind() {
+                Ok(Stmt {
+                    kind: StmtKind::Def(def),
+                    ..
+                }) => def,
+                Ok(_) => unreachable!(),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize(&[Tkt::Def, Tkt::End]);
+                    continue;
+                }
             };
+
             if def.bind_type != BindType::Fn {
-                self.throw("Expected method definition")?;
+                let err = self.method_error(&def, "expected method definition");
+                self.errors.push(err);
+                continue;
             }
 
             if def.bind.name.as_str() == "init" {
-                init = Some(def);
+                methods.push(def);
                 continue;
             }
 
@@ -87,28 +320,98 @@ impl Parser {
                 {
                     methods.push(def)
                 }
-                _ => self.throw("Methods should receive `this` as a parameter")?,
+                _ => {
+                    let err =
+                        self.method_error(&def, "methods should receive `this` as a parameter");
+                    self.errors.push(err);
+                }
+            }
+        }
+
+        self.close(start, keyword)?;
+        Ok(methods)
+    }
+
+    /// Consumes the `end` of a block opened by `keyword` at `start`
+    fn close(&mut self, start: usize, keyword: &str) -> ParseResult<()> {
+        if self.current.token == Tkt::Eof {
+            let open = Span::new(self.file, start, start + keyword.len());
+            return Err(self
+                .error("E0002", format!("unclosed `{}`", keyword))
+                .with_label(open, format!("`{}` opened here", keyword)));
+        }
+        self.next()
+    }
+
+    /// Parses the parenthesized fields of a type or of one of its variants
+    fn fields(&mut self) -> ParseResult<Vec<VarDecl>> {
+        let mut fields = Vec::new();
+
+        self.expect(Tkt::Lparen)?;
+        while self.current.token != Tkt::Rparen {
+            fields.push(self.param()?);
+
+            if self.current.token == Tkt::Rparen {
+                break;
             }
+
+            self.expect_and_skip(Tkt::Comma)?;
         }
+
         self.next()?;
+        Ok(fields)
+    }
 
-        Ok(Stmt::new(
-            StmtKind::Type {
-                name,
-                params,
-                methods,
-                init,
-            },
-            line,
-            column,
+    /// Parses the variants of a type, `Circle(r) | Rect(w, h)`, the variants without fields
+    /// don't need the parentheses
+    fn variants(&mut self) -> ParseResult<Vec<Variant>> {
+        let mut variants = Vec::new();
+
+        loop {
+            let (line, column) = (self.current.line, self.current.column);
+            let name = self.var_decl()?;
+            let params = if self.current.token == Tkt::Lparen {
+                self.fields()?
+            } else {
+                Vec::new()
+            };
+
+            // the variants are globals, so a second one would replace the first
+            let first = variants
+                .iter()
+                .find(|it: &&Variant| it.name.name == name.name);
+            if let Some(first) = first {
+                let message = format!("the variant `{}` is declared twice", name.name);
+                let err = ParseError::new("E0104", line, column, name.span, message)
+                    .with_label(first.name.span, "first declared here");
+                self.errors.push(err);
+            }
+            variants.push(Variant { name, params });
+
+            if self.current.token != Tkt::Bar {
+                break;
+            }
+            self.next()?;
+        }
+
+        Ok(variants)
+    }
+
+    fn method_error(&self, def: &Def, message: &str) -> ParseError {
+        let Location { line, column, .. } = def.value.location;
+        ParseError::new("E0102", line, column, def.bind.span, message).with_help(format!(
+            "methods are written as `def {}(this, ...) = ...`",
+            def.bind.name
         ))
     }
 
     fn def_bind(&mut self) -> ParseResult<Stmt> {
+        let doc = self.take_doc();
+        let start = self.current.span.start;
         self.expect(Tkt::Def)?;
 
         if self.peek()?.token == Tkt::Lparen {
-            return self.def_fn();
+            return self.def_fn(doc, start);
         }
 
         let line = self.current.line;
@@ -124,43 +427,145 @@ impl Parser {
                 bind,
                 value,
                 bind_type: BindType::Value,
+                doc,
             }),
             line,
             column,
+            self.span(start),
         ))
     }
 
-    fn def_fn(&mut self) -> ParseResult<Stmt> {
+    fn def_fn(&mut self, doc: Option<String>, start: usize) -> ParseResult<Stmt> {
         let line = self.current.line;
         let column = self.current.column;
+        let span = self.current.span;
 
-        let name = match take(&mut self.current.token) {
-            Tkt::Name(id) => id,
-            other => self.throw(format!("Expected name, found {}", other))?,
-        };
+        let name = self.name()?;
 
         self.next()?;
-        let value = self.function()?;
-        let bind = VarDecl::new(name);
+        let value = self.function(self.current.span.start)?;
+        let bind = VarDecl::new(name, span);
 
         Ok(Stmt::new(
             StmtKind::Def(Def {
                 bind,
                 value,
                 bind_type: BindType::Fn,
+                doc,
             }),
             line,
             column,
+            self.span(start),
         ))
     }
 
     fn next(&mut self) -> ParseResult<()> {
-        self.current = self.lexer.next().unwrap()?;
-        Ok(())
+        self.docs = take(&mut self.peeked_docs);
+        // the current token may have been taken, in which case the end stays where it was
+        self.end = self.end.max(self.current.span.end);
+
+        loop {
+            match self.lexer.next().unwrap() {
+                Ok(Token {
+                    token: Tkt::Doc(doc),
+                    ..
+                }) => self.docs.push(doc),
+                Ok(token) => {
+                    self.current = token;
+                    return Ok(());
+                }
+                // the lexer carries on after an error, so it's recorded and the token after it
+                // is used instead
+                Err(e) if e.diagnostic().code == "E0002" => self.unclosed = Some(e),
+                Err(e) => self.errors.push(e),
+            }
+        }
+    }
+
+    /// Takes the doc comments written right before the current token, joining their lines
+    fn take_doc(&mut self) -> Option<String> {
+        if self.docs.is_empty() {
+            None
+        } else {
+            Some(take(&mut self.docs).join("\n"))
+        }
+    }
+
+    /// Returns the span from `start` to the end of the last token consumed
+    fn span(&self, start: usize) -> Span {
+        Span::new(self.file, start, self.end)
+    }
+
+    fn error(&self, code: &'static str, err: impl Into<String>) -> ParseError {
+        let Token {
+            line, column, span, ..
+        } = self.current;
+        ParseError::new(code, line, column, span, err)
+    }
+
+    fn throw<T>(&self, code: &'static str, err: impl Into<String>) -> ParseResult<T> {
+        Err(self.error(code, err))
+    }
+
+    /// Checks if the current token can't be part of an expression, so it ends any unclosed
+    /// delimiter before it
+    fn at_boundary(&self) -> bool {
+        matches!(
+            self.current.token,
+            Tkt::Eof | Tkt::Def | Tkt::Type | Tkt::Trait | Tkt::Impl | Tkt::End
+        )
+    }
+
+    /// Returns the error for an unexpected token after an item of a sequence that should be
+    /// closed by `close`, `open` is the span of the opening delimiter
+    fn unexpected_in(&self, close: Tkt, open: Span) -> ParseError {
+        if self.at_boundary() {
+            return self
+                .error("E0002", "unclosed delimiter")
+                .with_label(open, "delimiter opened here");
+        }
+
+        let err = self.error(
+            "E0100",
+            format!(
+                "expected `,` or `{}`, found `{}`",
+                close, self.current.token
+            ),
+        );
+        if self.at_closer() {
+            return err.with_label(open, "unclosed delimiter");
+        }
+        err
     }
 
-    fn throw<T>(&self, err: impl Into<String>) -> ParseResult<T> {
-        ParseError::throw(self.current.line, self.current.column, err.into())
+    /// Checks if the current token closes a pair of delimiters
+    fn at_closer(&self) -> bool {
+        matches!(self.current.token, Tkt::Rparen | Tkt::Rbrack | Tkt::Rbrace)
+    }
+
+    /// Recovers from an error in an item of a sequence closed by `close`, skipping to the next
+    /// `,` or to `close`. Fails if the sequence is never closed, with `err` itself if it was
+    /// found where the sequence ends or at a closing delimiter of an outer sequence
+    fn recover_item(&mut self, err: ParseError, close: Tkt, open: Span) -> ParseResult<()> {
+        if self.at_boundary() || (self.at_closer() && self.current.token != close) {
+            return Err(err);
+        }
+
+        self.errors.push(err);
+        self.synchronize(&[
+            Tkt::Comma,
+            close.clone(),
+            Tkt::Def,
+            Tkt::Type,
+            Tkt::Trait,
+            Tkt::Impl,
+            Tkt::End,
+        ]);
+
+        if self.at_boundary() {
+            return Err(self.unexpected_in(close, open));
+        }
+        Ok(())
     }
 
     fn expect(&mut self, expected: Tkt) -> ParseResult<()> {
@@ -172,10 +577,10 @@ impl Parser {
         if self.current.token == expected {
             Ok(())
         } else {
-            self.throw(format!(
-                "Expected {}, found `{}`",
-                expected, self.current.token
-            ))
+            self.throw(
+                "E0100",
+                format!("expected `{}`, found `{}`", expected, self.current.token),
+            )
         }
     }
 
@@ -192,17 +597,36 @@ impl Parser {
     }
 
     fn peek(&mut self) -> ParseResult<&Token> {
-        match self.lexer.peek().unwrap() {
-            Ok(t) => Ok(t),
-            Err(e) => Err(*e),
+        while let Some(
+            Err(_)
+            | Ok(Token {
+                token: Tkt::Doc(_), ..
+            }),
+        ) = self.lexer.peek()
+        {
+            match self.lexer.next().unwrap() {
+                Ok(Token {
+                    token: Tkt::Doc(doc),
+                    ..
+                }) => self.peeked_docs.push(doc),
+                Err(e) => self.errors.push(e),
+                Ok(_) => unreachable!(),
+            }
+        }
+
+        match self.lexer.peek() {
+            Some(Ok(t)) => Ok(t),
+            _ => unreachable!("the lexer never ends, and its errors were recorded"),
         }
     }
 
     fn expr(&mut self) -> ParseResult<Expr> {
+        let start = self.current.span.start;
         let mut expr = match self.current.token {
             Tkt::Let => self.let_()?,
             Tkt::If => self.condition()?,
             Tkt::Fn => self.fn_()?,
+            Tkt::Become => self.become_()?,
             _ => self.logic_or()?,
         };
 
@@ -215,6 +639,7 @@ impl Parser {
             expr.location = Location {
                 line: self.current.line,
                 column: self.current.column,
+                span: self.span(start),
             };
         }
 
@@ -222,6 +647,7 @@ impl Parser {
     }
 
     fn condition(&mut self) -> ParseResult<Expr> {
+        let start = self.current.span.start;
         self.expect(Tkt::If)?;
         let line = self.current.line;
         let column = self.current.column;
@@ -242,26 +668,30 @@ impl Parser {
             },
             line,
             column,
+            self.span(start),
         ))
     }
 
     fn args(&mut self) -> ParseResult<Vec<VarDecl>> {
         self.assert(Tkt::Lparen)?;
+        let open = self.current.span;
 
         let mut args = vec![];
 
         self.next()?;
         while self.current.token != Tkt::Rparen {
-            let var = self.var_decl()?;
-            args.push(var);
+            match self.param() {
+                Ok(var) => args.push(var),
+                Err(e) => self.recover_item(e, Tkt::Rparen, open)?,
+            }
 
             match &self.current.token {
                 Tkt::Comma => self.skip(Tkt::Comma)?,
                 Tkt::Rparen => break,
-                _ => self.throw(format!(
-                    "Expected `,`, `)` or other token, found `{}`",
-                    &self.current.token
-                ))?,
+                _ => {
+                    self.recover_item(self.unexpected_in(Tkt::Rparen, open), Tkt::Rparen, open)?;
+                    self.skip(Tkt::Comma)?;
+                }
             }
         }
         self.next()?;
@@ -270,15 +700,44 @@ impl Parser {
     }
 
     fn fn_(&mut self) -> ParseResult<Expr> {
+        let start = self.current.span.start;
         self.expect(Tkt::Fn)?;
-        self.function()
+        self.function(start)
     }
 
-    fn function(&mut self) -> ParseResult<Expr> {
+    /// Parses a tail call, which reuses the frame of the function that makes it
+    fn become_(&mut self) -> ParseResult<Expr> {
+        let start = self.current.span.start;
+        let line = self.current.line;
+        let column = self.current.column;
+        self.expect(Tkt::Become)?;
+
+        let call = self.call()?;
+        if !matches!(call.kind, ExprKind::App { .. }) {
+            return Err(ParseError::new(
+                "E0100",
+                call.line(),
+                call.column(),
+                call.span(),
+                "expected a function call after `become`",
+            ));
+        }
+
+        Ok(Expr::new(
+            ExprKind::Become(Box::new(call)),
+            line,
+            column,
+            self.span(start),
+        ))
+    }
+
+    /// Parses the parameters and the body of a function, whose span starts at `start`
+    fn function(&mut self, start: usize) -> ParseResult<Expr> {
         let line = self.current.line;
         let column = self.current.column;
 
         let args = self.args()?;
+        let ret = self.return_type()?;
 
         self.expect(Tkt::Assign)?;
 
@@ -287,37 +746,80 @@ impl Parser {
         Ok(Expr::new(
             ExprKind::Lambda {
                 args,
+                ret,
                 body: Box::new(body),
             },
             line,
             column,
+            self.span(start),
         ))
     }
 
+    /// Parses the annotated return type of a function, `-> Str`, if it has one
+    fn return_type(&mut self) -> ParseResult<Option<TypeAnnotation>> {
+        if self.current.token != Tkt::Arrow {
+            return Ok(None);
+        }
+
+        self.next()?;
+        Ok(Some(self.annotation()?))
+    }
+
+    /// Returns the name in the current token, which is left in place for the parser to
+    /// recover from if it isn't a name
+    fn name(&self) -> ParseResult<Symbol> {
+        match &self.current.token {
+            Tkt::Name(id) => Ok(*id),
+            other => self.throw("E0101", format!("expected name, found `{}`", other)),
+        }
+    }
+
     fn var_decl(&mut self) -> ParseResult<VarDecl> {
-        let name = match take(&mut self.current.token) {
-            Tkt::Name(id) => id,
-            other => self.throw(format!("Expected name, found `{}`", other))?,
-        };
+        let span = self.current.span;
+        let name = self.name()?;
 
         self.next()?;
 
-        Ok(VarDecl::new(name))
+        Ok(VarDecl::new(name, span))
+    }
+
+    /// Parses a parameter of a function or of a type, with an optional type annotation
+    fn param(&mut self) -> ParseResult<VarDecl> {
+        let decl = self.var_decl()?;
+        if self.current.token != Tkt::Colon {
+            return Ok(decl);
+        }
+
+        self.next()?;
+        Ok(decl.with_type(self.annotation()?))
+    }
+
+    /// Parses the name of a type in an annotation
+    fn annotation(&mut self) -> ParseResult<TypeAnnotation> {
+        let span = self.current.span;
+        let name = self.name()?;
+        self.next()?;
+
+        Ok(TypeAnnotation { name, span })
     }
 
     fn bind_fn(&mut self) -> ParseResult<Bind> {
         let line = self.current.line;
         let column = self.current.column;
+        let span = self.current.span;
 
-        let name = match take(&mut self.current.token) {
-            Tkt::Name(id) => id,
-            other => self.throw(format!("Expected name, found `{}`", other))?,
-        };
+        let name = self.name()?;
 
         self.next()?;
-        let value = self.function()?;
+        let value = self.function(self.current.span.start)?;
 
-        Ok(Bind::new(VarDecl::new(name), Box::new(value), line, column))
+        Ok(Bind::new(
+            VarDecl::new(name, span),
+            Box::new(value),
+            line,
+            column,
+            self.span(span.start),
+        ))
     }
 
     fn bind(&mut self) -> ParseResult<Bind> {
@@ -333,10 +835,17 @@ impl Parser {
         self.expect(Tkt::Assign)?;
         let value = self.expr()?;
 
-        Ok(Bind::new(bind, Box::new(value), line, column))
+        Ok(Bind::new(
+            bind,
+            Box::new(value),
+            line,
+            column,
+            self.span(bind.span.start),
+        ))
     }
 
     fn let_(&mut self) -> ParseResult<Expr> {
+        let start = self.current.span.start;
         self.expect(Tkt::Let)?;
 
         let mut binds = vec![];
@@ -356,7 +865,12 @@ impl Parser {
 
         let body = Box::new(self.expr()?);
 
-        Ok(Expr::new(ExprKind::Let { binds, body }, line, column))
+        Ok(Expr::new(
+            ExprKind::Let { binds, body },
+            line,
+            column,
+            self.span(start),
+        ))
     }
 
     fn logic_or(&mut self) -> ParseResult<Expr> {
@@ -370,6 +884,7 @@ impl Parser {
 
             let line = left.line();
             let column = left.column();
+            let span = self.span(left.span().start);
 
             left = Expr::new(
                 ExprKind::Binary {
@@ -379,6 +894,7 @@ impl Parser {
                 },
                 line,
                 column,
+                span,
             );
         }
 
@@ -396,6 +912,7 @@ impl Parser {
 
             let line = left.line();
             let column = left.column();
+            let span = self.span(left.span().start);
 
             left = Expr::new(
                 ExprKind::Binary {
@@ -405,6 +922,7 @@ impl Parser {
                 },
                 line,
                 column,
+                span,
             );
         }
 
@@ -419,6 +937,7 @@ impl Parser {
             self.next()?;
             let right = self.cmp()?;
 
+            let span = self.span(left.span().start);
             left = Expr::new(
                 ExprKind::Binary {
                     left: Box::new(left),
@@ -427,6 +946,7 @@ impl Parser {
                 },
                 op.line,
                 op.column,
+                span,
             );
         }
 
@@ -441,6 +961,7 @@ impl Parser {
             self.next()?;
             let right = self.cons()?;
 
+            let span = self.span(left.span().start);
             left = Expr::new(
                 ExprKind::Binary {
                     left: Box::new(left),
@@ -449,6 +970,7 @@ impl Parser {
                 },
                 op.line,
                 op.column,
+                span,
             );
         }
 
@@ -463,6 +985,7 @@ impl Parser {
             self.next()?;
             let right = self.cons()?;
 
+            let span = self.span(left.span().start);
             left = Expr::new(
                 ExprKind::Cons {
                     head: Box::new(left),
@@ -470,6 +993,7 @@ impl Parser {
                 },
                 op.line,
                 op.column,
+                span,
             );
         }
 
@@ -485,6 +1009,7 @@ impl Parser {
             self.next()?;
             let right = self.term()?;
 
+            let span = self.span(left.span().start);
             left = Expr::new(
                 ExprKind::Binary {
                     left: Box::new(left),
@@ -493,6 +1018,7 @@ impl Parser {
                 },
                 op.line,
                 op.column,
+                span,
             );
         }
 
@@ -507,6 +1033,7 @@ impl Parser {
             self.next()?;
             let right = self.fact()?;
 
+            let span = self.span(left.span().start);
             left = Expr::new(
                 ExprKind::Binary {
                     left: Box::new(left),
@@ -515,6 +1042,7 @@ impl Parser {
                 },
                 op.line,
                 op.column,
+                span,
             );
         }
 
@@ -524,11 +1052,12 @@ impl Parser {
     fn fact(&mut self) -> ParseResult<Expr> {
         let mut left = self.prefix()?;
 
-        while let Tkt::Mul | Tkt::Div = self.current.token {
+        while let Tkt::Mul | Tkt::Div | Tkt::Rem | Tkt::FloorDiv = self.current.token {
             let op = take(&mut self.current);
             self.next()?;
             let right = self.prefix()?;
 
+            let span = self.span(left.span().start);
             left = Expr::new(
                 ExprKind::Binary {
                     left: Box::new(left),
@@ -537,6 +1066,7 @@ impl Parser {
                 },
                 op.line,
                 op.column,
+                span,
             );
         }
 
@@ -544,7 +1074,7 @@ impl Parser {
     }
 
     fn prefix(&mut self) -> ParseResult<Expr> {
-        if let Tkt::Sub | Tkt::Not = &self.current.token {
+        if let Tkt::Sub | Tkt::Not | Tkt::BitNot = &self.current.token {
             let op = take(&mut self.current);
             self.next()?;
             let right = self.prefix()?;
@@ -552,10 +1082,37 @@ impl Parser {
                 ExprKind::UnOp(op.token.try_into().unwrap(), Box::new(right)),
                 op.line,
                 op.column,
+                self.span(op.span.start),
             ))
         } else {
-            self.instance()
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> ParseResult<Expr> {
+        let left = self.instance()?;
+
+        if let Tkt::Pow = self.current.token {
+            let op = take(&mut self.current);
+            self.next()?;
+
+            // `**` is right-associative and binds tighter than the unary operators on its left
+            let right = self.prefix()?;
+
+            let span = self.span(left.span().start);
+            return Ok(Expr::new(
+                ExprKind::Binary {
+                    left: Box::new(left),
+                    op: op.token.try_into().unwrap(),
+                    right: Box::new(right),
+                },
+                op.line,
+                op.column,
+                span,
+            ));
         }
+
+        Ok(left)
     }
 
     fn instance(&mut self) -> ParseResult<Expr> {
@@ -569,14 +1126,22 @@ impl Parser {
             self.assert(Tkt::Lparen)?;
             let args = self.call_args()?;
 
-            Ok(Expr::new(ExprKind::New { ty, args }, op.line, op.column))
+            Ok(Expr::new(
+                ExprKind::New { ty, args },
+                op.line,
+                op.column,
+                self.span(op.span.start),
+            ))
         } else {
             self.dot()
         }
     }
 
     fn dot(&mut self) -> ParseResult<Expr> {
-        let mut obj = self.call()?;
+        let mut obj = match self.current.token {
+            Tkt::Super => self.super_call()?,
+            _ => self.call()?,
+        };
 
         while self.current.token == Tkt::Dot {
             obj = self.dot_access(obj)?;
@@ -588,6 +1153,7 @@ impl Parser {
     fn dot_access(&mut self, obj: Expr) -> ParseResult<Expr> {
         let line = self.current.line;
         let column = self.current.column;
+        let start = obj.span().start;
 
         self.next()?;
 
@@ -601,26 +1167,74 @@ impl Parser {
                 ExprKind::Invoke { obj, field, args },
                 line,
                 column,
+                self.span(start),
             ))
         } else {
-            Ok(Expr::new(ExprKind::Field { obj, field }, line, column))
+            Ok(Expr::new(
+                ExprKind::Field { obj, field },
+                line,
+                column,
+                self.span(start),
+            ))
         }
     }
 
+    /// Parses a call to a method of the extended type, `super.field(args)`, which can only be
+    /// made by the methods of a type that extends another
+    fn super_call(&mut self) -> ParseResult<Expr> {
+        let parent = match self.parent {
+            Some(parent) => parent,
+            None => {
+                return Err(self
+                    .error(
+                        "E0103",
+                        "`super` used outside of the methods of a type that extends another",
+                    )
+                    .with_help("`super` calls the methods of the type written after `extends`"))
+            }
+        };
+        let op = take(&mut self.current);
+        self.next()?;
+
+        self.expect(Tkt::Dot)?;
+        let field = self.var_decl()?;
+        self.assert(Tkt::Lparen)?;
+        let args = self.call_args()?;
+
+        Ok(Expr::new(
+            ExprKind::Super {
+                parent,
+                field,
+                args,
+            },
+            op.line,
+            op.column,
+            self.span(op.span.start),
+        ))
+    }
+
     fn call_args(&mut self) -> ParseResult<Vec<Expr>> {
+        let open = self.current.span;
         let mut args = vec![];
 
         self.next()?;
         while self.current.token != Tkt::Rparen {
-            args.push(self.expr()?);
+            let start = self.current.span.start;
+            match self.expr() {
+                Ok(arg) => args.push(arg),
+                Err(e) => {
+                    self.recover_item(e, Tkt::Rparen, open)?;
+                    args.push(self.error_node(start));
+                }
+            }
 
             match &self.current.token {
                 Tkt::Comma => self.skip(Tkt::Comma)?,
                 Tkt::Rparen => break,
-                _ => self.throw(format!(
-                    "Expected `,`, `)` or other token, found `{}`",
-                    &self.current.token
-                ))?,
+                _ => {
+                    self.recover_item(self.unexpected_in(Tkt::Rparen, open), Tkt::Rparen, open)?;
+                    self.skip(Tkt::Comma)?;
+                }
             }
         }
         self.next()?;
@@ -637,6 +1251,7 @@ impl Parser {
 
             let line = callee.line();
             let column = callee.column();
+            let span = self.span(callee.span().start);
 
             callee = Expr::new(
                 ExprKind::App {
@@ -645,56 +1260,131 @@ impl Parser {
                 },
                 line,
                 column,
+                span,
             )
         }
 
         Ok(callee)
     }
 
-    fn list(&mut self) -> ParseResult<Expr> {
+    /// Parses the elements of a list whose `[` starts at `start`, stopping at the `]`
+    fn list(&mut self, start: usize) -> ParseResult<Expr> {
         let line = self.current.line;
         let column = self.current.column;
 
+        let open = Span::new(self.file, start, start + 1);
         let mut exprs = Vec::new();
         while self.current.token != Tkt::Rbrack {
-            exprs.push(self.expr()?); // compiles the argument
+            let start = self.current.span.start;
+            match self.expr() {
+                Ok(expr) => exprs.push(expr),
+                Err(e) => {
+                    self.recover_item(e, Tkt::Rbrack, open)?;
+                    exprs.push(self.error_node(start));
+                }
+            }
 
             match &self.current.token {
                 Tkt::Comma => self.skip(Tkt::Comma)?,
                 Tkt::Rbrack => break,
-                _ => self.throw(format!(
-                    "Expected `,`, `]` or other token, found `{}`",
-                    &self.current.token
-                ))?,
+                _ => {
+                    self.recover_item(self.unexpected_in(Tkt::Rbrack, open), Tkt::Rbrack, open)?;
+                    self.skip(Tkt::Comma)?;
+                }
             }
         }
 
-        Ok(Expr::new(ExprKind::List(exprs), line, column))
+        Ok(Expr::new(
+            ExprKind::List(exprs),
+            line,
+            column,
+            Span::new(self.file, start, self.current.span.end),
+        ))
+    }
+
+    fn interpolation(
+        &mut self,
+        parts: Vec<StrPart>,
+        line: usize,
+        column: usize,
+    ) -> ParseResult<Expr> {
+        let span = self.current.span;
+        let mut exprs = Vec::with_capacity(parts.len());
+
+        for part in parts {
+            let expr = match part {
+                StrPart::Lit(s) => Expr::new(ExprKind::Lit(Literal::Str(s)), line, column, span),
+                StrPart::Expr {
+                    src,
+                    line,
+                    column,
+                    offset,
+                } => {
+                    // the closing brace is kept, so the errors at the end of the expression point
+                    // to it instead of to the end of a source that doesn't exist
+                    let src = format!("{}}}", src);
+                    let lexer =
+                        Lexer::with_position(src, line, column, offset).with_file(self.file);
+                    let mut parser = Parser::new(lexer)?;
+                    parser.parent = self.parent;
+                    let start = parser.current.span.start;
+                    let expr = match parser.expr().and_then(|expr| {
+                        parser.assert(Tkt::Rbrace)?;
+                        Ok(expr)
+                    }) {
+                        Ok(expr) => expr,
+                        Err(e) => {
+                            parser.errors.push(e);
+                            parser.synchronize(&[]);
+                            parser.error_node(start)
+                        }
+                    };
+                    self.errors.append(&mut parser.errors);
+                    expr
+                }
+            };
+            exprs.push(expr);
+        }
+
+        Ok(Expr::new(
+            ExprKind::Interpolation(exprs),
+            line,
+            column,
+            span,
+        ))
     }
 
     fn primary(&mut self) -> ParseResult<Expr> {
         let line = self.current.line;
         let column = self.current.column;
+        let span = self.current.span;
 
         let obj = match self.current.token.clone() {
-            Tkt::Num(n) => Expr::new(ExprKind::Lit(Literal::Num(n)), line, column),
-            Tkt::Str(s) => Expr::new(ExprKind::Lit(Literal::Str(s)), line, column),
-            Tkt::True => Expr::new(ExprKind::Lit(Literal::Bool(true)), line, column),
-            Tkt::False => Expr::new(ExprKind::Lit(Literal::Bool(false)), line, column),
-            Tkt::Name(s) => Expr::new(ExprKind::Var(s), line, column),
-            Tkt::Sym(s) => Expr::new(ExprKind::Lit(Literal::Sym(s)), line, column),
+            Tkt::Int(n) => Expr::new(ExprKind::Lit(Literal::Int(n)), line, column, span),
+            Tkt::BigInt(n) => Expr::new(ExprKind::Lit(Literal::BigInt(n)), line, column, span),
+            Tkt::Num(n) => Expr::new(ExprKind::Lit(Literal::Num(n)), line, column, span),
+            Tkt::Str(s) => Expr::new(ExprKind::Lit(Literal::Str(s)), line, column, span),
+            Tkt::Interpolated(parts) => self.interpolation(parts, line, column)?,
+            Tkt::True => Expr::new(ExprKind::Lit(Literal::Bool(true)), line, column, span),
+            Tkt::False => Expr::new(ExprKind::Lit(Literal::Bool(false)), line, column, span),
+            Tkt::Name(s) => Expr::new(ExprKind::Var(s), line, column, span),
+            Tkt::Sym(s) => Expr::new(ExprKind::Lit(Literal::Sym(s)), line, column, span),
             Tkt::Lbrack => {
                 self.next()?;
-                self.list()?
+                self.list(span.start)?
             }
             Tkt::Lparen => {
                 self.next()?;
-                let expr = self.expr()?;
-                self.assert(Tkt::Rparen)?;
+                let mut expr = self.expr()?;
+                if self.current.token != Tkt::Rparen {
+                    return Err(self.unexpected_in(Tkt::Rparen, span));
+                }
+                // the span of a parenthesized expression covers the parentheses
+                expr.location.span = Span::new(self.file, span.start, self.current.span.end);
                 expr
             }
-            Tkt::Nil => Expr::new(ExprKind::Lit(Literal::Unit), line, column),
-            other => self.throw(format!("unexpected token `{}`", other))?,
+            Tkt::Nil => Expr::new(ExprKind::Lit(Literal::Unit), line, column, span),
+            other => self.throw("E0100", format!("unexpected token `{}`", other))?,
         };
 
         Ok(obj)
@@ -0,0 +1,392 @@
+//! Name resolution, which finds the names that aren't defined anywhere and the local variables
+//! that are never used or that shadow others, before the program runs
+use std::collections::HashSet;
+
+use vm::{Capability, Diagnostic, EnvTable, Span, Symbol};
+
+use crate::parser::ast::{Bind, Def, Expr, ExprKind, Location, Stmt, StmtKind, VarDecl};
+
+thread_local! {
+    /// The builtins of every capability group, built once since building them interns their
+    /// names again
+    static BUILTINS: EnvTable = {
+        let mut builtins = EnvTable::with_capacity(64);
+        for capability in Capability::ALL {
+            for (name, value) in capability.builtins().iter() {
+                builtins.insert(name, value);
+            }
+        }
+        builtins
+    };
+}
+
+/// Calls `f` with the builtins of every capability group
+pub(crate) fn builtins<T>(f: impl FnOnce(&EnvTable) -> T) -> T {
+    BUILTINS.with(f)
+}
+
+/// A local variable, a `let` binding or a parameter
+struct Local {
+    decl: VarDecl,
+    line: usize,
+    column: usize,
+    used: bool,
+}
+
+/// The locals introduced by a `let` or by the parameters of a function
+type Scope = Vec<Local>;
+
+/// Resolves the names of a program, the locals of a function can't be seen from the functions
+/// declared inside of it, since they are compiled into scopes of their own
+pub struct Resolver {
+    globals: HashSet<Symbol>,
+    /// The scopes of the function being resolved, innermost last
+    scopes: Vec<Scope>,
+    /// The scopes of the functions around the current one
+    enclosing: Vec<Vec<Scope>>,
+    diagnostics: Vec<Diagnostic>,
+    /// Whether the names that aren't defined are taken as globals set before running
+    external: bool,
+}
+
+impl Resolver {
+    /// Creates a resolver that knows the builtins of every capability group
+    pub fn new() -> Self {
+        let globals = builtins(|it| it.iter().map(|(name, _)| name).collect());
+
+        Self {
+            globals,
+            scopes: Vec::new(),
+            enclosing: Vec::new(),
+            diagnostics: Vec::new(),
+            external: false,
+        }
+    }
+
+    #[must_use]
+    /// Takes the names that aren't defined anywhere as globals that will be set before the
+    /// program runs, like when the globals of the embedder aren't known, instead of reporting
+    /// them. The locals of the functions around a function are still reported
+    pub fn allow_external(mut self) -> Self {
+        self.external = true;
+        self
+    }
+
+    #[must_use]
+    /// Makes the globals defined outside of the program known, like the ones set by an embedder
+    /// or defined by the previous entries of a REPL
+    pub fn with_globals(mut self, globals: &EnvTable) -> Self {
+        self.globals.extend(globals.iter().map(|(name, _)| name));
+        self
+    }
+
+    /// Resolves a whole program, whose `def`s and `type`s can be used before being defined,
+    /// returning the diagnostics in the order they appear in the source
+    pub fn resolve(mut self, stmts: &[Stmt]) -> Vec<Diagnostic> {
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Def(def) => self.globals.insert(def.bind.name),
+                StmtKind::Type { name, variants, .. } => {
+                    self.globals.extend(variants.iter().map(|it| it.name.name));
+                    self.globals.insert(name.name)
+                }
+                StmtKind::Trait { name, .. } => self.globals.insert(name.name),
+                StmtKind::Impl { .. } | StmtKind::Expr(_) => continue,
+            };
+        }
+
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+
+        self.diagnostics.sort_by_key(|it| it.span.start);
+        self.diagnostics
+    }
+
+    /// Resolves a single expression, like an entry of a REPL
+    pub fn resolve_expr(mut self, expr: &Expr) -> Vec<Diagnostic> {
+        self.expr(expr);
+        self.diagnostics.sort_by_key(|it| it.span.start);
+        self.diagnostics
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Def(def) => self.expr(&def.value),
+            StmtKind::Type {
+                parent,
+                methods,
+                init,
+                ..
+            } => {
+                if let Some(parent) = parent {
+                    let Location { line, column, .. } = stmt.location;
+                    self.var(parent.name, parent.span, line, column);
+                }
+                for Def { value, .. } in methods.iter().chain(init.as_deref()) {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Trait { methods, .. } => {
+                for method in methods {
+                    if let Some(body) = &method.body {
+                        let Location { line, column, .. } = method.location;
+                        self.function(&method.args, body, line, column);
+                    }
+                }
+            }
+            StmtKind::Impl {
+                trait_,
+                ty,
+                methods,
+            } => {
+                let Location { line, column, .. } = stmt.location;
+                self.var(trait_.name, trait_.span, line, column);
+                self.var(ty.name, ty.span, line, column);
+                for Def { value, .. } in methods {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Var(name) => self.var(*name, expr.span(), expr.line(), expr.column()),
+            ExprKind::Lambda { args, body, .. } => {
+                self.function(args, body, expr.line(), expr.column())
+            }
+            ExprKind::Let { binds, body } => {
+                self.scopes.push(Scope::new());
+                for Bind {
+                    bind,
+                    value,
+                    location,
+                } in binds
+                {
+                    // the value is evaluated before the name is bound
+                    self.expr(value);
+                    self.declare(*bind, location.line, location.column);
+                }
+                self.expr(body);
+                self.pop_scope();
+            }
+            ExprKind::If { cond, then, else_ } => {
+                self.expr(cond);
+                self.expr(then);
+                self.expr(else_);
+            }
+            ExprKind::App { callee: obj, args } | ExprKind::New { ty: obj, args } => {
+                self.expr(obj);
+                args.iter().for_each(|it| self.expr(it));
+            }
+            ExprKind::Invoke { obj, args, .. } => {
+                self.expr(obj);
+                args.iter().for_each(|it| self.expr(it));
+            }
+            ExprKind::Super { args, .. } => {
+                // the receiver of the call is `this`
+                let this = Symbol::from_static("this");
+                self.var(this, expr.span(), expr.line(), expr.column());
+                args.iter().for_each(|it| self.expr(it));
+            }
+            ExprKind::Field { obj, .. } => self.expr(obj),
+            ExprKind::List(xs) | ExprKind::Interpolation(xs) => {
+                xs.iter().for_each(|it| self.expr(it));
+            }
+            ExprKind::Binary { left, right, .. }
+            | ExprKind::Cons {
+                head: left,
+                tail: right,
+            }
+            | ExprKind::Seq { left, right } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::UnOp(_, right) | ExprKind::Become(right) => self.expr(right),
+            ExprKind::Lit(_) | ExprKind::Error => {}
+        }
+    }
+
+    /// Resolves a function, whose body only sees its parameters and the globals
+    fn function(&mut self, args: &[VarDecl], body: &Expr, line: usize, column: usize) {
+        let outer = std::mem::take(&mut self.scopes);
+        self.enclosing.push(outer);
+
+        self.scopes.push(Scope::new());
+        for arg in args {
+            self.declare(*arg, line, column);
+        }
+        self.expr(body);
+        self.pop_scope();
+
+        self.scopes = self.enclosing.pop().unwrap();
+    }
+
+    /// Adds a local to the innermost scope, warning if it shadows another local of the same
+    /// function
+    fn declare(&mut self, decl: VarDecl, line: usize, column: usize) {
+        if !ignored(decl.name) {
+            let shadowed = self
+                .scopes
+                .iter()
+                .flatten()
+                .rfind(|it| it.decl.name == decl.name);
+            if let Some(shadowed) = shadowed {
+                let warning = Diagnostic::new(
+                    "W0303",
+                    format!("`{}` shadows a local variable", decl.name),
+                    decl.span,
+                    line,
+                    column,
+                )
+                .warning()
+                .with_label(shadowed.decl.span, "previously defined here")
+                .with_help(format!(
+                    "rename one of them, or prefix it with an underscore: `_{}`",
+                    decl.name
+                ));
+                self.diagnostics.push(warning);
+            }
+        }
+
+        self.scopes.last_mut().unwrap().push(Local {
+            decl,
+            line,
+            column,
+            used: false,
+        });
+    }
+
+    /// Pops the innermost scope, warning about the locals that were never used
+    fn pop_scope(&mut self) {
+        for local in self.scopes.pop().unwrap() {
+            if local.used || ignored(local.decl.name) || local.decl.name.as_str() == "this" {
+                continue;
+            }
+
+            let warning = Diagnostic::new(
+                "W0302",
+                format!("unused variable `{}`", local.decl.name),
+                local.decl.span,
+                local.line,
+                local.column,
+            )
+            .warning()
+            .with_help(format!(
+                "if this is intentional, prefix it with an underscore: `_{}`",
+                local.decl.name
+            ));
+            self.diagnostics.push(warning);
+        }
+    }
+
+    fn var(&mut self, name: Symbol, span: Span, line: usize, column: usize) {
+        let local = self
+            .scopes
+            .iter_mut()
+            .flatten()
+            .rfind(|it| it.decl.name == name);
+
+        if let Some(local) = local {
+            local.used = true;
+            return;
+        }
+
+        if self.globals.contains(&name) {
+            return;
+        }
+
+        let is_outer = |it: &Local| it.decl.name == name;
+        if self.external && !self.enclosing.iter().flatten().flatten().any(is_outer) {
+            return;
+        }
+
+        let error = Diagnostic::new(
+            "E0301",
+            format!("undefined name `{}`", name),
+            span,
+            line,
+            column,
+        );
+
+        // the name may be a local of a function around this one, which can't be captured. It's
+        // marked as used so that it isn't reported twice
+        let outer = self
+            .enclosing
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .rfind(|it| it.decl.name == name)
+            .map(|it| {
+                it.used = true;
+                it.decl.span
+            });
+
+        let error = match outer {
+            Some(span) => error
+                .with_label(span, "defined outside of the function")
+                .with_help(format!(
+                    "functions can't use the locals of the functions around them, pass `{}` as \
+                     an argument",
+                    name
+                )),
+            None => match self.suggest(name) {
+                Some(similar) => error.with_help(format!("did you mean `{}`?", similar)),
+                None => error,
+            },
+        };
+        self.diagnostics.push(error);
+    }
+
+    /// Finds the visible name that is the most similar to an undefined one
+    fn suggest(&self, name: Symbol) -> Option<Symbol> {
+        let name = name.as_str();
+        let max = (name.chars().count() / 3).max(1);
+
+        let locals = self.scopes.iter().flatten().map(|it| it.decl.name);
+        let mut candidates = locals
+            .chain(self.globals.iter().copied())
+            .collect::<Vec<_>>();
+        // sorted so that ties are always broken in the same way
+        candidates.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        candidates
+            .into_iter()
+            .map(|it| (distance(name, it.as_str()), it))
+            .filter(|(distance, _)| *distance <= max)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, it)| it)
+    }
+}
+
+/// Checks if a local is named to be ignored by the warnings, like `_` or `_unused`
+fn ignored(name: Symbol) -> bool {
+    name.as_str().starts_with('_')
+}
+
+/// Computes the Levenshtein distance between two names
+pub(crate) fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(diagonal + cost);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
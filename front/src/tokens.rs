@@ -1,9 +1,31 @@
+use vm::Span;
+
+/// A part of an interpolated string literal
 #[derive(Debug, PartialEq, Clone)]
+pub enum StrPart {
+    /// Literal text, with its escapes already applied
+    Lit(String),
+    /// The source of an embedded `${...}` expression and the position where it starts, `offset`
+    /// is the byte offset of the source in its file
+    Expr {
+        src: String,
+        line: usize,
+        column: usize,
+        offset: usize,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
 pub enum TokenType {
     // Literals
+    Int(i64),
+    BigInt(vm::BigInt),
     Num(f64),
     Str(String),
+    Interpolated(Vec<StrPart>),
     Sym(vm::Symbol),
+    /// A `///` doc comment, without the slashes
+    Doc(String),
     Name(vm::Symbol),
     True,
     False,
@@ -24,6 +46,11 @@ pub enum TokenType {
     Or,
     Not,
     Type,
+    Trait,
+    Impl,
+    For,
+    Extends,
+    Super,
     End,
     New,
 
@@ -33,6 +60,8 @@ pub enum TokenType {
     Mul,
     Div,
     Rem,
+    Pow,
+    FloorDiv,
     Eq,
     Ne,
     Greater,
@@ -49,6 +78,7 @@ pub enum TokenType {
     BitXor,
     Shr, // right-shift
     Shl, // left-shift
+    BitNot,
 
     // Symbol
     Lparen,
@@ -61,25 +91,36 @@ pub enum TokenType {
     Semicolon,
     Seq,
     Pipe,
+    Bar, // separates the variants of a type
     Arrow,
     FatArrow,
+    Colon,
     Dot,
 
+    #[default]
     Eof,
 }
 
-impl Default for TokenType {
-    fn default() -> Self {
-        Self::Eof
-    }
-}
-
 impl std::fmt::Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let res = match self {
-            Self::Num(n) => n.to_string(),
+            Self::Int(n) => n.to_string(),
+            Self::BigInt(n) => n.to_string(),
+            Self::Num(n) => format!("{:?}", n),
             Self::Str(s) => "\"".to_owned() + s + "\"",
+            Self::Interpolated(parts) => {
+                let mut str = String::from('"');
+                for part in parts {
+                    match part {
+                        StrPart::Lit(s) => str.push_str(s),
+                        StrPart::Expr { src, .. } => str.push_str(&format!("${{{}}}", src)),
+                    }
+                }
+                str.push('"');
+                str
+            }
             Self::Sym(s) => format!(":{}", s),
+            Self::Doc(s) => format!("///{}", s),
             Self::Name(v) => format!("{}", v),
             Self::True => "true".to_string(),
             Self::False => "false".to_string(),
@@ -99,6 +140,11 @@ impl std::fmt::Display for TokenType {
             Self::Or => "or".into(),
             Self::Not => "not".into(),
             Self::Type => "type".into(),
+            Self::Trait => "trait".into(),
+            Self::Impl => "impl".into(),
+            Self::For => "for".into(),
+            Self::Extends => "extends".into(),
+            Self::Super => "super".into(),
             Self::End => "end".into(),
             Self::New => "new".into(),
 
@@ -107,6 +153,8 @@ impl std::fmt::Display for TokenType {
             Self::Mul => '*'.into(),
             Self::Div => '/'.into(),
             Self::Rem => '%'.into(),
+            Self::Pow => "**".into(),
+            Self::FloorDiv => "~/".into(),
             Self::Eq => "==".into(),
             Self::Ne => "!=".into(),
             Self::Greater => ">".into(),
@@ -122,6 +170,7 @@ impl std::fmt::Display for TokenType {
             Self::BitXor => "^^^".into(),
             Self::Shr => ">>>".into(),
             Self::Shl => "<<<".into(),
+            Self::BitNot => "~~~".into(),
 
             Self::Lparen => '('.into(),
             Self::Rparen => ')'.into(),
@@ -133,8 +182,10 @@ impl std::fmt::Display for TokenType {
             Self::Semicolon => ';'.into(),
             Self::Seq => ">>".into(),
             Self::Pipe => "|>".into(),
+            Self::Bar => '|'.into(),
             Self::Arrow => "->".into(),
             Self::FatArrow => "=>".into(),
+            Self::Colon => ':'.into(),
             Self::Dot => ".".into(),
 
             Self::Eof => "<eof>".into(),
@@ -163,25 +214,35 @@ pub fn fetch_keyword<T: AsRef<str>>(word: T) -> Option<TokenType> {
         "or" => Some(TokenType::Or),
         "not" => Some(TokenType::Not),
         "type" => Some(TokenType::Type),
+        "trait" => Some(TokenType::Trait),
+        "impl" => Some(TokenType::Impl),
+        "for" => Some(TokenType::For),
+        "extends" => Some(TokenType::Extends),
+        "super" => Some(TokenType::Super),
         "end" => Some(TokenType::End),
         "new" => Some(TokenType::New),
         _ => None,
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Token {
+/// A comment skipped by the lexer, doc comments included
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    /// The source text of the comment, delimiters included
+    pub text: String,
+    /// The line where the comment starts
     pub line: usize,
+    /// The column where the comment starts
     pub column: usize,
-    pub token: TokenType,
+    /// Whether the comment starts its line, instead of trailing some code
+    pub own_line: bool,
 }
 
-impl Default for Token {
-    fn default() -> Self {
-        Self {
-            line: 0,
-            column: 0,
-            token: TokenType::Eof,
-        }
-    }
+#[derive(Debug, PartialEq, Default)]
+pub struct Token {
+    pub line: usize,
+    pub column: usize,
+    /// The bytes of the source that the token covers
+    pub span: Span,
+    pub token: TokenType,
 }
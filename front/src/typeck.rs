@@ -0,0 +1,888 @@
+//! A gradual type checker, which finds the values that are used with a type they can never
+//! have, like a string added to a number. The types come from the literals and the type
+//! annotations, and everything else is dynamic, so unannotated code is never reported
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+};
+
+use vm::{Diagnostic, EnvTable, Span, Symbol, Value};
+
+use crate::{
+    parser::ast::{
+        BinOp, Bind, Def, Expr, ExprKind, Literal, Stmt, StmtKind, TypeAnnotation, UnOp, VarDecl,
+        Variant,
+    },
+    plural,
+    resolver::{builtins, distance},
+};
+
+/// The types that a number can have
+const NUMBERS: [&str; 4] = ["Int", "Float", "BigInt", "Ratio"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    /// A value whose type is only known when the program runs
+    Any,
+    /// A number of any of the numeric types
+    Num,
+    /// A value of a builtin or a user type, like `Str` or `Point`
+    Named(Symbol),
+    /// A function whose parameters are known
+    Fn(Rc<Signature>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Param {
+    name: Symbol,
+    ty: Type,
+    /// The span of the annotation of the parameter
+    span: Option<Span>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Signature {
+    params: Vec<Param>,
+    ret: Type,
+    /// The span of the name or of the code of the function
+    span: Span,
+}
+
+impl Type {
+    fn named(name: &'static str) -> Type {
+        Type::Named(Symbol::from_static(name))
+    }
+
+    fn is_any(&self) -> bool {
+        *self == Type::Any
+    }
+
+    fn is_number(&self) -> bool {
+        match self {
+            Type::Num => true,
+            Type::Named(name) => NUMBERS.contains(&name.as_str()),
+            _ => false,
+        }
+    }
+
+    /// Checks if a value can have both types, `Any` overlaps with every type and `Num` with
+    /// every number
+    fn overlaps(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Num, other) | (other, Type::Num) => other.is_number(),
+            _ => self.to_string() == other.to_string(),
+        }
+    }
+
+    /// The type of a value that is either of the two types
+    fn join(self, other: Type) -> Type {
+        if self == other {
+            self
+        } else if self.is_number() && other.is_number() {
+            Type::Num
+        } else {
+            Type::Any
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Any => write!(f, "Any"),
+            Type::Num => write!(f, "Num"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Fn(_) => write!(f, "Fn"),
+        }
+    }
+}
+
+/// Checks the types of a program, the locals of a function can't be seen from the functions
+/// declared inside of it, like in the resolver
+pub struct TypeChecker {
+    /// The names of the types that annotations can use
+    types: HashSet<Symbol>,
+    /// The types of the globals defined with `def`
+    globals: HashMap<Symbol, Type>,
+    /// The parameters of the types declared with `type`, which `new` receives
+    constructors: HashMap<Symbol, Rc<Signature>>,
+    /// The type that every variant belongs to
+    variant_of: HashMap<Symbol, Symbol>,
+    /// The type that every type declared with `extends` extends
+    parents: HashMap<Symbol, Symbol>,
+    /// The methods of the traits declared with `trait`, with their number of parameters and
+    /// whether they have a default implementation
+    traits: HashMap<Symbol, Vec<(Symbol, usize, bool)>>,
+    /// The locals of the function being checked, innermost last
+    locals: Vec<(Symbol, Type)>,
+    diagnostics: Vec<Diagnostic>,
+    /// Whether the unknown types in annotations are taken as types defined before running
+    external: bool,
+}
+
+impl TypeChecker {
+    /// Creates a checker that knows the builtin types of every capability group
+    pub fn new() -> Self {
+        let mut checker = Self {
+            types: HashSet::from([Symbol::from_static("Any")]),
+            globals: HashMap::new(),
+            constructors: HashMap::new(),
+            variant_of: HashMap::new(),
+            parents: HashMap::new(),
+            traits: HashMap::new(),
+            locals: Vec::new(),
+            diagnostics: Vec::new(),
+            external: false,
+        };
+
+        builtins(|it| {
+            for (name, value) in it.iter() {
+                checker.learn(name, &value);
+            }
+        });
+        checker
+    }
+
+    /// Makes the checker know the types defined outside of the program, like the ones
+    /// defined by the previous entries of a REPL
+    #[must_use]
+    pub fn with_globals(mut self, globals: &EnvTable) -> Self {
+        for (name, value) in globals.iter() {
+            self.learn(name, &value);
+        }
+        self
+    }
+
+    /// Takes the unknown types of the annotations as types that will be defined before the
+    /// program runs instead of reporting them, see [`crate::resolver::Resolver::allow_external`]
+    #[must_use]
+    pub fn allow_external(mut self) -> Self {
+        self.external = true;
+        self
+    }
+
+    /// Learns the types and the variants among the globals
+    fn learn(&mut self, name: Symbol, value: &Value) {
+        match value {
+            Value::Type(ty) => {
+                self.types.insert(name);
+                if let Some(parent) = ty.variant_of {
+                    self.variant_of.insert(name, parent);
+                }
+                // like `BigInt`, which extends `Int`
+                if let Some(parent) = &ty.parent {
+                    self.parents.insert(name, parent.name);
+                }
+            }
+            // the variants without fields, like `None`, are values
+            Value::Instance(inst) => {
+                if let Some(parent) = inst.ty.variant_of {
+                    self.variant_of.insert(name, parent);
+                    self.globals.insert(name, Type::Named(name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks a whole program, whose `def`s and `type`s can be used before being defined,
+    /// returning the diagnostics in the order they appear in the source
+    pub fn check(mut self, stmts: &[Stmt]) -> Vec<Diagnostic> {
+        for stmt in stmts {
+            if let StmtKind::Type {
+                name,
+                parent,
+                variants,
+                ..
+            } = &stmt.kind
+            {
+                self.types.insert(name.name);
+                if let Some(parent) = parent {
+                    self.parents.insert(name.name, parent.name);
+                }
+                for variant in variants {
+                    self.types.insert(variant.name.name);
+                    self.variant_of.insert(variant.name.name, name.name);
+                }
+            }
+        }
+
+        // names defined more than once can't be known statically
+        let mut defined = HashSet::new();
+        for stmt in stmts {
+            let globals = match &stmt.kind {
+                StmtKind::Def(Def { bind, value, .. }) => {
+                    vec![(bind.name, self.signature(value, bind))]
+                }
+                // a type with variants can't be instantiated, its variants are
+                StmtKind::Type { name, variants, .. } if !variants.is_empty() => {
+                    let mut globals = vec![(name.name, Type::Any)];
+                    for Variant { name, params } in variants {
+                        let signature = Rc::new(self.constructor(name, params));
+                        self.constructors.insert(name.name, signature.clone());
+
+                        // the variants without fields are values
+                        let ty = if params.is_empty() {
+                            Type::Named(name.name)
+                        } else {
+                            Type::Fn(signature)
+                        };
+                        globals.push((name.name, ty));
+                    }
+                    globals
+                }
+                StmtKind::Type { name, params, .. } => {
+                    let signature = self.constructor(name, params);
+                    self.constructors.insert(name.name, Rc::new(signature));
+                    vec![(name.name, Type::Any)]
+                }
+                StmtKind::Trait { name, methods, .. } => {
+                    let methods = methods
+                        .iter()
+                        .map(|it| (it.bind.name, it.args.len(), it.body.is_some()))
+                        .collect();
+                    self.traits.insert(name.name, methods);
+                    vec![(name.name, Type::Any)]
+                }
+                StmtKind::Impl { .. } | StmtKind::Expr(_) => continue,
+            };
+
+            for (name, ty) in globals {
+                let ty = if defined.insert(name) { ty } else { Type::Any };
+                self.globals.insert(name, ty);
+            }
+        }
+
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Def(def) => {
+                    self.expr(&def.value);
+                }
+                StmtKind::Type {
+                    params,
+                    variants,
+                    methods,
+                    init,
+                    ..
+                } => {
+                    let fields = variants.iter().flat_map(|it| &it.params);
+                    for param in params.iter().chain(fields) {
+                        self.annotation(param.ty, stmt.location.line, stmt.location.column);
+                    }
+                    for Def { value, .. } in methods.iter().chain(init.as_deref()) {
+                        self.expr(value);
+                    }
+                }
+                StmtKind::Trait { methods, .. } => {
+                    for method in methods {
+                        match &method.body {
+                            Some(body) => {
+                                self.function(&method.args, method.ret, body, body);
+                            }
+                            None => {
+                                let (line, column) = (method.location.line, method.location.column);
+                                for arg in &method.args {
+                                    self.annotation(arg.ty, line, column);
+                                }
+                                self.annotation(method.ret, line, column);
+                            }
+                        }
+                    }
+                }
+                StmtKind::Impl {
+                    trait_,
+                    ty,
+                    methods,
+                } => {
+                    self.implementation(stmt, trait_, ty, methods);
+                    for Def { value, .. } in methods {
+                        self.expr(value);
+                    }
+                }
+                StmtKind::Expr(expr) => {
+                    self.expr(expr);
+                }
+            }
+        }
+
+        self.diagnostics.sort_by_key(|it| it.span.start);
+        self.diagnostics
+    }
+
+    /// Checks a single expression, like an entry of a REPL
+    pub fn check_expr(mut self, expr: &Expr) -> Vec<Diagnostic> {
+        self.expr(expr);
+        self.diagnostics.sort_by_key(|it| it.span.start);
+        self.diagnostics
+    }
+
+    /// Checks that an `impl` block of a trait declared in the program defines the methods of
+    /// the trait, with the same number of parameters
+    fn implementation(&mut self, stmt: &Stmt, trait_: &VarDecl, ty: &VarDecl, methods: &[Def]) {
+        let expected = match self.traits.get(&trait_.name) {
+            Some(expected) => expected.clone(),
+            None => return,
+        };
+
+        for Def { bind, value, .. } in methods {
+            let arity = match &value.kind {
+                ExprKind::Lambda { args, .. } => args.len(),
+                _ => continue,
+            };
+            let message = match expected.iter().find(|(name, ..)| *name == bind.name) {
+                None => format!("`{}` isn't a method of `{}`", bind.name, trait_.name),
+                Some((_, params, _)) if *params != arity => format!(
+                    "the method `{}` of `{}` takes {} parameters, but this one takes {}",
+                    bind.name, trait_.name, params, arity
+                ),
+                Some(_) => continue,
+            };
+            let error = Diagnostic::new("E0308", message, bind.span, value.line(), value.column());
+            self.diagnostics.push(error);
+        }
+
+        let missing = expected
+            .iter()
+            .filter(|(name, _, default)| {
+                !default && !methods.iter().any(|it| it.bind.name == *name)
+            })
+            .map(|(name, ..)| format!("`{}`", name))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            let message = format!(
+                "`{}` doesn't implement `{}`, {} missing",
+                ty.name,
+                trait_.name,
+                match missing.len() {
+                    1 => format!("the method {} is", missing[0]),
+                    _ => format!("the methods {} are", missing.join(", ")),
+                }
+            );
+            let error = Diagnostic::new(
+                "E0308",
+                message,
+                trait_.span.to(ty.span),
+                stmt.location.line,
+                stmt.location.column,
+            )
+            .with_help("the methods without a default implementation must be defined");
+            self.diagnostics.push(error);
+        }
+    }
+
+    /// Returns the type of a `def`, whose return type is only known if it's annotated
+    fn signature(&self, value: &Expr, bind: &VarDecl) -> Type {
+        match &value.kind {
+            ExprKind::Lambda { args, ret, .. } => Type::Fn(Rc::new(Signature {
+                params: args.iter().map(|it| self.param(it)).collect(),
+                ret: ret.map_or(Type::Any, |it| self.resolve(it.name)),
+                span: bind.span,
+            })),
+            _ => Type::Any,
+        }
+    }
+
+    /// Returns the signature of a type or of a variant, which receives its fields
+    fn constructor(&self, name: &VarDecl, params: &[VarDecl]) -> Signature {
+        Signature {
+            params: params.iter().map(|it| self.param(it)).collect(),
+            ret: Type::Named(name.name),
+            span: name.span,
+        }
+    }
+
+    /// Checks if a value can have both types, like [`Type::overlaps`], a variant also
+    /// overlaps with the type it belongs to, and a type with the types it extends
+    fn overlaps(&self, a: &Type, b: &Type) -> bool {
+        a.overlaps(b) || self.subtype(a, b) || self.subtype(b, a)
+    }
+
+    /// Checks if `a` is a variant of `b`, or extends it directly or through its parents
+    fn subtype(&self, a: &Type, b: &Type) -> bool {
+        let (Type::Named(mut name), Type::Named(target)) = (a, b) else {
+            return false;
+        };
+
+        // the chain is bounded, since types declared more than once can form cycles
+        for _ in 0..=self.parents.len() {
+            match self
+                .variant_of
+                .get(&name)
+                .or_else(|| self.parents.get(&name))
+            {
+                Some(parent) if parent == target => return true,
+                Some(parent) => name = *parent,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    fn param(&self, decl: &VarDecl) -> Param {
+        Param {
+            name: decl.name,
+            ty: decl.ty.map_or(Type::Any, |it| self.resolve(it.name)),
+            span: decl.ty.map(|it| it.span),
+        }
+    }
+
+    /// Returns the type named in an annotation, unknown types are dynamic
+    fn resolve(&self, name: Symbol) -> Type {
+        match name.as_str() {
+            "Any" => Type::Any,
+            "Num" => Type::Num,
+            _ if self.types.contains(&name) => Type::Named(name),
+            _ => Type::Any,
+        }
+    }
+
+    /// Returns the type of an annotation, reporting the names that aren't types
+    fn annotation(&mut self, ty: Option<TypeAnnotation>, line: usize, column: usize) -> Type {
+        let ty = match ty {
+            Some(ty) => ty,
+            None => return Type::Any,
+        };
+
+        if !self.external && !self.types.contains(&ty.name) {
+            let error = Diagnostic::new(
+                "E0307",
+                format!("unknown type `{}`", ty.name),
+                ty.span,
+                line,
+                column,
+            );
+            let error = match self.suggest(ty.name) {
+                Some(similar) => error.with_help(format!("did you mean `{}`?", similar)),
+                None => error.with_help(
+                    "annotations use the builtin types like `Int` or `Str`, `Num`, `Any` and the \
+                     types declared with `type`",
+                ),
+            };
+            self.diagnostics.push(error);
+        }
+        self.resolve(ty.name)
+    }
+
+    /// Finds the type name that is the most similar to an unknown one
+    fn suggest(&self, name: Symbol) -> Option<Symbol> {
+        let name = name.as_str();
+        let max = (name.chars().count() / 3).max(1);
+
+        let mut candidates = self.types.iter().copied().collect::<Vec<_>>();
+        // sorted so that ties are always broken in the same way
+        candidates.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        candidates
+            .into_iter()
+            .map(|it| (distance(name, it.as_str()), it))
+            .filter(|(distance, _)| *distance <= max)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, it)| it)
+    }
+
+    fn error(&mut self, code: &'static str, node: &Expr, message: impl Into<String>) {
+        let error = Diagnostic::new(code, message, node.span(), node.line(), node.column());
+        self.diagnostics.push(error);
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Type {
+        match &expr.kind {
+            ExprKind::Lit(lit) => match lit {
+                Literal::Int(_) => Type::named("Int"),
+                Literal::BigInt(_) => Type::named("BigInt"),
+                Literal::Num(_) => Type::named("Float"),
+                Literal::Str(_) => Type::named("Str"),
+                Literal::Bool(_) => Type::named("Bool"),
+                Literal::Sym(_) => Type::named("Sym"),
+                Literal::Unit => Type::named("Nil"),
+            },
+            ExprKind::Var(name) => self.var(*name),
+            ExprKind::List(xs) => {
+                for it in xs {
+                    self.expr(it);
+                }
+                Type::named("List")
+            }
+            ExprKind::Interpolation(parts) => {
+                for it in parts {
+                    self.expr(it);
+                }
+                Type::named("Str")
+            }
+            ExprKind::Lambda { args, ret, body } => self.function(args, *ret, body, expr),
+            ExprKind::Let { binds, body } => {
+                let len = self.locals.len();
+                for Bind { bind, value, .. } in binds {
+                    // the value is checked before the name is bound
+                    let ty = self.expr(value);
+                    self.locals.push((bind.name, ty));
+                }
+                let ty = self.expr(body);
+                self.locals.truncate(len);
+                ty
+            }
+            ExprKind::If { cond, then, else_ } => {
+                self.expr(cond);
+                let then = self.expr(then);
+                then.join(self.expr(else_))
+            }
+            ExprKind::App { callee, args } => self.call(callee, args),
+            ExprKind::New { ty, args } => self.instance(ty, args, expr),
+            ExprKind::Invoke { obj, args, .. } => {
+                self.expr(obj);
+                for it in args {
+                    self.expr(it);
+                }
+                Type::Any
+            }
+            ExprKind::Super { args, .. } => {
+                for it in args {
+                    self.expr(it);
+                }
+                Type::Any
+            }
+            ExprKind::Field { obj, .. } => {
+                self.expr(obj);
+                Type::Any
+            }
+            ExprKind::Cons { head, tail } => {
+                self.expr(head);
+                let ty = self.expr(tail);
+                if !ty.overlaps(&Type::named("List")) {
+                    self.error(
+                        "E0304",
+                        tail,
+                        format!("expected a `List` after `::`, found `{}`", ty),
+                    );
+                }
+                Type::named("List")
+            }
+            ExprKind::Seq { left, right } => {
+                self.expr(left);
+                self.expr(right)
+            }
+            ExprKind::Binary { left, op, right } => self.binary(left, *op, right, expr),
+            ExprKind::UnOp(op, right) => {
+                let ty = self.expr(right);
+                match op {
+                    UnOp::Not => Type::named("Bool"),
+                    UnOp::Neg if !ty.overlaps(&Type::Num) => {
+                        self.error(
+                            "E0304",
+                            expr,
+                            format!("can't negate a value of type `{}`", ty),
+                        );
+                        Type::Any
+                    }
+                    UnOp::Neg => ty,
+                    UnOp::BitNot => Type::Any,
+                }
+            }
+            ExprKind::Become(call) => self.expr(call),
+            ExprKind::Error => Type::Any,
+        }
+    }
+
+    fn var(&self, name: Symbol) -> Type {
+        match self.locals.iter().rfind(|(it, _)| *it == name) {
+            Some((_, ty)) => ty.clone(),
+            None => self.globals.get(&name).cloned().unwrap_or(Type::Any),
+        }
+    }
+
+    /// Checks a function, whose body only sees its parameters and the globals
+    fn function(
+        &mut self,
+        args: &[VarDecl],
+        ret: Option<TypeAnnotation>,
+        body: &Expr,
+        node: &Expr,
+    ) -> Type {
+        let (line, column) = (node.line(), node.column());
+
+        let mut params = Vec::new();
+        for arg in args {
+            let ty = self.annotation(arg.ty, line, column);
+            params.push(Param {
+                name: arg.name,
+                ty,
+                span: arg.ty.map(|it| it.span),
+            });
+        }
+        let locals = params.iter().map(|it| (it.name, it.ty.clone())).collect();
+        let ret_ty = self.annotation(ret, line, column);
+
+        let outer = std::mem::replace(&mut self.locals, locals);
+        let body_ty = self.expr(body);
+        self.locals = outer;
+
+        let ret_ty = match ret {
+            Some(ret) if !self.overlaps(&body_ty, &ret_ty) => {
+                let error = Diagnostic::new(
+                    "E0304",
+                    format!(
+                        "expected the function to return `{}`, found `{}`",
+                        ret_ty, body_ty
+                    ),
+                    body.span(),
+                    body.line(),
+                    body.column(),
+                )
+                .with_label(ret.span, "return type annotated here");
+                self.diagnostics.push(error);
+                ret_ty
+            }
+            Some(_) => ret_ty,
+            None => body_ty,
+        };
+
+        Type::Fn(Rc::new(Signature {
+            params,
+            ret: ret_ty,
+            span: node.span(),
+        }))
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr]) -> Type {
+        let callee_ty = self.expr(callee);
+        let types = args.iter().map(|it| self.expr(it)).collect::<Vec<_>>();
+
+        let signature = match callee_ty {
+            Type::Fn(signature) => signature,
+            Type::Any => return Type::Any,
+            Type::Named(name) if name.as_str() == "Fn" => return Type::Any,
+            ty => {
+                self.error(
+                    "E0305",
+                    callee,
+                    format!("expected a function to call, found `{}`", ty),
+                );
+                return Type::Any;
+            }
+        };
+
+        // the variants of a type are called to build them
+        let variant = match &callee.kind {
+            ExprKind::Var(name)
+                if self.variant_of.contains_key(name)
+                    && !self.locals.iter().any(|(it, _)| it == name) =>
+            {
+                Some(*name)
+            }
+            _ => None,
+        };
+
+        let arity = signature.params.len();
+        if args.len() > arity {
+            let (callee, label) = match variant {
+                Some(name) => (format!("`{}`", name), "variant declared here"),
+                None => (String::from("the function"), "function defined here"),
+            };
+            let error = Diagnostic::new(
+                "E0306",
+                format!(
+                    "{} takes {} but {} {} given",
+                    callee,
+                    plural(arity, "argument"),
+                    args.len(),
+                    if args.len() == 1 { "was" } else { "were" }
+                ),
+                args[arity].span(),
+                args[arity].line(),
+                args[arity].column(),
+            )
+            .with_label(signature.span, label);
+            self.diagnostics.push(error);
+            return Type::Any;
+        }
+
+        self.arguments(&signature, args, &types, variant);
+
+        // calls with fewer arguments are partial applications
+        if args.len() < arity {
+            Type::Fn(Rc::new(Signature {
+                params: signature.params[args.len()..].to_vec(),
+                ret: signature.ret.clone(),
+                span: signature.span,
+            }))
+        } else {
+            signature.ret.clone()
+        }
+    }
+
+    /// Checks the values passed to the parameters of a function, or to the fields of a type
+    /// when `ty` is given
+    fn arguments(
+        &mut self,
+        signature: &Signature,
+        args: &[Expr],
+        types: &[Type],
+        ty: Option<Symbol>,
+    ) {
+        for (param, (arg, found)) in signature.params.iter().zip(args.iter().zip(types)) {
+            if self.overlaps(&param.ty, found) {
+                continue;
+            }
+
+            let message = match ty {
+                Some(ty) => format!(
+                    "expected `{}` for the field `{}` of `{}`, found `{}`",
+                    param.ty, param.name, ty, found
+                ),
+                None => format!(
+                    "expected `{}` for the parameter `{}`, found `{}`",
+                    param.ty, param.name, found
+                ),
+            };
+            let mut error = Diagnostic::new("E0304", message, arg.span(), arg.line(), arg.column());
+            if let Some(span) = param.span {
+                error = error.with_label(span, "type annotated here");
+            }
+            self.diagnostics.push(error);
+        }
+    }
+
+    fn instance(&mut self, ty: &Expr, args: &[Expr], node: &Expr) -> Type {
+        let types = args.iter().map(|it| self.expr(it)).collect::<Vec<_>>();
+
+        // a local with the name of the type hides it
+        let name = match &ty.kind {
+            ExprKind::Var(name) if !self.locals.iter().any(|(it, _)| it == name) => *name,
+            _ => {
+                self.expr(ty);
+                return Type::Any;
+            }
+        };
+
+        let signature = match self.constructors.get(&name) {
+            Some(signature) => signature.clone(),
+            None => return Type::Any,
+        };
+
+        let arity = signature.params.len();
+        if args.len() != arity {
+            let error = Diagnostic::new(
+                "E0306",
+                format!(
+                    "`{}` takes {} but {} {} given",
+                    name,
+                    plural(arity, "argument"),
+                    args.len(),
+                    if args.len() == 1 { "was" } else { "were" }
+                ),
+                node.span(),
+                node.line(),
+                node.column(),
+            )
+            .with_label(signature.span, "type declared here");
+            self.diagnostics.push(error);
+        } else {
+            self.arguments(&signature, args, &types, Some(name));
+        }
+
+        Type::Named(name)
+    }
+
+    fn binary(&mut self, left: &Expr, op: BinOp, right: &Expr, node: &Expr) -> Type {
+        let (a, b) = (self.expr(left), self.expr(right));
+
+        let valid = match op {
+            // numbers are added to numbers, and strings to strings
+            BinOp::Add => {
+                let str = Type::named("Str");
+                let addable = |it: &Type| it.overlaps(&Type::Num) || it.overlaps(&str);
+                addable(&a)
+                    && addable(&b)
+                    && (a.is_any()
+                        || b.is_any()
+                        || (a.is_number() && b.is_number())
+                        || (a == str && b == str))
+            }
+            BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem | BinOp::Pow | BinOp::FloorDiv => {
+                a.overlaps(&Type::Num) && b.overlaps(&Type::Num)
+            }
+            // numbers of different types are compared by their values
+            BinOp::Less | BinOp::LessEq | BinOp::Greater | BinOp::GreaterEq => {
+                (a.is_number() && b.is_number()) || self.overlaps(&a, &b)
+            }
+            _ => true,
+        };
+
+        if !valid {
+            let error = Diagnostic::new(
+                "E0304",
+                format!("can't apply `{}` to `{}` and `{}`", symbol(op), a, b),
+                node.span(),
+                node.line(),
+                node.column(),
+            );
+            let str = Type::named("Str");
+            let error = match op {
+                BinOp::Add if a == str || b == str => error.with_help(
+                    "convert the other value with `str`, or use an interpolated string like \
+                     \"${value}\"",
+                ),
+                _ => error,
+            };
+            self.diagnostics.push(error);
+            return Type::Any;
+        }
+
+        match op {
+            BinOp::Eq
+            | BinOp::Ne
+            | BinOp::Less
+            | BinOp::LessEq
+            | BinOp::Greater
+            | BinOp::GreaterEq => Type::named("Bool"),
+            BinOp::Add if a.to_string() == "Str" || b.to_string() == "Str" => Type::named("Str"),
+            BinOp::Add
+            | BinOp::Sub
+            | BinOp::Mul
+            | BinOp::Div
+            | BinOp::Rem
+            | BinOp::Pow
+            | BinOp::FloorDiv
+                if a.is_number() && b.is_number() =>
+            {
+                Type::Num
+            }
+            _ => Type::Any,
+        }
+    }
+}
+
+/// Returns the source text of an operator
+fn symbol(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Less => "<",
+        BinOp::LessEq => "<=",
+        BinOp::Greater => ">",
+        BinOp::GreaterEq => ">=",
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Rem => "%",
+        BinOp::Pow => "**",
+        BinOp::FloorDiv => "~/",
+        BinOp::BitAnd => "&&&",
+        BinOp::BitOr => "|||",
+        BinOp::BitXor => "^^^",
+        BinOp::Shr => ">>>",
+        BinOp::Shl => "<<<",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::And => "and",
+        BinOp::Or => "or",
+    }
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{output, run, run_on};
+use vm::{ErrorKind, VirtualMachine};
+
+#[test]
+fn factorials_are_exact() {
+    let src = "def fact(n) = if n < 2 then 1 else n * fact(n - 1)\nprintln(fact(30))";
+    assert_eq!(output(src), "265252859812191058636308480000000\n");
+}
+
+#[test]
+fn ratios_are_exact() {
+    assert_eq!(
+        output(
+            "println(ratio(1, 3) + ratio(1, 6))\nprintln(ratio(2, 4))\nprintln(ratio(1, 3) * 3)"
+        ),
+        "1/2\n1/2\n1\n"
+    );
+}
+
+#[test]
+fn shifts_overflow_into_big_integers() {
+    assert_eq!(
+        output("println(1 <<< 64)\nprintln((1 <<< 64) >>> 63)"),
+        "18446744073709551616\n2\n"
+    );
+}
+
+#[test]
+fn huge_shifts_are_errors() {
+    let error = run("println(1 <<< 9223372036854775807)").error();
+    assert_eq!(error.kind, ErrorKind::Runtime);
+    assert!(error.err.contains("too large"), "{}", error.err);
+
+    let error = run("println(1 <<< (2 ** 70))").error();
+    assert!(error.err.contains("too large"), "{}", error.err);
+}
+
+#[test]
+fn shifts_are_checked_against_the_memory_limit() {
+    let vm = VirtualMachine::default().with_memory_limit(100_000);
+    let run = run_on(vm, "println(1 <<< 10000000)");
+    assert_eq!(run.error().kind, ErrorKind::MemoryLimit);
+}
+
+#[test]
+fn big_multiplications_are_checked_against_the_memory_limit() {
+    let vm = VirtualMachine::default().with_memory_limit(100_000);
+    let src = "def square(n, times) = if times == 0 then n else square(n * n, times - 1)\n\
+               println(square(2 ** 64, 20))";
+    assert_eq!(run_on(vm, src).error().kind, ErrorKind::MemoryLimit);
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::run_on;
+use vm::{Capability, ErrorKind, VmBuilder};
+
+#[test]
+fn core_builtins_are_always_granted() {
+    let vm = VmBuilder::new().allow(Capability::Io).build();
+    let run = run_on(vm, "println([1, 2].len())");
+    assert_eq!(run.output(), "2\n");
+}
+
+#[test]
+fn builtins_of_denied_capabilities_are_not_granted() {
+    let vm = VmBuilder::new().build();
+    let error = run_on(vm, "println(1)").error();
+    assert_eq!(error.kind, ErrorKind::NotGranted(Capability::Io));
+    assert_eq!(error.code, "E0207");
+}
+
+#[test]
+fn denying_a_capability_removes_its_builtins() {
+    let vm = VmBuilder::new()
+        .allow_all()
+        .deny(Capability::Process)
+        .build();
+    let run = run_on(vm, "println(1)\nexit(2)");
+    assert_eq!(run.stdout, "1\n");
+    assert_eq!(run.error().kind, ErrorKind::NotGranted(Capability::Process));
+}
+
+#[test]
+fn every_capability_group_can_be_denied() {
+    assert_eq!(
+        Capability::ALL.map(|it| it.name()),
+        ["core", "io", "fs", "process", "time"]
+    );
+    let vm = VmBuilder::new()
+        .allow_all()
+        .deny(Capability::Fs)
+        .deny(Capability::Time)
+        .build();
+    assert_eq!(run_on(vm, "println(1)").output(), "1\n");
+    assert!(Capability::Fs.builtins().is_empty());
+}
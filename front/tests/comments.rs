@@ -0,0 +1,62 @@
+mod common;
+
+use common::output;
+use vm::{Doc, Symbol, Value, VirtualMachine};
+
+#[test]
+fn block_comments_can_be_nested() {
+    assert_eq!(
+        output("/* a /* nested */ comment */\nprintln(1 /* inline */ + 1)"),
+        "2\n"
+    );
+}
+
+#[test]
+fn doc_comments_are_attached_to_definitions() {
+    let src = "/// Adds one to a number\n/// to another\ndef inc(n) = n + 1\n\
+               /// A point\ntype Point(x, y)\n  /// The sum of the coordinates\n  \
+               def sum(this) = this.x + this.y\nend\n\
+               println(doc(inc))\nprintln(doc(Point))\n\
+               println(doc(println))";
+    assert_eq!(
+        output(src),
+        "Adds one to a number\nto another\nA point\n\
+         println(value)\nPrints a value followed by a new line\n"
+    );
+}
+
+#[test]
+fn undocumented_values_have_no_doc() {
+    assert_eq!(
+        output("def f(x) = x\nprintln(doc(f))\nprintln(doc(1))"),
+        "nil\nnil\n"
+    );
+}
+
+#[test]
+fn doc_comments_of_methods_are_stored_on_their_functions() {
+    let (bytecode, constants) = common::compile(
+        "type Point(x, y)\n  /// The sum of the coordinates\n  def sum(this) = this.x + this.y\nend",
+    );
+    let mut vm = VirtualMachine::default();
+    vm.set_consts(constants);
+    vm.run(&bytecode).unwrap();
+
+    let point = match vm.get_global("Point") {
+        Some(Value::Type(ty)) => ty,
+        other => panic!("expected a type, found {:?}", other),
+    };
+    let sum = point.fields.get(&Symbol::new("sum")).unwrap();
+    assert_eq!(sum.doc().as_deref(), Some("The sum of the coordinates"));
+}
+
+#[test]
+fn builtin_docs_live_in_static_tables() {
+    let list = Value::List(Default::default()).type_of();
+    let map = list.fields.get(&Symbol::new("map")).unwrap();
+    assert!(matches!(map.doc(), Some(Doc::Static(doc)) if doc.starts_with("xs.map(f)")));
+
+    let before = vm::gc::allocated();
+    let _ = map.doc();
+    assert_eq!(vm::gc::allocated(), before);
+}
@@ -0,0 +1,84 @@
+//! Helpers shared by the integration tests, which compile and run yex programs while capturing
+//! what they print
+#![allow(dead_code)]
+
+use vm::{EnvTable, InterpretError, SharedBuffer, VirtualMachine};
+
+/// The outcome of running a program
+pub struct Run {
+    /// Everything the program wrote to its stdout
+    pub stdout: String,
+    /// How the program ended
+    pub result: Result<(), InterpretError>,
+}
+
+impl Run {
+    /// Returns the error that stopped the program, panicking if it ran successfully
+    pub fn error(self) -> InterpretError {
+        match self.result {
+            Ok(()) => panic!("the program ran successfully, printing {:?}", self.stdout),
+            Err(e) => e,
+        }
+    }
+
+    /// Returns what the program printed, panicking if it failed
+    pub fn output(self) -> String {
+        match self.result {
+            Ok(()) => self.stdout,
+            Err(e) => panic!("the program failed: {} ({})", e, e.code),
+        }
+    }
+}
+
+/// Compiles a program like `yex` does, reporting the names it doesn't define, panicking if it has
+/// compilation errors
+pub fn compile(src: &str) -> (vm::Bytecode, Vec<vm::Value>) {
+    match front::parse_file_with_globals("test.yex", src, &EnvTable::new()) {
+        Ok(res) => res,
+        Err(errors) => {
+            let errors = errors.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+            panic!("the program doesn't compile: {:?}", errors)
+        }
+    }
+}
+
+/// Returns the compilation errors of a program, panicking if it compiles
+pub fn errors(src: &str) -> Vec<String> {
+    match front::parse_file_with_globals("test.yex", src, &EnvTable::new()) {
+        Ok(_) => panic!("the program compiles"),
+        Err(errors) => errors.iter().map(|it| it.to_string()).collect(),
+    }
+}
+
+/// Returns the compilation errors of a program as diagnostics, panicking if it compiles
+pub fn diagnostics(src: &str) -> Vec<vm::Diagnostic> {
+    match front::parse_file_with_globals("test.yex", src, &EnvTable::new()) {
+        Ok(_) => panic!("the program compiles"),
+        Err(errors) => errors.into_iter().map(vm::Diagnostic::from).collect(),
+    }
+}
+
+/// Runs a program on a virtual machine with every capability
+pub fn run(src: &str) -> Run {
+    run_on(VirtualMachine::default(), src)
+}
+
+/// Runs a program on the given virtual machine, capturing its stdout
+pub fn run_on(vm: VirtualMachine, src: &str) -> Run {
+    let (bytecode, constants) = compile(src);
+
+    let stdout = SharedBuffer::new();
+    let mut vm = vm.with_stdout(stdout.clone());
+    vm.set_consts(constants);
+    let result = vm.run(&bytecode);
+
+    Run {
+        stdout: stdout.contents(),
+        result,
+    }
+}
+
+/// Runs a program and returns what it printed, panicking if it failed
+pub fn output(src: &str) -> String {
+    run(src).output()
+}
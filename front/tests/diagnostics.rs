@@ -0,0 +1,29 @@
+mod common;
+
+use common::{diagnostics, run};
+
+#[test]
+fn unclosed_delimiters_point_at_where_they_were_opened() {
+    let src = "def x = [1, 2\n";
+    let errors = diagnostics(src);
+    assert_eq!(errors[0].code, "E0002");
+    assert_eq!(errors[0].message, "unclosed delimiter");
+
+    let label = &errors[0].labels[0];
+    assert_eq!(label.message, "delimiter opened here");
+    assert_eq!(&src[label.span.start..label.span.end], "[");
+}
+
+#[test]
+fn syntax_errors_have_stable_codes() {
+    let errors = diagnostics("def x = (1 +)");
+    assert_eq!(errors[0].code, "E0100");
+}
+
+#[test]
+fn runtime_errors_have_stable_codes() {
+    let error = run("println(1 / 0)").error();
+    assert_eq!(error.code, "E0206");
+    assert_eq!(error.diagnostic().code, "E0206");
+    assert_eq!(error.diagnostic().message, error.err);
+}
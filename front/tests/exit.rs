@@ -0,0 +1,28 @@
+mod common;
+
+use common::run;
+use vm::ErrorKind;
+
+#[test]
+fn exit_unwinds_the_vm_with_its_code() {
+    let run = run("println(1)\nexit(3)\nprintln(2)");
+    assert_eq!(run.stdout, "1\n");
+
+    let error = run.error();
+    assert_eq!(error.kind, ErrorKind::Exit(3));
+    assert_eq!(error.exit_code(), Some(3));
+}
+
+#[test]
+fn exit_inside_a_function_stops_the_whole_program() {
+    let run = run("def stop(x) = exit(x)\nprintln(stop(7))\nprintln(2)");
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.error().exit_code(), Some(7));
+}
+
+#[test]
+fn invalid_exit_codes_are_runtime_errors() {
+    let error = run("exit(99999999999)").error();
+    assert_eq!(error.kind, ErrorKind::Runtime);
+    assert_eq!(error.exit_code(), None);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn positional_and_named_arguments() {
+    assert_eq!(
+        output(
+            "println(format(\"{} {0} {}\", [1, 2]))\n\
+             println(format(\"{name} is {age}\", (new Table()).insert(:name, \"ana\").insert(:age, 3)))"
+        ),
+        "1 1 2\nana is 3\n"
+    );
+}
+
+#[test]
+fn missing_named_arguments_are_errors() {
+    let error = run("println(format(\"{age}\", (new Table()).insert(:name, 1)))").error();
+    assert_eq!(error.err, "format: missing argument at column 1");
+    let error = run("println(format(\"{name}\", [1]))").error();
+    assert!(
+        error.err.contains("named argument `:name`"),
+        "{}",
+        error.err
+    );
+}
+
+#[test]
+fn width_fill_alignment_and_precision() {
+    assert_eq!(
+        output(
+            "println(format(\"[{:>8.2}]\", [3.14159]))\nprintln(format(\"[{:*^7}]\", [\"ab\"]))\n\
+             println(format(\"[{:<4}]\", [1]))\nprintln(format(\"[{:05}]\", [-42]))"
+        ),
+        "[    3.14]\n[**ab***]\n[1   ]\n[-0042]\n"
+    );
+}
+
+#[test]
+fn integer_bases_and_debug_form() {
+    assert_eq!(
+        output(
+            "println(format(\"{:x} {:X} {:o} {:b}\", [255, 255, 8, 5]))\n\
+             println(format(\"{:?}\", [\"a\"]) == inspect(\"a\"))"
+        ),
+        "ff FF 10 101\ntrue\n"
+    );
+}
+
+#[test]
+fn malformed_specs_report_their_column() {
+    let error = run("println(format(\"ab {:.}\", [1]))").error();
+    assert_eq!(
+        error.err,
+        "format: expected a precision after `.` at column 7"
+    );
+}
+
+#[test]
+fn huge_widths_and_precisions_are_errors() {
+    let error = run("println(format(\"{:99999999999}\", [1]))").error();
+    assert_eq!(
+        error.err,
+        "format: the width can't be larger than 65535 at column 3"
+    );
+
+    let error = run("println(format(\"{:.100000}\", [1.5]))").error();
+    assert_eq!(
+        error.err,
+        "format: the precision can't be larger than 65535 at column 4"
+    );
+
+    let error = run("println(format(\"{:99999999999999999999999}\", [1]))").error();
+    assert_eq!(
+        error.err,
+        "format: the width can't be larger than 65535 at column 3"
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::output;
+
+const MESSY: &str = "// a comment\ndef inc(n)=n+1\n/* block */\ntype Point(x,y)\ndef sum(this)=this.x+this.y\nend\n\
+def main = let p = new Point(1, 2) in if p.sum() > 2 then println(inc(p.sum())) else println([1,2,3].map(fn(x)=x*2))\n\
+def _ = main\n";
+
+const FORMATTED: &str = "// a comment\ndef inc(n) = n + 1\n/* block */\ntype Point(x, y)\n  def sum(this) = this.x + this.y\nend\n\
+def main =\n  let p = new Point(1, 2)\n  in if p.sum() > 2 then\n    println(inc(p.sum()))\n  else\n    println([1, 2, 3].map(fn(x) = x * 2))\n\
+def _ = main\n";
+
+#[test]
+fn formats_blocks_with_consistent_indentation_and_keeps_comments() {
+    assert_eq!(front::format(MESSY).unwrap(), FORMATTED);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    assert_eq!(front::format(FORMATTED).unwrap(), FORMATTED);
+}
+
+#[test]
+fn formatting_keeps_the_meaning_of_programs() {
+    let formatted = front::format(MESSY).unwrap();
+    assert_eq!(output(&formatted), output(MESSY));
+    assert_eq!(output(&formatted), "4\n");
+}
+
+#[test]
+fn sources_with_syntax_errors_are_not_formatted() {
+    assert!(front::format("def x = (1").is_err());
+}
+
+#[test]
+fn block_comments_inside_expressions_stay_in_place() {
+    let src = "def f(x) = x + /* inner */ 1\ndef g = [1, /* two */ 2, 3]\n\
+               def h(x) = f(x /* last */) + 2\n";
+    assert_eq!(front::format(src).unwrap(), src);
+
+    let messy = "def f(x)=x+/* inner */1\n/* before */ def g=f( /* arg */ 2)\n";
+    let formatted = "def f(x) = x + /* inner */ 1\n/* before */\ndef g = f(/* arg */ 2)\n";
+    assert_eq!(front::format(messy).unwrap(), formatted);
+    assert_eq!(front::format(formatted).unwrap(), formatted);
+}
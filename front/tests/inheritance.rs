@@ -0,0 +1,94 @@
+mod common;
+
+use common::{diagnostics, output, run};
+
+const USERS: &str = "type User(name)
+  def greet(this) = \"hi \" + this.name
+  def name_of(this) = this.name
+end
+type Admin(name, perms) extends User
+  def greet(this) = super.greet() + \" (admin)\"
+end
+def admin = new Admin(\"ana\", [:all])
+";
+
+fn users(src: &str) -> String {
+    output(&format!("{}{}", USERS, src))
+}
+
+#[test]
+fn methods_are_inherited() {
+    assert_eq!(users("println(admin.name_of())"), "ana\n");
+}
+
+#[test]
+fn super_calls_the_replaced_method() {
+    assert_eq!(users("println(admin.greet())"), "hi ana (admin)\n");
+}
+
+#[test]
+fn is_a_understands_subtypes() {
+    assert_eq!(
+        users(
+            "println(is_a?(admin, User))\nprintln(is_a?(admin, Admin))\n\
+             def user = new User(\"bo\")\nprintln(is_a?(user, Admin))"
+        ),
+        "true\ntrue\nfalse\n"
+    );
+}
+
+#[test]
+fn typeof_returns_the_type_a_value_was_created_with() {
+    assert_eq!(users("println(typeof(admin))"), "<type(Admin)>\n");
+}
+
+#[test]
+fn annotations_accept_the_types_that_extend_them() {
+    assert_eq!(
+        users("def greet(u: User) = u.greet()\nprintln(greet(admin))"),
+        "hi ana (admin)\n"
+    );
+}
+
+#[test]
+fn initializers_run_from_the_extended_type_down() {
+    let src = "type A(x)
+  def init(this) = let _ = println(\"a\") in this
+end
+type B(x) extends A
+  def init(this) = let _ = println(\"b\") in this
+end
+def _ = new B(1)";
+    assert_eq!(output(src), "a\nb\n");
+}
+
+#[test]
+fn traits_are_inherited() {
+    assert_eq!(
+        users(
+            "trait Named = def label(this) end\n\
+             impl Named for User\n  def label(this) = this.name\nend\n\
+             println(admin.label())\nprintln(implements?(admin, Named))"
+        ),
+        "ana\ntrue\n"
+    );
+}
+
+#[test]
+fn subtypes_must_have_the_fields_of_their_parent() {
+    let error = run("type User(name) end\ntype Admin(perms) extends User end").error();
+    assert_eq!(error.code, "E0213");
+    assert_eq!(
+        error.err,
+        "`Admin` extends `User`, but doesn't have its field `name`"
+    );
+}
+
+#[test]
+fn super_is_only_valid_in_subtypes() {
+    let errors = diagnostics("type User(name)\n  def greet(this) = super.greet()\nend");
+    assert_eq!(
+        errors[0].message,
+        "`super` used outside of the methods of a type that extends another"
+    );
+}
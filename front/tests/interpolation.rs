@@ -0,0 +1,30 @@
+mod common;
+
+use common::{errors, output};
+
+#[test]
+fn expressions_are_embedded_in_strings() {
+    assert_eq!(
+        output("def name = \"ana\"\nprintln(\"Hello ${name}, you are ${20 + 1}\")"),
+        "Hello ana, you are 21\n"
+    );
+}
+
+#[test]
+fn embedded_values_are_converted_to_strings() {
+    assert_eq!(
+        output("println(\"${[1, 2]} ${:a} ${nil}\")"),
+        "[1, 2] :a nil\n"
+    );
+}
+
+#[test]
+fn dollars_can_be_escaped() {
+    assert_eq!(output("println(\"\\${x} $5\")"), "${x} $5\n");
+}
+
+#[test]
+fn errors_inside_embedded_expressions_point_at_them() {
+    let errors = errors("def x = 1\ndef y = \"a ${x +} b\"");
+    assert_eq!(errors, ["[2:17] unexpected token `}`"]);
+}
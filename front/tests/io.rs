@@ -0,0 +1,18 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::{output, run_on};
+use vm::VirtualMachine;
+
+#[test]
+fn print_and_println_write_to_the_vm_stdout() {
+    assert_eq!(output("print(1)\nprint(\"a\")\nprintln(2)"), "1a2\n");
+}
+
+#[test]
+fn input_reads_a_line_from_the_vm_stdin() {
+    let vm = VirtualMachine::default().with_stdin(Cursor::new("ana\nbob\n"));
+    let run = run_on(vm, "println(\"hi \" + input(\"name? \"))");
+    assert_eq!(run.output(), "name? hi ana\n");
+}
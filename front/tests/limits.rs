@@ -0,0 +1,162 @@
+mod common;
+
+use common::{compile, run_on};
+use vm::{ErrorKind, VirtualMachine, VmBuilder};
+
+#[test]
+fn fuel_stops_infinite_loops() {
+    let vm = VirtualMachine::default().with_fuel(200);
+    let run = run_on(vm, "def spin(n) = spin(n + 1)\nspin(0)");
+    assert_eq!(run.error().kind, ErrorKind::OutOfFuel);
+}
+
+#[test]
+fn deep_recursion_overflows_without_crashing_the_host() {
+    let run = run_on(
+        VirtualMachine::default(),
+        "def spin(n) = spin(n + 1)\nspin(0)",
+    );
+    let error = run.error();
+    assert_eq!(error.kind, ErrorKind::StackOverflow);
+    assert_eq!(error.code, "E0215");
+}
+
+#[test]
+fn recursion_with_pending_operations_overflows_too() {
+    let run = run_on(
+        VirtualMachine::default(),
+        "def spin(n) = 1 + spin(n + 1)\nspin(0)",
+    );
+    assert_eq!(run.error().kind, ErrorKind::StackOverflow);
+}
+
+#[test]
+fn recursion_without_locals_stops_at_the_depth_limit() {
+    let run = run_on(VirtualMachine::default(), "def spin() = spin() + 1\nspin()");
+    let error = run.error();
+    assert_eq!(error.kind, ErrorKind::StackOverflow);
+    assert!(error.err.contains("nested calls"), "{}", error.err);
+}
+
+#[test]
+fn ordinary_recursion_reaches_the_default_depth() {
+    let src = "def sum(n) = if n == 0 then 0 else n + sum(n - 1)\nprintln(sum(995))";
+    assert_eq!(run_on(VirtualMachine::default(), src).output(), "495510\n");
+
+    let src = "def sum(n) = if n == 0 then 0 else n + sum(n - 1)\nprintln(sum(1000))";
+    let error = run_on(VirtualMachine::default(), src).error();
+    assert_eq!(error.kind, ErrorKind::StackOverflow);
+    assert!(error.err.contains("nested calls"), "{}", error.err);
+}
+
+#[test]
+fn recursion_through_builtins_reaches_the_depth_limit() {
+    // every call through `map` takes more of the host's stack, so the limit is kept low for the
+    // stack of a test thread
+    let src = "def deep(n) = if n == 0 then 0 else [n - 1].map(deep).head()\nprintln(deep(DEPTH))";
+    let vm = VmBuilder::new().allow_all().max_depth(100).build();
+    assert_eq!(run_on(vm, &src.replace("DEPTH", "98")).output(), "0\n");
+
+    let vm = VmBuilder::new().allow_all().max_depth(100).build();
+    let error = run_on(vm, &src.replace("DEPTH", "100")).error();
+    assert_eq!(error.kind, ErrorKind::StackOverflow);
+    assert!(error.err.contains("nested calls"), "{}", error.err);
+}
+
+#[test]
+fn the_depth_limit_is_configurable() {
+    let src = "def sum(n) = if n == 0 then 0 else n + sum(n - 1)\nprintln(sum(50))";
+
+    let vm = VmBuilder::new().allow_all().max_depth(20).build();
+    assert_eq!(vm.max_depth(), 20);
+    assert_eq!(run_on(vm, src).error().kind, ErrorKind::StackOverflow);
+
+    let vm = VmBuilder::new().allow_all().max_depth(60).build();
+    assert_eq!(run_on(vm, src).output(), "1275\n");
+}
+
+#[test]
+fn failed_calls_dont_leak_depth_or_locals() {
+    let (bytecode, constants) =
+        compile("def fail(n) = if n == 0 then 1 / 0 else fail(n - 1) + 1\nfail(5)");
+    let mut vm = VmBuilder::new().max_depth(10).build();
+    vm.set_consts(constants);
+
+    // without the counters being restored, the depth limit would be hit by the third run
+    for _ in 0..5 {
+        let error = vm.run(&bytecode).unwrap_err();
+        assert_ne!(error.kind, ErrorKind::StackOverflow, "{}", error);
+    }
+}
+
+#[test]
+fn limits_have_codes_of_their_own() {
+    let codes = [
+        ErrorKind::OutOfFuel,
+        ErrorKind::MemoryLimit,
+        ErrorKind::Interrupted,
+        ErrorKind::StackOverflow,
+    ]
+    .map(|it| it.code());
+    assert_eq!(codes, ["E0208", "E0214", "E0209", "E0215"]);
+}
+
+#[test]
+fn huge_ranges_hit_the_memory_limit_before_allocating() {
+    let before = vm::gc::allocated();
+    let vm = VirtualMachine::default().with_memory_limit(1_000_000);
+    let run = run_on(vm, "println(1)\ndef xs = range(0, 100000000)\nprintln(2)");
+
+    assert_eq!(run.stdout, "1\n");
+    assert_eq!(run.error().kind, ErrorKind::MemoryLimit);
+    assert!(vm::gc::allocated() < before + 1_000_000);
+}
+
+#[test]
+fn small_allocations_fit_in_the_memory_limit() {
+    let vm = VirtualMachine::default().with_memory_limit(1_000_000);
+    let run = run_on(vm, "println(range(0, 100).len())");
+    assert_eq!(run.output(), "100\n");
+}
+
+#[test]
+fn builtins_hit_the_memory_limit_before_allocating() {
+    let programs = [
+        "def s = \"ab\".repeat(4000)\ndef xs = s.chars()",
+        "def s = \"ab\".repeat(4000)\ndef t = [s, s, s].join(\"\")",
+        "def s = \"ab\".repeat(4000)\ndef t = s + s + s",
+        "def xs = range(0, 200)\ndef ys = xs.map(fn(x) = x).map(fn(x) = x)",
+        "def s = \"ab\".repeat(4000)\ndef t = format(\"{}{}{}\", [s, s, s])",
+    ];
+
+    for src in programs {
+        let vm = VirtualMachine::default().with_memory_limit(20_000);
+        let error = run_on(vm, src).error();
+        assert_eq!(error.kind, ErrorKind::MemoryLimit, "{}: {}", src, error);
+        // the errors of `gc::reserve` say what was about to be allocated
+        assert!(error.err.contains("allocating"), "{}: {}", src, error);
+    }
+}
+
+#[test]
+fn memory_limits_are_counted_per_vm() {
+    let limited = VirtualMachine::default().with_memory_limit(10_000);
+
+    // another machine of the same thread allocates more than the limit and keeps it
+    let (bytecode, constants) = compile("def xs = range(0, 300)");
+    let mut other = VirtualMachine::default();
+    other.set_consts(constants);
+    other.run(&bytecode).unwrap();
+
+    let run = run_on(limited, "println(range(0, 100).len())");
+    assert_eq!(run.output(), "100\n");
+}
+
+#[test]
+fn interrupted_vms_stop_with_an_error() {
+    let vm = VirtualMachine::default();
+    vm.interrupt_handle().interrupt();
+    let run = run_on(vm, "println(1)");
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.error().kind, ErrorKind::Interrupted);
+}
@@ -0,0 +1,92 @@
+use front::lint::{lint, Config, Level, Rule};
+use vm::Diagnostic;
+
+fn diagnostics(src: &str, config: &Config) -> Vec<Diagnostic> {
+    match lint("test.yex", src, config) {
+        Ok(diagnostics) => diagnostics,
+        Err(errors) => panic!("the program doesn't parse: {:?}", errors),
+    }
+}
+
+fn codes(src: &str) -> Vec<String> {
+    diagnostics(src, &Config::default())
+        .iter()
+        .map(|it| it.code.to_string())
+        .collect()
+}
+
+#[test]
+fn recursive_calls_in_tail_position_are_reported() {
+    let src = "def count(n, acc) = if n == 0 then acc else count(n - 1, acc + 1)";
+    let found = diagnostics(src, &Config::default());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].code, "missed_tail_call");
+    assert_eq!(
+        found[0].message,
+        "recursive call to `count` in tail position"
+    );
+    assert_eq!(
+        found[0].help,
+        ["write it as `become count(n - 1, acc + 1)` to reuse the frame of the function"]
+    );
+}
+
+#[test]
+fn tail_calls_and_calls_outside_of_the_tail_are_fine() {
+    assert!(codes("def count(n) = if n == 0 then 0 else become count(n - 1)").is_empty());
+    assert!(codes("def sum(n) = if n == 0 then 0 else n + sum(n - 1)").is_empty());
+    // a partial application doesn't call the function
+    assert!(codes("def add(a, b) = if a == 0 then b else add(a - 1)").is_empty());
+}
+
+#[test]
+fn shadowed_functions_are_not_recursive() {
+    assert!(codes("def f(f) = f(1)").is_empty());
+    assert!(codes("def f(x) = let f = fn(y) = y in f(x)").is_empty());
+}
+
+#[test]
+fn rules_can_be_allowed() {
+    let config = Config::parse("missed_tail_call = allow").unwrap();
+    let src = "def count(n) = if n == 0 then 0 else count(n - 1)";
+    assert!(diagnostics(src, &config).is_empty());
+
+    let config = Config::default().with_level(Rule::NilComparison, Level::Allow);
+    assert!(diagnostics("def f(x) = x == nil", &config).is_empty());
+}
+
+#[test]
+fn nil_comparisons_are_reported() {
+    assert_eq!(codes("def f(x) = x == nil"), ["nil_comparison"]);
+}
+
+#[test]
+fn extra_arguments_are_errors() {
+    assert_eq!(codes("def f(x) = x\nf(1, 2)"), ["arity_mismatch"]);
+}
+
+#[test]
+fn trailing_suppressions_silence_their_line() {
+    assert!(codes("def f(x) = x == nil // yex-lint: allow(nil_comparison)").is_empty());
+    assert_eq!(
+        codes("def f(x) = x == nil // yex-lint: allow(unused_this)\ndef g(x) = x == nil"),
+        ["nil_comparison", "nil_comparison"]
+    );
+}
+
+#[test]
+fn suppressions_on_their_own_line_silence_the_next_line_of_code() {
+    let src = "// yex-lint: allow(nil_comparison, arity_mismatch)\n\n\
+               def f(x) = x == nil\ndef g(x) = x == nil";
+    let found = diagnostics(src, &Config::default());
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].code, found[0].line), ("nil_comparison", 4));
+}
+
+#[test]
+fn suppressions_of_unknown_rules_are_reported() {
+    assert_eq!(
+        codes("def f(x) = x == nil // yex-lint: allow(nil_comparisons)"),
+        ["unknown_rule", "nil_comparison"]
+    );
+}
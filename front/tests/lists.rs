@@ -0,0 +1,83 @@
+mod common;
+
+use common::{output, run};
+use vm::Value;
+
+#[test]
+fn slicing_and_combining() {
+    assert_eq!(
+        output(
+            "println([1, 2].concat([3]))\nprintln([1, 2, 3].take(2))\nprintln([1, 2, 3].drop(2))\n\
+             println([1, 2, 3].zip([:a, :b]))\nprintln([:a, :b].enumerate())"
+        ),
+        "[1, 2, 3]\n[1, 2]\n[3]\n[[1, :a], [2, :b]]\n[[0, :a], [1, :b]]\n"
+    );
+}
+
+#[test]
+fn higher_order_methods_call_back_into_the_vm() {
+    assert_eq!(
+        output(
+            "println([1, 2].flat_map(fn(x) = [x, x]))\nprintln([1, 2].any?(fn(x) = x > 1))\n\
+             println([1, 2].all?(fn(x) = x > 1))\nprintln([1, 2, 3].find(fn(x) = x > 1))\n\
+             println(range(0, 5).filter(fn(x) = x % 2 == 0).sum())"
+        ),
+        "[1, 1, 2, 2]\ntrue\nfalse\n2\n6\n"
+    );
+}
+
+#[test]
+fn sorting_is_stable() {
+    assert_eq!(
+        output(
+            "println([3, 1, 2].sort())\nprintln([3, 1, 2].sort_by(fn(a, b) = compare(b, a)))\n\
+             println([[1, :a], [0, :b], [1, :c]].sort_by(fn(a, b) = compare(a.head(), b.head())))"
+        ),
+        "[1, 2, 3]\n[3, 2, 1]\n[[0, :b], [1, :a], [1, :c]]\n"
+    );
+}
+
+#[test]
+fn grouping_and_searching() {
+    assert_eq!(
+        output(
+            "println([1, 2, 1].uniq())\nprintln([1, 2, 3].partition(fn(x) = x > 1))\n\
+             println([1, 2, 3].group_by(fn(x) = x % 2))\nprintln([1, 2].index_of(2))\n\
+             println([1, 2].contains?(3))\nprintln([3, 1].min())\nprintln([1, 2].join(\"-\"))"
+        ),
+        "[1, 2]\n[[2, 3], [1]]\n[[1, [1, 3]], [0, [2]]]\n1\nfalse\n1\n1-2\n"
+    );
+}
+
+#[test]
+fn uniq_and_group_by_compare_keys_of_any_kind() {
+    assert_eq!(
+        output(
+            "println([1, 1.0, \"a\", \"a\", :b, :b, [1], [1], 0, -0.0, true, true].uniq())\n\
+             println([1, \"a\", [2], 1.0, \"a\", [2]].group_by(fn(x) = x))"
+        ),
+        "[1, \"a\", :b, [1], 0, true]\n[[1, [1, 1.0]], [\"a\", [\"a\", \"a\"]], [[2], [[2], [2]]]]\n"
+    );
+}
+
+#[test]
+fn errors_in_callbacks_propagate() {
+    let error = run("println([1, 2].map(fn(x) = x + \"a\"))").error();
+    assert_eq!(error.code, "E0205");
+}
+
+#[test]
+fn huge_ranges_are_errors_without_a_memory_limit() {
+    let error = run("println(range(0, 9223372036854775807).len())").error();
+    assert!(error.err.contains("range()"), "{}", error.err);
+}
+
+#[test]
+fn builtin_types_are_shared() {
+    let (a, b) = (Value::Int(1).type_of(), Value::Int(2).type_of());
+    let before = vm::gc::allocated();
+    let _ = Value::List(Default::default()).type_of();
+    let _ = Value::Int(3).type_of();
+    assert_eq!(vm::gc::allocated(), before);
+    assert!(std::ptr::eq(&*a, &*b));
+}
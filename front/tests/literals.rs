@@ -0,0 +1,23 @@
+mod common;
+
+use common::{errors, output};
+
+#[test]
+fn raw_strings_keep_backslashes_and_dollars() {
+    assert_eq!(
+        output("println(r\"a\\d+\\n${x}\")\nprintln(r#\"say \"hi\"\"#)"),
+        "a\\d+\\n${x}\nsay \"hi\"\n"
+    );
+}
+
+#[test]
+fn multi_line_strings_strip_the_common_indentation() {
+    let src = "def query = \"\"\"\n    SELECT *\n      FROM users\n    \"\"\"\nprint(query)";
+    assert_eq!(output(src), "SELECT *\n  FROM users\n");
+}
+
+#[test]
+fn tokens_after_multi_line_strings_keep_their_position() {
+    let errors = errors("def a = \"\"\"\n  x\n  \"\"\"\ndef b = )");
+    assert_eq!(errors[0], "[4:9] unexpected token `)`");
+}
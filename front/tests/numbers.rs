@@ -0,0 +1,65 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn integer_literals() {
+    assert_eq!(
+        output("println(0xff)\nprintln(0o17)\nprintln(0b1010)\nprintln(1_000_000)"),
+        "255\n15\n10\n1000000\n"
+    );
+}
+
+#[test]
+fn integer_division_and_remainder() {
+    assert_eq!(
+        output("println(7 / 2)\nprintln(7 % 3)\nprintln(-7 ~/ 2)"),
+        "3\n1\n-4\n"
+    );
+}
+
+#[test]
+fn mixed_arithmetic_promotes_to_floats() {
+    assert_eq!(
+        output("println(1 + 0.5)\nprintln(typeof(2 * 1.0))"),
+        "1.5\n<type(Float)>\n"
+    );
+}
+
+#[test]
+fn overflowing_integers_become_big_integers() {
+    assert_eq!(
+        output("println(9223372036854775807 + 1)\nprintln(typeof(9223372036854775807 + 1))"),
+        "9223372036854775808\n<type(BigInt)>\n"
+    );
+    assert_eq!(
+        output("println(typeof(9223372036854775807 + 1 - 1))"),
+        "<type(Int)>\n"
+    );
+}
+
+#[test]
+fn every_number_type_extends_num() {
+    assert_eq!(
+        output(
+            "println(is_a?(1, Num))\nprintln(is_a?(1.5, Num))\nprintln(is_a?(2 ** 100, Num))\n\
+             println(is_a?(ratio(1, 3), Num))\nprintln(is_a?(\"1\", Num))"
+        ),
+        "true\ntrue\ntrue\ntrue\nfalse\n"
+    );
+}
+
+#[test]
+fn integer_division_by_zero_is_an_error() {
+    assert!(common::run("println(1 / 0)").result.is_err());
+}
+
+#[test]
+fn num_annotations_accept_every_number() {
+    // the string goes through a function without annotations, so only the VM can reject it
+    let src = "def twice(n: Num) -> Num = n * 2\ndef id(x) = x\nprintln(twice(2))\n\
+               println(twice(1.5))\nprintln(twice(id(\"a\")))";
+    let run = common::run_on(vm::VirtualMachine::default().with_type_checks(), src);
+    assert_eq!(run.stdout, "4\n3.0\n");
+    assert_eq!(run.error().code, "E0210");
+}
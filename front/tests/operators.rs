@@ -0,0 +1,78 @@
+mod common;
+
+use common::{output, run, run_on};
+use vm::{ErrorKind, VirtualMachine};
+
+#[test]
+fn remainder_power_and_floor_division() {
+    assert_eq!(
+        output("println(7 % 3)\nprintln(2 ** 3 ** 2)\nprintln(-2 ** 2)\nprintln(-7 ~/ 2)"),
+        "1\n512\n-4\n-4\n"
+    );
+}
+
+#[test]
+fn remainders_take_the_sign_of_the_divisor() {
+    assert_eq!(
+        output("println(-7 % 2)\nprintln(7 % -2)\nprintln(-7 % -2)\nprintln(-7.5 % 2)"),
+        "1\n-1\n-1\n0.5\n"
+    );
+    assert_eq!(
+        output("println(-(2 ** 70) % 3)\nprintln(ratio(-7, 2) % 2)"),
+        "2\n1/2\n"
+    );
+}
+
+#[test]
+fn floor_division_and_remainder_agree() {
+    let src = "def check(a, b) = a ~/ b * b + a % b == a\n\
+               println([check(-7, 2), check(7, -2), check(-(2 ** 70), 3), check(-7.5, 2)])";
+    assert_eq!(output(src), "[true, true, true, true]\n");
+}
+
+#[test]
+fn integer_division_and_remainder_agree() {
+    assert_eq!(
+        output("println(-7 / 2)\nprintln(7 / -2)\nprintln(-(2 ** 70) / 3 == -(2 ** 70) ~/ 3)"),
+        "-4\n-4\ntrue\n"
+    );
+
+    let src = "def check(a, b) = a / b * b + a % b == a\n\
+               println([check(-7, 2), check(7, -2), check(-7, -2), check(-(2 ** 70), 3)])";
+    assert_eq!(output(src), "[true, true, true, true]\n");
+}
+
+#[test]
+fn negative_exponents_give_ratios() {
+    assert_eq!(
+        output("println(2 ** -1)\nprintln(ratio(2, 3) ** 2)"),
+        "1/2\n4/9\n"
+    );
+}
+
+#[test]
+fn bitwise_not() {
+    assert_eq!(output("println(~~~0)\nprintln(~~~5)"), "-1\n-6\n");
+}
+
+#[test]
+fn powers_of_one_and_zero_never_grow() {
+    assert_eq!(
+        output("println(1 ** 2147483647)\nprintln((-1) ** 2147483647)\nprintln(0 ** 2147483647)"),
+        "1\n-1\n0\n"
+    );
+}
+
+#[test]
+fn huge_powers_are_errors() {
+    let error = run("println(10 ** 2147483647)").error();
+    assert_eq!(error.kind, ErrorKind::Runtime);
+    assert!(error.err.contains("too large"), "{}", error.err);
+}
+
+#[test]
+fn powers_are_checked_against_the_memory_limit() {
+    let vm = VirtualMachine::default().with_memory_limit(100_000);
+    let run = run_on(vm, "println(3 ** 10000000)");
+    assert_eq!(run.error().kind, ErrorKind::MemoryLimit);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn strings_and_symbols_are_ordered_lexicographically() {
+    assert_eq!(
+        output("println(\"a\" < \"b\")\nprintln(\"b\" <= \"ab\")\nprintln(:apple < :banana)"),
+        "true\nfalse\ntrue\n"
+    );
+}
+
+#[test]
+fn lists_are_ordered_element_wise() {
+    assert_eq!(
+        output("println([1, 2] < [1, 3])\nprintln([1, 2] < [1, 2, 0])\nprintln([2] > [1, 9])"),
+        "true\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn booleans_and_mixed_numbers_are_ordered() {
+    assert_eq!(
+        output("println(false < true)\nprintln(1 < 1.5)\nprintln(ratio(1, 2) >= 0.5)"),
+        "true\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn compare_returns_the_sign_of_the_ordering() {
+    assert_eq!(
+        output(
+            "println(compare(1, 2))\nprintln(compare(\"b\", \"b\"))\nprintln(compare([2], [1]))"
+        ),
+        "-1\n0\n1\n"
+    );
+}
+
+#[test]
+fn values_of_different_types_can_not_be_compared() {
+    // the string goes through a function without annotations, so only the VM can reject it
+    let error = run("def id(x) = x\nprintln(1 < id(\"a\"))").error();
+    assert!(error.err.contains("Can't compare"), "{}", error.err);
+}
@@ -0,0 +1,27 @@
+use front::outline::{outline, Item};
+
+fn names(src: &str) -> Vec<String> {
+    outline(src)
+        .unwrap()
+        .into_iter()
+        .map(|item| match item {
+            Item::Def(def) => def.name,
+            Item::Type(ty) => ty.name,
+            Item::Trait(tr) => tr.name,
+        })
+        .collect()
+}
+
+#[test]
+fn entry_points_are_not_outlined() {
+    let src = "/// Prints a greeting\ndef _ = println(\"hi\")\n\
+               def greet(name) = \"hi \" + name\n\
+               def _ = println(greet(\"you\"))";
+    assert_eq!(names(src), ["greet"]);
+}
+
+#[test]
+fn definitions_are_outlined_in_order() {
+    let src = "type Point(x, y) end\ntrait Show = def show(this) end\ndef origin = new Point(0, 0)";
+    assert_eq!(names(src), ["Point", "Show", "origin"]);
+}
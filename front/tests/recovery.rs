@@ -0,0 +1,53 @@
+mod common;
+
+use common::errors;
+
+#[test]
+fn every_syntax_error_is_reported_at_once() {
+    let errors = errors("def x = (1 +)\ndef y = [1 2]\ndef z = 3\ndef w = )\n");
+    assert_eq!(
+        errors,
+        [
+            "[1:13] unexpected token `)`",
+            "[2:12] expected `,` or `]`, found `2`",
+            "[4:9] unexpected token `)`",
+        ]
+    );
+}
+
+#[test]
+fn the_valid_definitions_are_still_checked() {
+    let diagnostics = front::check("test.yex", "def x = (1 +)\ndef y = undefined_name\n");
+    let messages = diagnostics
+        .iter()
+        .map(|it| it.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert!(messages[1].contains("undefined_name"), "{:?}", messages);
+}
+
+#[test]
+fn recovery_synchronizes_on_end() {
+    let errors = errors("type A(x)\n  def f(this) = )\nend\ndef g = ]\n");
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+}
+
+#[test]
+fn errors_in_interpolations_stop_at_the_closing_brace() {
+    assert_eq!(
+        errors("println(\"x ${ 1 + } y\")\nprintln(\"${f(1 } z\")"),
+        [
+            "[1:19] unexpected token `}`",
+            "[2:16] expected `,` or `)`, found `}`",
+        ]
+    );
+}
+
+#[test]
+fn unclosed_strings_are_reported_once() {
+    let found = common::diagnostics("def x = (1 +)\nprintln(\"abc)\n");
+    let codes = found.iter().map(|it| it.code).collect::<Vec<_>>();
+    assert_eq!(codes, ["E0100", "E0002"]);
+    assert_eq!(found[1].message, "unclosed delimiter");
+    assert_eq!(found[1].labels[0].message, "delimiter opened here");
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{diagnostics, output};
+use vm::{EnvTable, Severity, Symbol, Value, VirtualMachine};
+
+#[test]
+fn undefined_names_fail_the_compilation() {
+    let errors = diagnostics("def increment(n) = n + 1\nprintln(incremnt(1))");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "E0301");
+    assert_eq!(errors[0].message, "undefined name `incremnt`");
+    assert_eq!(errors[0].help, ["did you mean `increment`?"]);
+}
+
+#[test]
+fn locals_of_enclosing_functions_can_not_be_used() {
+    let errors = diagnostics("def f(x) = fn(y) = x + y");
+    assert_eq!(errors[0].code, "E0301");
+    assert_eq!(
+        errors[0].labels[0].message,
+        "defined outside of the function"
+    );
+}
+
+#[test]
+fn definitions_can_be_used_before_they_are_defined() {
+    assert_eq!(
+        output("def f(x) = twice(x)\ndef twice(n) = n * 2\nprintln(f(2))"),
+        "4\n"
+    );
+}
+
+#[test]
+fn warnings_do_not_stop_the_compilation() {
+    assert_eq!(output("def f(x) = let y = 1 in x\nprintln(f(2))"), "2\n");
+
+    let warnings = front::check("test.yex", "def f(x) = let y = 1 in let x = 2 in x");
+    let codes = warnings.iter().map(|it| it.code).collect::<Vec<_>>();
+    assert_eq!(codes, ["W0302", "W0302", "W0303"]);
+    assert!(warnings.iter().all(|it| it.severity == Severity::Warning));
+}
+
+#[test]
+fn globals_defined_outside_of_the_program_are_known() {
+    let mut vm = VirtualMachine::default();
+    vm.set_global("answer", Value::Int(42));
+    let empty = EnvTable::new();
+    assert!(front::parse_file_with_globals("test.yex", "println(answer)", &empty).is_err());
+
+    let (bytecode, constants) =
+        front::parse_file_with_globals("test.yex", "println(answer)", vm.globals()).unwrap();
+    vm.set_consts(constants);
+    assert!(vm.run(&bytecode).is_ok());
+    assert!(vm.get_global(Symbol::new("answer")).is_some());
+}
+
+#[test]
+fn expressions_are_resolved_too() {
+    let empty = EnvTable::new();
+    assert!(front::parse_expr_file_with_globals("test.yex", "missing + 1", &empty).is_err());
+    assert!(front::parse_expr_file_with_globals("test.yex", "1 + 1", &empty).is_ok());
+}
+
+#[test]
+fn names_defined_outside_are_only_checked_with_the_globals() {
+    // the globals of an embedder may be set after compiling, so they aren't known by `parse`
+    let (bytecode, constants) = front::parse_file("test.yex", "println(answer)").unwrap();
+    let mut vm = VirtualMachine::default();
+    vm.set_global("answer", Value::Int(42));
+    vm.set_consts(constants);
+    assert!(vm.run(&bytecode).is_ok());
+
+    assert!(front::parse_expr("missing + 1").is_ok());
+    assert!(front::parse("def f(p: Point) = p").is_ok());
+    // the locals of the functions around are never globals
+    assert!(front::parse("def f(x) = fn(y) = x + y").is_err());
+}
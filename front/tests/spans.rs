@@ -0,0 +1,22 @@
+mod common;
+
+use common::{diagnostics, run};
+use vm::Symbol;
+
+#[test]
+fn parse_errors_have_byte_ranges_in_their_file() {
+    let src = "def x = 1\ndef y = (1 +)";
+    let errors = diagnostics(src);
+    let span = errors[0].span;
+    assert_eq!(span.file, Symbol::new("test.yex"));
+    assert_eq!(&src[span.start..span.end], ")");
+}
+
+#[test]
+fn runtime_errors_point_at_the_expression_that_failed() {
+    let src = "def x = 1\nprintln(x + \"a\")";
+    let error = run(src).error();
+    assert_eq!((error.line, error.column), (2, 11));
+    assert_eq!(error.span.file, Symbol::new("test.yex"));
+    assert_eq!(&src[error.span.start..error.span.end], "x + \"a\"");
+}
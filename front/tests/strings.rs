@@ -0,0 +1,81 @@
+mod common;
+
+use common::{output, run, run_on};
+use vm::{gc::GcRef, ErrorKind, Value, VirtualMachine};
+
+#[test]
+fn unicode_aware_methods() {
+    assert_eq!(
+        output(
+            "println(\"héllo\".len())\nprintln(\"héllo\".upper())\nprintln(\"ab\".chars())\n\
+             println(\"héllo\".reverse())\nprintln(\"héllo\".slice(1, 3))"
+        ),
+        "5\nHÉLLO\n[\"a\", \"b\"]\nolléh\nél\n"
+    );
+}
+
+#[test]
+fn the_len_operator_counts_characters_like_len() {
+    let len = |s: &str| Value::Str(GcRef::new(s.to_string())).len();
+    assert_eq!(len("é"), 1);
+    assert_eq!(len("héllo"), 5);
+    assert_eq!(len(""), 0);
+    assert_eq!(output("println(\"héllo\".len())"), "5\n");
+}
+
+#[test]
+fn split_can_keep_the_empty_parts() {
+    assert_eq!(
+        output(
+            "println(\" a,,b \".split(\",\"))\nprintln(\"a,,b\".split(\",\", true))\n\
+             println(\",a\".split(\",\", false))"
+        ),
+        "[\"a\", \"b\"]\n[\"a\", \"\", \"b\"]\n[\"a\"]\n"
+    );
+    let error = run("\"a\".split(\",\", 1)").error();
+    assert_eq!(error.err, "split() expected a boolean, but found `1`");
+    assert_eq!(run("\"a\".split()").error().code, "E0202");
+}
+
+#[test]
+fn trimming_and_searching() {
+    assert_eq!(
+        output(
+            "println(\"  a \".trim() + \"|\")\nprintln(\"abc\".starts_with?(\"ab\"))\n\
+             println(\"abc\".ends_with?(\"b\"))\nprintln(\"abc\".find(\"c\"))"
+        ),
+        "a|\ntrue\nfalse\n2\n"
+    );
+}
+
+#[test]
+fn repeating_and_padding() {
+    assert_eq!(
+        output(
+            "println(\"ab\".repeat(3))\nprintln(\"7\".pad_left(3, \"0\"))\n\
+             println(\"7\".pad_right(3, \".\"))\nprintln(\", \".join([\"a\", \"b\"]))"
+        ),
+        "ababab\n007\n7..\na, b\n"
+    );
+}
+
+#[test]
+fn huge_repeats_and_paddings_are_errors() {
+    for src in [
+        "println(\"ab\".repeat(9223372036854775807))",
+        "println(\"a\".repeat(4611686018427387904))",
+        "println(\"a\".pad_left(9223372036854775807, \"é\"))",
+        "println(\"a\".pad_right(9223372036854775807, \" \"))",
+    ] {
+        let error = run(src).error();
+        assert_eq!(error.kind, ErrorKind::Runtime, "{}", src);
+        assert!(error.err.contains("can't create strings"), "{}", error.err);
+    }
+}
+
+#[test]
+fn repeats_are_checked_against_the_memory_limit() {
+    let vm = VirtualMachine::default().with_memory_limit(100_000);
+    let run = run_on(vm, "println(\"ab\".repeat(1000000))");
+    assert_eq!(run.error().kind, ErrorKind::MemoryLimit);
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use common::{errors, output};
+
+#[test]
+fn tail_calls_reuse_the_frame() {
+    let src = "def count(n, acc) =\n  if n == 0 then acc\n  else let m = n - 1 in become count(m, acc + 1)\n\
+               println(count(10000, 0))";
+    assert_eq!(output(src), "10000\n");
+}
+
+#[test]
+fn become_needs_a_call() {
+    let errors = errors("def f(x) = become x");
+    assert_eq!(errors, ["[1:19] expected a function call after `become`"]);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{diagnostics, output};
+use vm::VirtualMachine;
+
+const SHOW: &str = "trait Show =
+  def show(this)
+  def twice(this) = this.show() + this.show()
+end
+type Point(x, y) end
+impl Show for Point
+  def show(this) = \"a point\"
+end
+";
+
+#[test]
+fn impls_add_the_methods_of_a_trait() {
+    let src = format!("{}println((new Point(1, 2)).show())", SHOW);
+    assert_eq!(output(&src), "a point\n");
+}
+
+#[test]
+fn types_use_the_default_methods() {
+    let src = format!("{}println((new Point(1, 2)).twice())", SHOW);
+    assert_eq!(output(&src), "a pointa point\n");
+}
+
+#[test]
+fn builtin_types_can_implement_traits() {
+    let src = format!(
+        "{}impl Show for List\n  def show(this) = \"a list\"\nend\nprintln([1, 2].twice())",
+        SHOW
+    );
+    assert_eq!(output(&src), "a lista list\n");
+}
+
+#[test]
+fn implements_checks_the_type_of_a_value() {
+    let src = format!(
+        "{}println(implements?(new Point(1, 2), Show))\nprintln(implements?(\"a\", Show))",
+        SHOW
+    );
+    assert_eq!(output(&src), "true\nfalse\n");
+}
+
+#[test]
+fn incomplete_impls_fail_the_compilation() {
+    let src =
+        "trait Show = def show(this) end\ntype P() end\nimpl Show for P\n  def shw(this) = 1\nend";
+    let errors = diagnostics(src);
+    let messages = errors
+        .iter()
+        .map(|it| it.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "`P` doesn't implement `Show`, the method `show` is missing",
+            "`shw` isn't a method of `Show`"
+        ]
+    );
+}
+
+#[test]
+fn incomplete_impls_of_traits_defined_elsewhere_fail_when_they_run() {
+    let mut vm = VirtualMachine::default();
+    let (bytecode, constants) =
+        front::parse_file("test.yex", "trait Show = def show(this) end").unwrap();
+    vm.set_consts(constants);
+    vm.run(&bytecode).unwrap();
+
+    let src = "impl Show for Str\n  def show(this, prefix) = prefix\nend";
+    let (bytecode, constants) =
+        front::parse_file_with_globals("test.yex", src, vm.globals()).unwrap();
+    vm.set_consts(constants);
+    assert_eq!(vm.run(&bytecode).unwrap_err().code, "E0212");
+}
+
+#[test]
+fn declarations_are_found_by_their_first_keyword() {
+    for src in [
+        "def x = 1",
+        "type P() end",
+        "trait Show = def show(this) end",
+        "impl Show for List def show(this) = 1 end",
+        "type Admin(name) extends User end",
+    ] {
+        assert!(front::is_declaration(src), "{}", src);
+    }
+    for src in [
+        "define(1)",
+        "typeof(1)",
+        "traits",
+        "implements?([], Show)",
+        "1 + 1",
+    ] {
+        assert!(!front::is_declaration(src), "{}", src);
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{diagnostics, output, run_on};
+use vm::{EnvTable, VirtualMachine};
+
+#[test]
+fn type_errors_fail_the_compilation() {
+    let errors = diagnostics("println(1 + \"a\")");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "E0304");
+    assert_eq!(errors[0].message, "can't apply `+` to `Int` and `Str`");
+}
+
+#[test]
+fn arguments_must_match_the_annotations() {
+    let errors = diagnostics("def twice(n: Int) -> Int = n * 2\nprintln(twice(\"a\"))");
+    assert_eq!(errors[0].code, "E0304");
+    assert_eq!(
+        errors[0].message,
+        "expected `Int` for the parameter `n`, found `Str`"
+    );
+}
+
+#[test]
+fn unknown_annotations_fail_the_compilation() {
+    let errors = diagnostics("def f(x: Strr) = x");
+    assert_eq!(errors[0].code, "E0307");
+    assert_eq!(errors[0].help, ["did you mean `Str`?"]);
+}
+
+#[test]
+fn unannotated_code_is_dynamic() {
+    assert_eq!(
+        output("def add(a, b) = a + b\nprintln(add(1, 2))\nprintln(add(\"a\", \"b\"))"),
+        "3\nab\n"
+    );
+    assert_eq!(output("println(1 < 1.5)"), "true\n");
+}
+
+#[test]
+fn expressions_are_checked_too() {
+    assert!(front::parse_expr("1 - \"a\"").is_err());
+    assert!(front::parse_expr("1 - 2").is_ok());
+}
+
+#[test]
+fn types_defined_outside_of_the_program_can_be_annotations() {
+    let mut vm = VirtualMachine::default();
+    let (bytecode, constants) = front::parse_file("test.yex", "type Point(x, y) end").unwrap();
+    vm.set_consts(constants);
+    vm.run(&bytecode).unwrap();
+
+    let src = "def x_of(p: Point) = p.x";
+    let empty = EnvTable::new();
+    assert!(front::parse_file_with_globals("test.yex", src, &empty).is_err());
+    assert!(front::parse_file_with_globals("test.yex", src, vm.globals()).is_ok());
+}
+
+#[test]
+fn int_annotations_accept_integers_past_64_bits() {
+    let src = "def fact(n: Int) -> Int = if n < 2 then 1 else n * fact(n - 1)\n\
+               def id(n: Int) -> Int = n\n\
+               println(fact(30))\nprintln(id(2 ** 80))\nprintln(is_a?(2 ** 80, Int))";
+    let run = run_on(VirtualMachine::default().with_type_checks(), src);
+    assert_eq!(
+        run.output(),
+        "265252859812191058636308480000000\n1208925819614629174706176\ntrue\n"
+    );
+}
+
+#[test]
+fn variants_with_fields_are_functions() {
+    let src = "type Shape = Circle(r) | Square end\n\
+               def build(f: Fn) = f(1)\n\
+               println(build(Circle).r)";
+    let run = run_on(VirtualMachine::default().with_type_checks(), src);
+    assert_eq!(run.output(), "1\n");
+
+    let errors = diagnostics("type Shape = Circle(r) | Square end\ndef f(x: Fn) = x\nf(Square)");
+    assert_eq!(errors[0].code, "E0304");
+}
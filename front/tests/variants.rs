@@ -0,0 +1,96 @@
+mod common;
+
+use common::{diagnostics, output, run};
+
+const SHAPE: &str = "type Shape = Circle(r) | Rect(w, h) | Empty
+  def area(this) =
+    if tag(this) == :Circle then 3 * this.r * this.r
+    else if tag(this) == :Rect then this.w * this.h
+    else 0
+end
+";
+
+fn shape(src: &str) -> String {
+    output(&format!("{}{}", SHAPE, src))
+}
+
+#[test]
+fn variants_are_built_by_calling_them() {
+    assert_eq!(
+        shape("println(Rect(2, 3).area())\nprintln(Circle(1).area())\nprintln(Empty.area())"),
+        "6\n3\n0\n"
+    );
+    assert_eq!(shape("println(Circle(1))"), "Circle(1)\n");
+}
+
+#[test]
+fn variants_are_types_of_their_own() {
+    assert_eq!(
+        shape(
+            "println(typeof(Circle(1)) == typeof(Rect(1, 2)))\n\
+             println(typeof(Circle(1)) == typeof(Circle(2)))"
+        ),
+        "false\ntrue\n"
+    );
+    assert_eq!(
+        shape("println(Circle(1) == Circle(1))\nprintln(Circle(1) == Circle(2))"),
+        "true\nfalse\n"
+    );
+}
+
+#[test]
+fn tags_and_variants_can_be_inspected() {
+    assert_eq!(
+        shape("println(tag(Rect(1, 2)))\nprintln(variants(Shape))"),
+        ":Rect\n[<type(Circle)>, <type(Rect)>, <type(Empty)>]\n"
+    );
+    assert_eq!(shape("println(is_a?(Circle(1), Shape))"), "true\n");
+}
+
+#[test]
+fn types_with_variants_can_not_be_instantiated() {
+    let error = run(&format!("{}new Shape()", SHAPE)).error();
+    assert_eq!(error.code, "E0211");
+    assert!(error.err.contains("Circle, Rect, Empty"), "{}", error.err);
+}
+
+#[test]
+fn annotations_accept_every_variant() {
+    let src = "def area(s: Shape) = s.area()\nprintln(area(Rect(1, 2)))\nprintln(area(Empty))";
+    assert_eq!(shape(src), "2\n0\n");
+
+    let errors = diagnostics(&format!("{}def area(s: Shape) = s.area()\narea(1)", SHAPE));
+    assert_eq!(
+        errors[0].message,
+        "expected `Shape` for the parameter `s`, found `Int`"
+    );
+}
+
+#[test]
+fn the_prelude_has_option_and_result() {
+    assert_eq!(
+        output(
+            "println(Some(1).map(fn(x) = x + 1))\nprintln(None.unwrap_or(0))\n\
+             println(Err(\"not found\").ok?())\nprintln(Ok(2).unwrap_or(0))"
+        ),
+        "Some(2)\n0\nfalse\n2\n"
+    );
+}
+
+#[test]
+fn tags_are_built_once_per_variant() {
+    let src = format!(
+        "{}def a = tag(Circle(1))\ndef b = tag(Circle(2))\nprintln(a == b)\nprintln(tag(1))",
+        SHAPE
+    );
+    assert_eq!(output(&src), "true\nnil\n");
+}
+
+#[test]
+fn variants_can_not_be_declared_twice() {
+    let errors = diagnostics("type S = Circle(r) | Circle(w) end");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "E0104");
+    assert_eq!(errors[0].message, "the variant `Circle` is declared twice");
+    assert_eq!(errors[0].labels[0].message, "first declared here");
+}
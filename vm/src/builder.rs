@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufReader},
+};
+
+use crate::{
+    env::EnvTable, limits::Limits, prelude, Symbol, VirtualMachine, LOCALS_SIZE, MAX_DEPTH,
+    STACK_SIZE,
+};
+
+/// A group of builtin functions that can be granted to a [`VirtualMachine`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Pure functions and the builtin types (`str`, `num`, `format`, `List`, ...)
+    Core,
+    /// Console input and output (`print`, `println`, `input`)
+    Io,
+    /// Filesystem access, which has no builtins yet
+    Fs,
+    /// Control over the running process (`exit`)
+    Process,
+    /// Access to the system clock, which has no builtins yet
+    Time,
+}
+
+impl Capability {
+    /// Every capability group
+    pub const ALL: [Capability; 5] = [
+        Capability::Core,
+        Capability::Io,
+        Capability::Fs,
+        Capability::Process,
+        Capability::Time,
+    ];
+
+    /// Returns the name of the capability
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Core => "core",
+            Capability::Io => "io",
+            Capability::Fs => "fs",
+            Capability::Process => "process",
+            Capability::Time => "time",
+        }
+    }
+
+    /// Returns the builtins of the capability group
+    pub fn builtins(&self) -> EnvTable {
+        prelude::prelude(*self)
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Builds a [`VirtualMachine`] exposing only the builtins of the granted capabilities, by
+/// default only [`Capability::Core`] is granted
+#[derive(Debug, Clone)]
+pub struct VmBuilder {
+    capabilities: Vec<Capability>,
+    max_depth: usize,
+}
+
+impl Default for VmBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VmBuilder {
+    /// Creates a new builder, granting only the [`Capability::Core`] group
+    pub fn new() -> Self {
+        Self {
+            capabilities: vec![Capability::Core],
+            max_depth: MAX_DEPTH,
+        }
+    }
+
+    #[must_use]
+    /// Grants a capability group
+    pub fn allow(mut self, capability: Capability) -> Self {
+        if !self.capabilities.contains(&capability) {
+            self.capabilities.push(capability);
+        }
+        self
+    }
+
+    #[must_use]
+    /// Grants every capability group
+    pub fn allow_all(mut self) -> Self {
+        for capability in Capability::ALL {
+            self = self.allow(capability);
+        }
+        self
+    }
+
+    #[must_use]
+    /// Revokes a capability group
+    pub fn deny(mut self, capability: Capability) -> Self {
+        self.capabilities.retain(|it| *it != capability);
+        self
+    }
+
+    #[must_use]
+    /// Sets how many functions can be running at once, calling more raises a stack overflow
+    /// error. The stack of values and the locals get room for every function that can be
+    /// running, so recursion can reach the limit.
+    ///
+    /// Every call also takes a frame of the host's stack, more when it goes through a builtin
+    /// like `map`, so raising the limit may need a thread with a bigger stack. The default fits
+    /// in the stack of a main thread
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Builds the virtual machine
+    pub fn build(self) -> VirtualMachine {
+        let mut globals = EnvTable::with_capacity(64);
+        let mut denied = Vec::new();
+
+        for capability in Capability::ALL {
+            let builtins = prelude::prelude(capability);
+
+            if self.capabilities.contains(&capability) {
+                for (name, value) in builtins.iter() {
+                    globals.insert(name, value);
+                }
+            } else {
+                denied.extend(builtins.iter().map(|(name, _)| (name, capability)));
+            }
+        }
+
+        VirtualMachine {
+            stack: Vec::with_capacity(STACK_SIZE),
+            locals: Vec::with_capacity(LOCALS_SIZE),
+            used_locals: 0,
+            depth: 0,
+            max_depth: self.max_depth,
+            constants: Vec::new(),
+            globals,
+            denied,
+            stdout: Box::new(io::stdout()),
+            stdin: Box::new(BufReader::new(io::stdin())),
+            limits: Limits::default(),
+            check_types: false,
+            impls: HashMap::new(),
+        }
+    }
+}
+
+impl VirtualMachine {
+    /// Returns the capability that would have defined the given global, if it wasn't granted
+    pub(crate) fn denied_capability(&self, name: Symbol) -> Option<Capability> {
+        self.denied
+            .iter()
+            .find(|(denied, _)| *denied == name)
+            .map(|(_, capability)| *capability)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::Span;
+
+/// A secondary message attached to a range of the source, like the place where an unclosed
+/// delimiter was opened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// The range the label points to
+    pub span: Span,
+    /// The text shown next to the range
+    pub message: String,
+}
+
+impl Label {
+    /// Creates a new [`Label`]
+    pub fn new<T: Into<String>>(span: Span, message: T) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Severity {
+    /// The program can't be compiled or it failed while running
+    #[default]
+    Error,
+    /// The program runs, but it's probably not doing what it should
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// An error or a warning, with everything needed to show it along with the source code that
+/// caused it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The stable code of the error, like `E0002`
+    pub code: &'static str,
+    /// Whether the diagnostic is an error or a warning
+    pub severity: Severity,
+    /// The error message
+    pub message: String,
+    /// The range of the source that caused the error
+    pub span: Span,
+    /// The line where the error happened, used when the source isn't available
+    pub line: usize,
+    /// The column where the error happened, used when the source isn't available
+    pub column: usize,
+    /// Other ranges of the source related to the error
+    pub labels: Vec<Label>,
+    /// Suggestions on how to fix the error
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates a new error [`Diagnostic`] without labels or help notes
+    pub fn new<T: Into<String>>(
+        code: &'static str,
+        message: T,
+        span: Span,
+        line: usize,
+        column: usize,
+    ) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            line,
+            column,
+            labels: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    /// Turns the diagnostic into a warning
+    #[must_use]
+    pub fn warning(mut self) -> Self {
+        self.severity = Severity::Warning;
+        self
+    }
+
+    /// Checks if the diagnostic is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Adds a secondary label to the diagnostic
+    #[must_use]
+    pub fn with_label<T: Into<String>>(mut self, span: Span, message: T) -> Self {
+        self.labels.push(Label::new(span, message));
+        self
+    }
+
+    /// Adds a help note to the diagnostic
+    #[must_use]
+    pub fn with_help<T: Into<String>>(mut self, help: T) -> Self {
+        self.help.push(help.into());
+        self
+    }
+}
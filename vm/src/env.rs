@@ -17,7 +17,7 @@ struct Entry {
     pub value: Value,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 /// A table of key-value pairs
 pub struct EnvTable {
     capacity: usize,
@@ -154,10 +154,10 @@ impl EnvTable {
         self.count == 0
     }
 
-    /// Iterates over the table
-    fn iter(&self) -> impl Iterator<Item = (Key, Value)> + '_ {
+    /// Iterates over the table, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Key, Value)> + '_ {
         unsafe {
-            slice::from_raw_parts(self.entries, self.count)
+            slice::from_raw_parts(self.entries, self.capacity)
                 .iter()
                 .filter(|it| it.key.is_some())
                 .map(|it| (it.key.unwrap(), it.value.clone()))
@@ -179,6 +179,17 @@ impl std::fmt::Display for EnvTable {
     }
 }
 
+impl Clone for EnvTable {
+    // the entries are copied, sharing them would free them twice
+    fn clone(&self) -> Self {
+        let mut table = Self::with_capacity(self.capacity);
+        for (key, value) in self.iter() {
+            table.insert(key, value);
+        }
+        table
+    }
+}
+
 impl Default for EnvTable {
     fn default() -> Self {
         Self::new()
@@ -203,6 +214,6 @@ impl PartialEq for EnvTable {
         }
 
         self.iter()
-            .all(|(key, value)| other.get(&key).map_or(false, |v| value == v))
+            .all(|(key, value)| other.get(&key).is_some_and(|v| value == v))
     }
 }
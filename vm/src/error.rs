@@ -1,10 +1,72 @@
 use std::fmt;
 
+use crate::{Capability, Diagnostic, Span};
+
+/// The kind of an [`InterpretError`], makes it possible to distinguish runtime errors from
+/// errors that are only used to unwind the virtual machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A runtime error, raised by the virtual machine or by the `raise` function
+    Runtime,
+    /// The script called `exit`, with the given exit code
+    Exit(i32),
+    /// The virtual machine ran out of its instruction budget
+    OutOfFuel,
+    /// The virtual machine allocated more memory than it was allowed to
+    MemoryLimit,
+    /// The program nested too many calls or used too many values at once
+    StackOverflow,
+    /// The execution was stopped through an [`crate::InterruptHandle`]
+    Interrupted,
+    /// The script used a builtin from a capability that wasn't granted to the virtual machine
+    NotGranted(Capability),
+}
+
+impl ErrorKind {
+    /// Returns the error code used for the errors of this kind that don't have a more specific
+    /// one
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Runtime | Self::Exit(_) => "E0200",
+            Self::NotGranted(_) => "E0207",
+            Self::OutOfFuel => "E0208",
+            Self::Interrupted => "E0209",
+            Self::MemoryLimit => "E0214",
+            Self::StackOverflow => "E0215",
+        }
+    }
+}
+
+/// An error that stopped the execution of the virtual machine
 #[derive(Debug)]
 pub struct InterpretError {
+    /// The error message
     pub err: String,
+    /// The line of the instruction that raised the error
     pub line: usize,
+    /// The column of the instruction that raised the error
     pub column: usize,
+    /// The source code that the instruction that raised the error was compiled from
+    pub span: Span,
+    /// The stable code of the error, like `E0201`
+    pub code: &'static str,
+    /// The error kind
+    pub kind: ErrorKind,
+}
+
+impl InterpretError {
+    /// Returns the exit code if the error was caused by a call to `exit`
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.kind {
+            ErrorKind::Exit(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns the error as a diagnostic, which can be rendered with its source code
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.code, &*self.err, self.span, self.line, self.column)
+    }
 }
 
 impl fmt::Display for InterpretError {
@@ -12,4 +74,6 @@ impl fmt::Display for InterpretError {
         write!(f, "[{}:{}] {}", self.line, self.column, self.err)
     }
 }
+
+/// The result of running yex bytecode
 pub type InterpretResult<T> = Result<T, InterpretError>;
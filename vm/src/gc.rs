@@ -1,16 +1,160 @@
-use std::{cell::Cell, fmt::Debug, ptr::NonNull};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    mem,
+    ptr::NonNull,
+    rc::Rc,
+};
+
+use crate::{
+    error::{ErrorKind, InterpretResult},
+    literal::{instance::Instance, list::Node, mutable::Mutable, traits::Trait},
+    raise, Fn, FnKind, YexType,
+};
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+    static ACTIVE: RefCell<Option<Rc<Heap>>> = const { RefCell::new(None) };
+}
+
+/// The memory taken by the values a virtual machine with a memory limit created, every value is
+/// charged to the heap of the machine that was running when it was allocated
+#[derive(Debug)]
+pub(crate) struct Heap {
+    allocated: Cell<usize>,
+    limit: usize,
+}
+
+impl Heap {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            allocated: Cell::new(0),
+            limit,
+        }
+    }
+
+    /// The number of bytes taken by the values that are still alive
+    pub fn allocated(&self) -> usize {
+        self.allocated.get()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+/// Returns the number of bytes currently allocated through [`GcRef`] on this thread
+pub fn allocated() -> usize {
+    ALLOCATED.with(Cell::get)
+}
+
+/// Checks that `bytes` more bytes fit in the memory limit of the running virtual machine,
+/// raising an [`ErrorKind::MemoryLimit`] error before anything is allocated otherwise
+pub fn reserve(bytes: usize) -> InterpretResult<()> {
+    let Some((used, limit)) =
+        ACTIVE.with_borrow(|it| it.as_ref().map(|heap| (heap.allocated(), heap.limit())))
+    else {
+        return Ok(());
+    };
+
+    match used.checked_add(bytes) {
+        Some(total) if total <= limit => Ok(()),
+        _ => raise!(
+            @ErrorKind::MemoryLimit;
+            "Memory limit exceeded (allocating {} bytes with {} of {} bytes left)",
+            bytes,
+            limit.saturating_sub(used),
+            limit
+        ),
+    }
+}
+
+/// Checks that `count` values of type `T` plus `extra` bytes of heap memory can be allocated
+/// through [`GcRef`], see [`reserve`]
+pub fn reserve_refs<T>(count: usize, extra: usize) -> InterpretResult<()> {
+    reserve(
+        count
+            .saturating_mul(size_of_ref::<T>())
+            .saturating_add(extra),
+    )
+}
+
+/// Returns the number of bytes a value of type `T` takes when moved to the heap by [`GcRef`],
+/// besides the memory it owns
+pub fn size_of_ref<T>() -> usize {
+    mem::size_of::<Ref<T>>()
+}
+
+/// Sets the heap the values allocated on this thread are charged to, returning the previous one
+pub(crate) fn set_active(heap: Option<Rc<Heap>>) -> Option<Rc<Heap>> {
+    ACTIVE.with(|it| it.replace(heap))
+}
+
+/// Values that own heap memory besides their own size, used to keep track of the memory
+/// allocated by the virtual machine
+pub trait HeapSize {
+    /// The number of bytes owned by the value outside of it
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for FnKind {
+    fn heap_size(&self) -> usize {
+        match self {
+            FnKind::Bytecode(bt) => bt.capacity() * mem::size_of::<crate::OpCodeMetadata>(),
+            FnKind::Native(_) => 0,
+        }
+    }
+}
+
+impl HeapSize for num_bigint::BigInt {
+    fn heap_size(&self) -> usize {
+        (self.bits() / 8) as usize
+    }
+}
+
+impl HeapSize for num_rational::BigRational {
+    fn heap_size(&self) -> usize {
+        self.numer().heap_size() + self.denom().heap_size()
+    }
+}
+
+impl HeapSize for Fn {}
+impl HeapSize for YexType {}
+impl HeapSize for Instance {}
+impl HeapSize for Node {}
+impl HeapSize for Mutable {}
+impl HeapSize for Trait {}
 
 struct Ref<T> {
     pub(in crate::gc) inner: T,
     pub(in crate::gc) count: Cell<usize>,
+    pub(in crate::gc) size: usize,
+    pub(in crate::gc) heap: Option<Rc<Heap>>,
 }
 
 pub struct GcRef<T> {
     inner: NonNull<Ref<T>>,
 }
 
-impl<T> GcRef<T> {
+impl<T: HeapSize> GcRef<T> {
+    /// Moves the value to the heap, its size is charged to [`allocated`] and to the heap of the
+    /// running virtual machine before boxing it
     pub fn new(constant: T) -> Self {
+        let size = size_of_ref::<T>() + constant.heap_size();
+        ALLOCATED.with(|it| it.set(it.get() + size));
+        let heap = ACTIVE.with_borrow(Clone::clone);
+        if let Some(heap) = &heap {
+            heap.allocated.set(heap.allocated() + size);
+        }
+
         // SAFETY:
         // We pass the box to into_raw after the allocation, everything is properly aligned and
         // nothing can be null
@@ -19,11 +163,15 @@ impl<T> GcRef<T> {
                 inner: NonNull::new_unchecked(Box::into_raw(Box::new(Ref {
                     inner: constant,
                     count: Cell::new(1),
+                    size,
+                    heap,
                 }))),
             }
         }
     }
+}
 
+impl<T> GcRef<T> {
     fn from_inner(inner: NonNull<Ref<T>>) -> Self {
         Self { inner }
     }
@@ -72,6 +220,11 @@ impl<T> Drop for GcRef<T> {
         self.dec_ref();
 
         if self.ref_count() == 0 {
+            let (size, heap) = unsafe { (self.inner.as_ref().size, &self.inner.as_ref().heap) };
+            ALLOCATED.with(|it| it.set(it.get().saturating_sub(size)));
+            if let Some(heap) = heap {
+                heap.allocated.set(heap.allocated().saturating_sub(size));
+            }
             unsafe { drop(Box::from_raw(self.inner.as_ptr())) };
         }
     }
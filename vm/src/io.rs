@@ -0,0 +1,44 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// An in-memory writer that can be cloned and handed to the [`crate::VirtualMachine`], while the
+/// host keeps another handle to read what was written
+#[derive(Debug, Clone, Default)]
+pub struct SharedBuffer {
+    inner: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SharedBuffer {
+    /// Creates a new, empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the bytes written so far
+    pub fn bytes(&self) -> Vec<u8> {
+        self.inner.borrow().clone()
+    }
+
+    /// Returns the written bytes as a string, replacing invalid UTF-8 sequences
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.inner.borrow()).into_owned()
+    }
+
+    /// Discards everything written so far
+    pub fn clear(&self) {
+        self.inner.borrow_mut().clear();
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -3,76 +3,239 @@
 #![deny(clippy::all)]
 #![allow(clippy::unit_arg)]
 //! Virtual Machine implementation for the yex programming language
+mod builder;
+mod diagnostic;
 mod env;
 mod error;
 #[doc(hidden)]
 pub mod gc;
+mod io;
+mod limits;
 mod literal;
 mod opcode;
 mod prelude;
+mod span;
 mod stack;
 
 use gc::GcRef;
+use limits::Limits;
 use literal::{
     fun::{FnArgs, NativeFn},
-    yextype::instantiate,
+    traits::{self, Impls},
+    yextype::{extend, instantiate},
 };
 
-use crate::error::InterpretResult;
-
 pub use crate::{
+    builder::{Capability, VmBuilder},
+    diagnostic::{Diagnostic, Label, Severity},
     env::EnvTable,
+    error::{ErrorKind, InterpretError, InterpretResult},
+    io::SharedBuffer,
+    limits::InterruptHandle,
     literal::{
+        doc::Doc,
         fun::{Fn, FnKind},
         list::List,
         symbol::Symbol,
+        traits::Trait,
         yextype::YexType,
         Value,
     },
     opcode::{OpCode, OpCodeMetadata},
+    span::Span,
     stack::StackVec,
 };
+pub use num_bigint::BigInt;
+pub use num_rational::BigRational;
 
+/// How many values the stack can hold outside of functions
 const STACK_SIZE: usize = 512;
-const NIL: Value = Value::Nil;
+/// How many more values the stack can hold for every running function
+const FRAME_STACK: usize = 8;
+/// How many locals can be saved outside of functions
+const LOCALS_SIZE: usize = 1024;
+/// How many more locals can be saved for every running function
+const FRAME_LOCALS: usize = 16;
+/// How many functions can be running at once by default, see [`VmBuilder::max_depth`]
+const MAX_DEPTH: usize = 1000;
 
 static mut LINE: usize = 1;
 static mut COLUMN: usize = 1;
+static mut SPAN: Span = Span::new(Span::unnamed(), 0, 0);
 
+/// Raises an error at the current instruction, the error code is taken from the kind unless
+/// it's given, like `raise!(E0201; "message")`
 #[macro_export]
 #[doc(hidden)]
 macro_rules! raise {
-    ($($tt:tt)+) => {
+    (@$kind:expr, $code:expr; $($tt:tt)+) => {
         unsafe {
             let msg = format!($($tt)+);
-            Err($crate::error::InterpretError { line: $crate::LINE, column: $crate::COLUMN, err: msg })
+            Err($crate::error::InterpretError {
+                line: $crate::LINE,
+                column: $crate::COLUMN,
+                span: $crate::SPAN,
+                code: $code,
+                err: msg,
+                kind: $kind,
+            })
         }
-    }
+    };
+    (@$kind:expr; $($tt:tt)+) => {
+        $crate::raise!(@$kind, $kind.code(); $($tt)+)
+    };
+    ($code:ident; $($tt:tt)+) => {
+        $crate::raise!(@$crate::error::ErrorKind::Runtime, stringify!($code); $($tt)+)
+    };
+    ($($tt:tt)+) => {
+        $crate::raise!(@$crate::error::ErrorKind::Runtime; $($tt)+)
+    };
 }
 
-type Stack = StackVec<Value, STACK_SIZE>;
+type Stack = Vec<Value>;
 
 /// Bytecode for the virtual machine, contains the instructions to be executed and the constants to
 /// be loaded
 pub type Bytecode = Vec<OpCodeMetadata>;
 
 type BytecodeRef<'a> = &'a Bytecode;
-use std::{mem::swap, ops};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    mem::swap,
+    ops,
+};
 /// Implements the Yex virtual machine, which runs the [`crate::OpCode`] instructions in a stack
 /// model
 pub struct VirtualMachine {
     stack: Stack,
-    locals: [Value; 1024],
+    /// The locals of the running functions, grown as they are saved
+    locals: Vec<Value>,
     used_locals: usize,
+    /// How many functions are running
+    depth: usize,
+    /// How many functions can be running at once
+    max_depth: usize,
     constants: Vec<Value>,
     globals: EnvTable,
+    denied: Vec<(Symbol, Capability)>,
+    stdout: Box<dyn Write>,
+    stdin: Box<dyn BufRead>,
+    limits: Limits,
+    check_types: bool,
+    /// The methods added to the types by `impl` blocks, by the name of the type
+    impls: HashMap<Symbol, Impls>,
 }
 
 impl VirtualMachine {
-    /// Reset the instruction pointer and the stack
-    pub fn reset(&mut self) {
-        self.stack = stackvec![];
+    #[must_use]
+    /// Sets the writer used by the output functions of the prelude (`print`, `println`, ...)
+    pub fn with_stdout(mut self, stdout: impl Write + 'static) -> Self {
+        self.set_stdout(stdout);
+        self
+    }
+
+    #[must_use]
+    /// Sets the reader used by the input functions of the prelude (`input`, ...)
+    pub fn with_stdin(mut self, stdin: impl BufRead + 'static) -> Self {
+        self.set_stdin(stdin);
+        self
+    }
+
+    /// Replaces the writer used by the output functions of the prelude
+    pub fn set_stdout(&mut self, stdout: impl Write + 'static) {
+        self.stdout = Box::new(stdout);
+    }
+
+    /// Replaces the reader used by the input functions of the prelude
+    pub fn set_stdin(&mut self, stdin: impl BufRead + 'static) {
+        self.stdin = Box::new(stdin);
+    }
+
+    #[must_use]
+    /// Limits the number of instructions the virtual machine can execute
+    pub fn with_fuel(mut self, fuel: usize) -> Self {
+        self.set_fuel(Some(fuel));
+        self
+    }
+
+    #[must_use]
+    /// Limits the number of bytes taken by the values the virtual machine creates from now on,
+    /// which are counted apart from the ones of other machines of the same thread.
+    ///
+    /// The builtins that build strings and lists check the limit before allocating them, the
+    /// other values are checked before the next instruction, so they can go over the limit by
+    /// the size of a single value
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.limits.set_memory(bytes);
+        self
+    }
+
+    /// Sets the instruction budget, `None` removes the limit
+    pub fn set_fuel(&mut self, fuel: Option<usize>) {
+        self.limits.fuel = fuel;
+    }
+
+    /// Returns how many instructions can still be executed, if there is a limit
+    pub fn fuel(&self) -> Option<usize> {
+        self.limits.fuel
+    }
 
+    #[must_use]
+    /// Makes the virtual machine check the type annotations of the parameters, the return
+    /// values and the fields of the types, raising an error when a value doesn't match them
+    pub fn with_type_checks(mut self) -> Self {
+        self.set_type_checks(true);
+        self
+    }
+
+    /// Enables or disables the checks of the type annotations, they are disabled by default
+    pub fn set_type_checks(&mut self, enabled: bool) {
+        self.check_types = enabled;
+    }
+
+    /// Returns whether the type annotations are checked while the program runs
+    pub fn checks_types(&self) -> bool {
+        self.check_types
+    }
+
+    fn expect_type(&self, value: &Value, ty: Symbol) -> InterpretResult<()> {
+        if !self.check_types || value.has_type(ty) {
+            return Ok(());
+        }
+
+        raise!(
+            E0210;
+            "Expected a value of type `{}`, found `{}` of type `{}`",
+            ty,
+            value,
+            value.type_of().name
+        )
+    }
+
+    /// Returns how many functions can be running at once, see [`VmBuilder::max_depth`]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Returns a handle that can be used to interrupt the virtual machine from another thread
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.limits.handle()
+    }
+
+    pub(crate) fn stdout(&mut self) -> &mut dyn Write {
+        &mut *self.stdout
+    }
+
+    pub(crate) fn stdin(&mut self) -> &mut dyn BufRead {
+        &mut *self.stdin
+    }
+
+    /// Reset the instruction pointer, the stack and the locals
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.used_locals = 0;
+        self.depth = 0;
     }
 
     /// sets the constants for execution
@@ -85,6 +248,11 @@ impl VirtualMachine {
         self.stack.last().unwrap_or(&Value::Nil)
     }
 
+    /// Returns the global variables, the builtins of the granted capabilities among them
+    pub fn globals(&self) -> &EnvTable {
+        &self.globals
+    }
+
     /// Get the value of a global variable
     pub fn get_global<T: Into<Symbol>>(&self, name: T) -> Option<Value> {
         self.globals.get(&name.into())
@@ -97,212 +265,394 @@ impl VirtualMachine {
 
     /// Executes a given set of bytecode instructions
     pub fn run(&mut self, bytecode: BytecodeRef) -> InterpretResult<()> {
+        let _heap = self.limits.enter();
+        // the locals saved by the frame are dropped when it ends, even if it fails
+        let base = self.used_locals;
+        let result = self.run_frame(bytecode, base);
+        self.used_locals = base;
+        result
+    }
+
+    /// Runs the instructions of a frame whose locals start at `base`. Only the jumps and the
+    /// calls are run here, the rest are run by [`Self::execute`], so every nested call takes as
+    /// little of the host's stack as possible
+    fn run_frame(&mut self, bytecode: BytecodeRef, base: usize) -> InterpretResult<()> {
         let mut ip = 0;
-        let mut frame_locals = 0;
-
-        while ip < bytecode.len() {
-            let bytecode = &*bytecode;
-            let op = unsafe {
-                let op = bytecode[ip];
-                LINE = op.line;
-                COLUMN = op.column;
-                op.opcode
+
+        while let Some(meta) = bytecode.get(ip) {
+            // a single result for every instruction takes less of the host's stack than a `?` for
+            // each of them
+            let result = match self.prepare(meta) {
+                Ok(()) => match meta.opcode {
+                    OpCode::Halt => return Ok(()),
+
+                    // jump instructions
+                    OpCode::Jmp(offset) => {
+                        ip = offset;
+                        continue;
+                    }
+                    OpCode::Jmf(offset) => {
+                        if !self.pop().to_bool() {
+                            ip = offset;
+                            continue;
+                        }
+                        Ok(())
+                    }
+
+                    // function calls
+                    OpCode::Call(arity) => self.call(arity),
+                    OpCode::TCall(arity) => match self.valid_tail_call(arity, bytecode) {
+                        Ok(()) => {
+                            // the arguments are saved again, in the locals of the frame being
+                            // replaced
+                            self.used_locals = base;
+                            ip = 0;
+                            continue;
+                        }
+                        error => error,
+                    },
+                    OpCode::Invk(name, arity) => self.invoke(name, arity),
+                    OpCode::Super(name, arity) => self.super_call(name, arity),
+
+                    op => self.execute(op, base),
+                },
+                error => error,
             };
 
-            self.debug_stack(&op);
+            match result {
+                Ok(()) => ip += 1,
+                error => return error,
+            }
+        }
 
-            match op {
-                OpCode::Halt => return Ok(()),
+        Ok(())
+    }
 
-                // Stack manipulation
-                OpCode::Push(value) => {
-                    let value = self.constants[value].clone();
-                    self.push(value);
-                }
-                OpCode::Pop => {
-                    self.pop();
-                }
+    /// Records the position of an instruction and checks the limits before running it
+    #[inline(never)]
+    fn prepare(&mut self, meta: &OpCodeMetadata) -> InterpretResult<()> {
+        unsafe {
+            LINE = meta.line;
+            COLUMN = meta.column;
+            SPAN = meta.span;
+        }
 
-                OpCode::Dup => {
-                    let value = self.pop();
-                    self.push(value.clone());
-                    self.push(value);
-                }
+        self.debug_stack(&meta.opcode);
+        self.limits.check()?;
+        // every instruction leaves at most one more value on the stack
+        if self.stack.len() >= self.stack_limit() {
+            return self.overflow("too many values on the stack");
+        }
+        Ok(())
+    }
 
-                OpCode::Rev => {
-                    let (a, b) = self.pop_two();
-                    self.push(b);
-                    self.push(a);
-                }
+    /// Runs an instruction that doesn't call functions or jump
+    #[inline(never)]
+    fn execute(&mut self, op: OpCode, base: usize) -> InterpretResult<()> {
+        match op {
+            // Stack manipulation
+            OpCode::Push(value) => {
+                let value = self.constants[value].clone();
+                self.push(value);
+            }
+            OpCode::Pop => {
+                self.pop();
+            }
 
-                // jump instructions
-                OpCode::Jmp(offset) => {
-                    ip = offset;
-                    continue;
-                }
-                OpCode::Jmf(offset) => {
-                    if !self.pop().to_bool() {
-                        ip = offset;
-                        continue;
-                    }
-                }
+            OpCode::Dup => {
+                let value = self.pop();
+                self.push(value.clone());
+                self.push(value);
+            }
 
-                // function calls
-                OpCode::Call(arity) => self.call(arity)?,
-                OpCode::TCall(arity) => {
-                    self.valid_tail_call(arity, bytecode)?;
-                    ip = 0;
-                    continue;
-                }
+            OpCode::Rev => {
+                let (a, b) = self.pop_two();
+                self.push(b);
+                self.push(a);
+            }
 
-                // mathematical operators
-                OpCode::Add => self.binop(|a, b| a + b)?,
-                OpCode::Sub => self.binop(|a, b| a - b)?,
-                OpCode::Mul => self.binop(|a, b| a * b)?,
-                OpCode::Div => self.binop(|a, b| a / b)?,
-                OpCode::Rem => self.binop(|a, b| a % b)?,
-
-                // bitwise operators
-                OpCode::BitAnd => self.binop(|a, b| a & b)?,
-                OpCode::BitOr => self.binop(|a, b| a | b)?,
-                OpCode::Xor => self.binop(|a, b| a ^ b)?,
-                OpCode::Shl => self.binop(|a, b| a << b)?,
-                OpCode::Shr => self.binop(|a, b| a >> b)?,
-
-                // comparison operators
-                OpCode::Eq => self.binop(|a, b| Ok(a == b))?,
-                OpCode::Less => {
-                    let (a, b) = self.pop_two();
-                    self.push(a.ord_cmp(&b)?.is_lt().into());
-                }
-                OpCode::LessEq => {
-                    let (a, b) = self.pop_two();
-                    self.push(a.ord_cmp(&b)?.is_le().into());
-                }
+            // mathematical operators
+            OpCode::Add => self.binop(|a, b| a + b)?,
+            OpCode::Sub => self.binop(|a, b| a - b)?,
+            OpCode::Mul => self.binop(|a, b| a * b)?,
+            OpCode::Div => self.binop(|a, b| a / b)?,
+            OpCode::Rem => self.binop(|a, b| a % b)?,
+            OpCode::Pow => self.binop(|a, b| a.pow(b))?,
+            OpCode::FloorDiv => self.binop(|a, b| a.floor_div(b))?,
+
+            // bitwise operators
+            OpCode::BitAnd => self.binop(|a, b| a & b)?,
+            OpCode::BitOr => self.binop(|a, b| a | b)?,
+            OpCode::Xor => self.binop(|a, b| a ^ b)?,
+            OpCode::Shl => self.binop(|a, b| a << b)?,
+            OpCode::Shr => self.binop(|a, b| a >> b)?,
+
+            // comparison operators
+            OpCode::Eq => self.binop(|a, b| Ok(a.equals(&b)))?,
+            OpCode::Less => {
+                let (a, b) = self.pop_two();
+                self.push(a.compare(&b)?.is_lt().into());
+            }
+            OpCode::LessEq => {
+                let (a, b) = self.pop_two();
+                self.push(a.compare(&b)?.is_le().into());
+            }
 
-                // unary operators
-                OpCode::Not => {
-                    let value = self.pop();
-                    self.push(!value);
-                }
-                OpCode::Len => {
-                    let value = self.pop();
-                    self.push(Value::Num(value.len() as f64));
-                }
-                OpCode::Neg => {
-                    let value = self.pop();
-                    self.try_push(-value)?;
-                }
+            // unary operators
+            OpCode::Not => {
+                let value = self.pop();
+                self.push(!value);
+            }
+            OpCode::Len => {
+                let value = self.pop();
+                self.push(Value::Int(value.len() as i64));
+            }
+            OpCode::Neg => {
+                let value = self.pop();
+                self.try_push(-value)?;
+            }
+            OpCode::BitNot => {
+                let value = self.pop();
+                self.try_push(value.bit_not())?;
+            }
 
-                // locals manipulation
-                OpCode::Load(offset) => {
-                    let value = self.locals[offset + self.used_locals - frame_locals].clone();
-                    self.push(value);
-                }
-                OpCode::Save(offset) => {
-                    let value = self.pop();
+            // locals manipulation
+            OpCode::Load(offset) => {
+                let value = self.locals.get(base + offset).cloned().unwrap_or_default();
+                self.push(value);
+            }
+            OpCode::Save(offset) => {
+                let value = self.pop();
 
-                    self.used_locals += 1;
-                    frame_locals += 1;
-                    self.locals[offset + (self.used_locals - frame_locals)] = value;
+                let index = base + offset;
+                if index >= self.locals_limit() {
+                    return self.overflow("too many local variables");
                 }
-                OpCode::Drop(_) => {
-                    frame_locals -= 1;
-                    self.used_locals -= 1;
+                if index >= self.locals.len() {
+                    self.locals.resize(index + 1, Value::Nil);
                 }
 
-                // globals manipulation
-                OpCode::Loag(name) => {
-                    let value = match self.get_global(name) {
-                        Some(value) => value,
-                        None => raise!("Undefined global variable: {}", name)?,
-                    };
-                    self.push(value);
-                }
-                OpCode::Savg(name) => {
-                    let value = self.pop();
-                    self.set_global(name, value);
-                }
+                self.used_locals += 1;
+                self.locals[index] = value;
+            }
+            OpCode::Drop(_) => {
+                self.used_locals -= 1;
+            }
 
-                // list manipulation
-                OpCode::Prep => {
-                    let value = self.pop();
-                    let list = match self.pop() {
-                        Value::List(list) => list.prepend(value),
-                        value => raise!("Expected list, got {}", value)?,
-                    };
+            // globals manipulation
+            OpCode::Loag(name) => {
+                let value = match self.get_global(name) {
+                    Some(value) => value,
+                    None => match self.denied_capability(name) {
+                        Some(cap) => raise!(
+                            @ErrorKind::NotGranted(cap);
+                            "`{}` requires the `{}` capability, which was not granted",
+                            name,
+                            cap
+                        )?,
+                        None => raise!(E0201; "Undefined global variable: {}", name)?,
+                    },
+                };
+                self.push(value);
+            }
+            OpCode::Savg(name) => {
+                let value = self.pop();
+                self.set_global(name, value);
+            }
 
-                    self.push(Value::List(list));
-                }
+            // list manipulation
+            OpCode::Prep => {
+                let value = self.pop();
+                let list = match self.pop() {
+                    Value::List(list) => list.prepend(value),
+                    value => raise!("Expected list, got {}", value)?,
+                };
 
-                OpCode::New(arity) => {
-                    let ty = match self.pop() {
-                        Value::Type(ty) => ty,
-                        value => raise!("Expected type, got `{}`", value)?,
-                    };
+                self.push(Value::List(list));
+            }
 
-                    let mut args = vec![];
-                    for _ in 0..arity {
-                        args.push(self.pop());
-                    }
+            OpCode::New(arity) => {
+                let ty = match self.pop() {
+                    Value::Type(ty) => ty,
+                    value => raise!("Expected type, got `{}`", value)?,
+                };
 
-                    instantiate(self, ty, args)?;
+                let mut args = vec![];
+                for _ in 0..arity {
+                    args.push(self.pop());
                 }
-                OpCode::Get(field) => {
-                    let obj = match self.pop() {
-                        Value::Instance(obj) => obj,
-                        value => raise!("Expected instance, got `{}`", value)?,
-                    };
-
-                    let value = match obj.fields.get(&field) {
-                        Some(value) => value.clone(),
-                        None => raise!("Undefined field: {}", field)?,
-                    };
-
-                    self.push(value);
-                }
-                OpCode::Invk(name, arity) => self.invoke(name, arity)?,
-            }
 
-            ip += 1;
+                instantiate(self, ty, args)?;
+            }
+            OpCode::Get(field) => {
+                let obj = match self.pop() {
+                    Value::Instance(obj) => obj,
+                    value => raise!("Expected instance, got `{}`", value)?,
+                };
+
+                let value = match obj.fields.get(&field) {
+                    Some(value) => value.clone(),
+                    None => raise!(E0204; "Undefined field: {}", field)?,
+                };
+
+                self.push(value);
+            }
+            OpCode::Extend => {
+                let parent = self.pop();
+                let ty = match self.pop() {
+                    Value::Type(ty) => ty,
+                    value => raise!("Expected type, got `{}`", value)?,
+                };
+                let ty = extend(&ty, parent)?;
+                self.push(Value::Type(GcRef::new(ty)));
+            }
+            OpCode::Impl(len) => self.implement(len)?,
+            OpCode::Expect(ty) => self.expect_type(self.pop_last(), ty)?,
+
+            OpCode::Halt
+            | OpCode::Jmp(_)
+            | OpCode::Jmf(_)
+            | OpCode::Call(_)
+            | OpCode::TCall(_)
+            | OpCode::Invk(..)
+            | OpCode::Super(..) => unreachable!("{:?} is run by run_frame", op),
         }
 
-        self.used_locals -= frame_locals;
-
         Ok(())
     }
 
+    #[cold]
+    #[inline(never)]
+    fn overflow(&self, reason: &str) -> InterpretResult<()> {
+        raise!(@ErrorKind::StackOverflow; "Stack overflow, {}", reason)
+    }
+
+    fn super_call(&mut self, name: Symbol, arity: usize) -> InterpretResult<()> {
+        let ty = match self.pop() {
+            Value::Type(ty) => ty,
+            value => raise!("Expected type, got `{}`", value)?,
+        };
+        let value = self.pop();
+        self.call_method(&ty, value, name, arity)
+    }
+
     fn invoke(&mut self, name: Symbol, arity: usize) -> InterpretResult<()> {
         let value = self.pop();
         let ty = value.type_of();
+        self.call_method(&ty, value, name, arity)
+    }
 
+    /// Calls the method `name` of a type with `value` as the receiver, taking the arguments
+    /// from the stack
+    fn call_method(
+        &mut self,
+        ty: &YexType,
+        value: Value,
+        name: Symbol,
+        arity: usize,
+    ) -> InterpretResult<()> {
+        let method = self.find_method(ty, name, arity)?;
+
+        match &*method.body {
+            // the arguments are already on the stack, the receiver goes after them
+            FnKind::Bytecode(bt) => {
+                self.push(value);
+                self.call_bytecode(bt)
+            }
+            FnKind::Native(f) => self.call_native_method(*f, value, arity),
+        }
+    }
+
+    /// Finds the method `name` of a type, checking it can be called with `arity` arguments
+    #[inline(never)]
+    fn find_method(&self, ty: &YexType, name: Symbol, arity: usize) -> InterpretResult<GcRef<Fn>> {
+        let method = match self.method(ty, name) {
+            Some(Value::Fn(f)) => f,
+            Some(_) => unreachable!(),
+            None => raise!(E0204; "Undefined method: {}", name)?,
+        };
+
+        // arity + 1 because we push the receiver
+        let given = arity + 1;
+        if given > method.arity || given + method.optional < method.arity {
+            raise!(E0202; "Expected {} arguments, found {}", method.arity - 1, arity)?;
+        }
+
+        Ok(method)
+    }
+
+    #[inline(never)]
+    fn call_native_method(
+        &mut self,
+        fp: NativeFn,
+        value: Value,
+        arity: usize,
+    ) -> InterpretResult<()> {
         let mut args = stackvec![];
-        let mut i = 1;
-        for _ in 0..arity {
+        for i in 1..=arity {
             unsafe { args.insert_at(arity - i, self.pop()) };
-            i += 1;
         }
         unsafe { args.set_len(arity) };
 
         args.push(value);
+        self.call_native(fp, args)
+    }
 
-        let method = match ty.fields.get(&name) {
-            Some(value) => match value {
-                Value::Fn(f) => f,
-                _ => unreachable!(),
-            },
-            None => raise!("Undefined method: {}", name)?,
+    /// Finds a method of a type, defined by it, added by an `impl` block or inherited from the
+    /// type it extends
+    fn method(&self, ty: &YexType, name: Symbol) -> Option<Value> {
+        ty.fields
+            .get(&name)
+            .or_else(|| self.impl_method(ty, name))
+            .or_else(|| ty.parent.as_ref().and_then(|it| self.method(it, name)))
+    }
+
+    /// Finds a method added to the type by an `impl` block, or to the type it's a variant of
+    fn impl_method(&self, ty: &YexType, name: Symbol) -> Option<Value> {
+        let find = |ty: Symbol| self.impls.get(&ty).and_then(|it| it.methods.get(&name));
+        find(ty.name).or_else(|| ty.variant_of.and_then(find))
+    }
+
+    /// Checks if the type of a value, the type it's a variant of or the types it extends
+    /// implement a trait
+    pub(crate) fn implements(&self, value: &Value, tr: &Trait) -> bool {
+        let find = |ty: Symbol| {
+            self.impls
+                .get(&ty)
+                .is_some_and(|it| it.traits.contains(&tr.name))
         };
 
-        // arity + 1 because we push the receiver
-        if method.arity != arity + 1 {
-            raise!("Expected {} arguments, found {}", method.arity - 1, arity)?;
+        let mut ty = Some(value.type_of());
+        while let Some(it) = ty {
+            if find(it.name) || it.variant_of.is_some_and(find) {
+                return true;
+            }
+            ty = it.parent.clone();
         }
+        false
+    }
 
-        match &*method.body {
-            FnKind::Bytecode(bt) => self.call_bytecode(bt, args),
-            FnKind::Native(f) => self.call_native(*f, args),
+    fn implement(&mut self, len: usize) -> InterpretResult<()> {
+        let tr = match self.pop() {
+            Value::Trait(tr) => tr,
+            value => raise!(E0212; "Expected a trait to implement, found `{}`", value)?,
+        };
+        let ty = match self.pop() {
+            Value::Type(ty) => ty,
+            value => {
+                raise!(E0212; "Expected a type to implement `{}` for, found `{}`", tr.name, value)?
+            }
+        };
+
+        let mut methods = Vec::with_capacity(len);
+        for _ in 0..len {
+            match (self.pop(), self.pop()) {
+                (Value::Sym(name), Value::Fn(method)) => methods.push((name, method)),
+                _ => unreachable!(),
+            }
         }
+
+        traits::implement(&mut self.impls, &tr, &ty, methods)
     }
 
     #[cfg(debug_assertions)]
@@ -323,10 +673,8 @@ impl VirtualMachine {
 
         let mut args = stackvec![];
 
-        let mut i = 1;
-        for _ in 0..arity {
+        for i in 1..=arity {
             unsafe { args.insert_at(arity - i, self.pop()) };
-            i += 1;
         }
 
         unsafe { args.set_len(arity) };
@@ -338,10 +686,50 @@ impl VirtualMachine {
         args
     }
 
+    /// Calls `fun` with `args` from a native function and returns its result, the position of
+    /// the native call is restored afterwards so the errors it raises later point to it
+    pub(crate) fn call_with(&mut self, fun: &Value, args: &[Value]) -> InterpretResult<Value> {
+        let (line, column, span) = unsafe { (LINE, COLUMN, SPAN) };
+
+        // arguments are pushed from last to first, like the compiler does
+        for arg in args.iter().rev() {
+            self.push(arg.clone());
+        }
+        self.push(fun.clone());
+        self.call(args.len())?;
+
+        unsafe {
+            LINE = line;
+            COLUMN = column;
+            SPAN = span;
+        }
+        Ok(self.pop())
+    }
+
     pub(crate) fn call(&mut self, arity: usize) -> InterpretResult<()> {
-        let fun = match self.pop() {
+        match self.pop() {
+            // the arguments of a complete call are already on the stack, every nested call goes
+            // through here, so the other calls are left to `call_value` to keep this frame small
+            Value::Fn(fun) if fun.arity == arity && fun.args.is_empty() && fun.is_bytecode() => {
+                match &*fun.body {
+                    FnKind::Bytecode(bytecode) => self.call_bytecode(bytecode),
+                    FnKind::Native(_) => unreachable!(),
+                }
+            }
+            value => self.call_value(value, arity),
+        }
+    }
+
+    #[inline(never)]
+    fn call_value(&mut self, value: Value, arity: usize) -> InterpretResult<()> {
+        let fun = match value {
             Value::Fn(f) => f,
-            value => raise!("Expected a function to call, found {value}")?,
+            // the variants of a type are built by calling them, like `Circle(1)`
+            Value::Type(ty) if ty.variant_of.is_some() => {
+                let args = (0..arity).map(|_| self.pop()).collect();
+                return instantiate(self, ty, args);
+            }
+            value => raise!(E0203; "Expected a function to call, found {value}")?,
         };
 
         if arity < fun.arity {
@@ -356,7 +744,7 @@ impl VirtualMachine {
         let args = self.call_args(arity, &fun);
 
         if arity > fun.arity {
-            raise!("Too many arguments for function {}", *fun)?;
+            raise!(E0202; "Too many arguments for function {}", *fun)?;
         }
 
         if arity < fun.arity {
@@ -365,21 +753,30 @@ impl VirtualMachine {
         }
 
         match &*fun.body {
-            FnKind::Bytecode(bytecode) => self.call_bytecode(bytecode, args),
+            FnKind::Bytecode(bytecode) => {
+                for arg in args {
+                    self.push(arg);
+                }
+                self.call_bytecode(bytecode)
+            }
             FnKind::Native(ptr) => self.call_native(*ptr, args),
         }
     }
 
-    #[inline]
-    fn call_bytecode(&mut self, bytecode: BytecodeRef, args: FnArgs) -> InterpretResult<()> {
-        self.used_locals += 1;
-        for arg in args {
-            self.push(arg);
+    /// Runs the bytecode of a function, whose arguments were already pushed to the stack
+    fn call_bytecode(&mut self, bytecode: BytecodeRef) -> InterpretResult<()> {
+        if self.depth >= self.max_depth {
+            return self.overflow("too many nested calls");
         }
 
-        self.run(bytecode)?;
+        self.used_locals += 1;
+        self.depth += 1;
+
+        // the counters are restored even if the call fails, so the vm can still be used
+        let result = self.run(bytecode);
         self.used_locals -= 1;
-        Ok(())
+        self.depth -= 1;
+        result
     }
 
     #[inline]
@@ -393,13 +790,14 @@ impl VirtualMachine {
     fn valid_tail_call(&mut self, arity: usize, frame: BytecodeRef) -> InterpretResult<()> {
         let fun = match self.pop() {
             Value::Fn(fun) => fun,
-            value => raise!("Expected a function, found {value}")?,
+            value => raise!(E0203; "Expected a function, found {value}")?,
         };
         match &*fun.body {
             FnKind::Bytecode(_) if fun.arity != arity => {
                 raise!(
                     "Expected function with arity {}, found {}",
-                    arity, fun.arity
+                    arity,
+                    fun.arity
                 )
             }
             FnKind::Bytecode(bytecode) if bytecode != frame => {
@@ -412,6 +810,17 @@ impl VirtualMachine {
         }
     }
 
+    /// How many values the stack can hold, which grows with the depth limit so that every
+    /// function that can be called has room for its values
+    fn stack_limit(&self) -> usize {
+        STACK_SIZE.saturating_add(self.max_depth.saturating_mul(FRAME_STACK))
+    }
+
+    /// How many locals can be saved, which grows with the depth limit like the stack
+    fn locals_limit(&self) -> usize {
+        LOCALS_SIZE.saturating_add(self.max_depth.saturating_mul(FRAME_LOCALS))
+    }
+
     #[track_caller]
     pub(crate) fn push(&mut self, constant: Value) {
         self.stack.push(constant)
@@ -419,7 +828,7 @@ impl VirtualMachine {
 
     #[track_caller]
     pub(crate) fn pop(&mut self) -> Value {
-        self.stack.pop()
+        self.stack.pop().expect("Called pop() on an empty stack")
     }
 
     fn binop<T, F>(&mut self, f: F) -> InterpretResult<()>
@@ -445,15 +854,6 @@ impl VirtualMachine {
 
 impl Default for VirtualMachine {
     fn default() -> Self {
-        const STACK: Stack = StackVec::new();
-
-        let prelude = prelude::prelude();
-        Self {
-            stack: STACK,
-            locals: [NIL; 1024],
-            used_locals: 0,
-            constants: Vec::new(),
-            globals: prelude,
-        }
+        VmBuilder::new().allow_all().build()
     }
 }
@@ -0,0 +1,88 @@
+use std::{
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    error::ErrorKind,
+    gc::{self, Heap},
+    raise, InterpretResult,
+};
+
+/// A thread-safe handle that can stop a running [`crate::VirtualMachine`] from another thread
+#[derive(Debug, Clone)]
+pub struct InterruptHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    /// Requests the virtual machine to stop, it will raise an [`ErrorKind::Interrupted`] error
+    /// before running its next instruction
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Execution limits of the virtual machine, checked before every instruction
+#[derive(Debug, Default)]
+pub(crate) struct Limits {
+    /// How many instructions can still be executed
+    pub fuel: Option<usize>,
+    /// The values allocated while running and how many bytes they can take, only kept when
+    /// there is a memory limit
+    pub heap: Option<Rc<Heap>>,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl Limits {
+    pub fn set_memory(&mut self, bytes: usize) {
+        self.heap = Some(Rc::new(Heap::new(bytes)));
+    }
+
+    /// Charges the values allocated on this thread to the heap of the virtual machine, and makes
+    /// [`gc::reserve`] enforce its limit, until the returned guard is dropped
+    pub fn enter(&self) -> HeapGuard {
+        HeapGuard(gc::set_active(self.heap.clone()))
+    }
+
+    pub fn handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            flag: Arc::clone(&self.interrupt),
+        }
+    }
+
+    #[inline]
+    pub fn check(&mut self) -> InterpretResult<()> {
+        if self.interrupt.swap(false, Ordering::Relaxed) {
+            raise!(@ErrorKind::Interrupted; "Execution interrupted")?;
+        }
+
+        if let Some(fuel) = &mut self.fuel {
+            if *fuel == 0 {
+                raise!(@ErrorKind::OutOfFuel; "Instruction limit exceeded")?;
+            }
+            *fuel -= 1;
+        }
+
+        if let Some(heap) = &self.heap {
+            let (used, max) = (heap.allocated(), heap.limit());
+            if used > max {
+                raise!(@ErrorKind::MemoryLimit; "Memory limit exceeded ({} of {} bytes)", used, max)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Restores the heap of the enclosing run when dropped
+pub(crate) struct HeapGuard(Option<Rc<Heap>>);
+
+impl Drop for HeapGuard {
+    fn drop(&mut self) {
+        gc::set_active(self.0.take());
+    }
+}
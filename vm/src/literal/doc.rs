@@ -0,0 +1,44 @@
+use std::{fmt, ops::Deref};
+
+use crate::gc::GcRef;
+
+/// The documentation of a function, a type or a trait
+///
+/// Builtins point to the static tables they are declared in, so creating them allocates
+/// nothing, while definitions own the text of their `///` comments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Doc {
+    /// The documentation of a builtin
+    Static(&'static str),
+    /// The documentation taken from a `///` comment
+    Owned(GcRef<String>),
+}
+
+impl Deref for Doc {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Doc::Static(doc) => doc,
+            Doc::Owned(doc) => doc.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for Doc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl From<&'static str> for Doc {
+    fn from(doc: &'static str) -> Self {
+        Doc::Static(doc)
+    }
+}
+
+impl From<String> for Doc {
+    fn from(doc: String) -> Self {
+        Doc::Owned(GcRef::new(doc))
+    }
+}
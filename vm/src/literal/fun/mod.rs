@@ -1,11 +1,13 @@
 use crate::{
-    error::InterpretResult, gc::GcRef, stackvec, Bytecode, StackVec, Value, VirtualMachine,
+    error::InterpretResult, gc::GcRef, literal::doc::Doc, stackvec, Bytecode, StackVec, Value,
+    VirtualMachine,
 };
 pub type NativeFn = fn(*mut VirtualMachine, Vec<Value>) -> InterpretResult<Value>;
 pub type FnBody = GcRef<FnKind>;
 pub type FnArgs = StackVec<Value, 8>;
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(unpredictable_function_pointer_comparisons)]
 /// The kind of a function.
 pub enum FnKind {
     /// A native function.
@@ -19,10 +21,14 @@ pub enum FnKind {
 pub struct Fn {
     /// The number of argument the function receives
     pub arity: usize,
+    /// How many of the last arguments can be left out when the function is called as a method
+    pub optional: usize,
     /// The function body
     pub body: FnBody,
     /// The function Arguments
     pub args: FnArgs,
+    /// The documentation of the function, taken from its `///` comment
+    pub doc: Option<Doc>,
 }
 
 impl Fn {
@@ -30,8 +36,10 @@ impl Fn {
     pub fn new_bt(arity: usize, body: Bytecode) -> Self {
         Self {
             arity,
+            optional: 0,
             body: GcRef::new(FnKind::Bytecode(body)),
             args: FnArgs::new(),
+            doc: None,
         }
     }
 
@@ -39,11 +47,27 @@ impl Fn {
     pub fn new_native(arity: usize, native: NativeFn) -> Self {
         Self {
             arity,
+            optional: 0,
             body: GcRef::new(FnKind::Native(native)),
             args: FnArgs::new(),
+            doc: None,
         }
     }
 
+    #[must_use]
+    /// Lets the method be called without its last `count` arguments
+    pub fn with_optional(mut self, count: usize) -> Self {
+        self.optional = count;
+        self
+    }
+
+    #[must_use]
+    /// Attaches documentation to the function
+    pub fn with_doc(mut self, doc: impl Into<Doc>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
     /// Converts the Fn to a GcRef<Fn>
     #[must_use]
     pub fn to_gcref(self) -> GcRef<Fn> {
@@ -59,8 +83,10 @@ impl Fn {
 
         Fn {
             arity: self.arity + self.args.len() - args.len(),
+            optional: self.optional,
             body: self.body.clone(),
             args,
+            doc: self.doc.clone(),
         }
     }
 
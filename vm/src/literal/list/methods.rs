@@ -1,63 +1,103 @@
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
 use crate::{
     error::InterpretResult,
-    literal::{nil, Value},
+    gc,
+    literal::{nil, number, Value},
     raise, VirtualMachine,
 };
 
-use super::List;
+use super::{List, Node};
+
+fn list<'a>(name: &str, value: &'a Value) -> InterpretResult<&'a List> {
+    match value {
+        Value::List(xs) => Ok(xs),
+        other => raise!("{}() expected a list, but found `{}`", name, other),
+    }
+}
+
+fn count(name: &str, value: &Value) -> InterpretResult<usize> {
+    match value {
+        Value::Int(n) if *n >= 0 => Ok(*n as usize),
+        other => raise!(
+            "{}() expected a valid positive integer, but found `{}`",
+            name,
+            other
+        ),
+    }
+}
+
+/// Checks that `count` nodes can be allocated before building the lists made of them
+fn reserve(count: usize) -> InterpretResult<()> {
+    gc::reserve_refs::<Node>(count, 0)
+}
+
+fn pair(a: Value, b: Value) -> Value {
+    Value::List(List::new().prepend(b).prepend(a))
+}
+
+/// A stable merge sort that stops at the first error returned by `cmp`
+fn merge_sort<F>(mut xs: Vec<Value>, cmp: &mut F) -> InterpretResult<Vec<Value>>
+where
+    F: FnMut(&Value, &Value) -> InterpretResult<Ordering>,
+{
+    if xs.len() <= 1 {
+        return Ok(xs);
+    }
+
+    let right = xs.split_off(xs.len() / 2);
+    let left = merge_sort(xs, cmp)?;
+    let right = merge_sort(right, cmp)?;
+
+    let mut sorted = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        // only take from the right when it's strictly smaller, keeping equal elements in order
+        if cmp(y, x)?.is_lt() {
+            sorted.push(right.next().unwrap());
+        } else {
+            sorted.push(left.next().unwrap());
+        }
+    }
+
+    sorted.extend(left);
+    sorted.extend(right);
+    Ok(sorted)
+}
 
 pub fn rev(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
-    let xs = match &args[0] {
-        Value::List(xs) => xs,
-        other => return raise!("rev[0] expected a list, but found `{}`", other),
-    };
+    let xs = list("rev", &args[0])?;
+    reserve(xs.len())?;
     Ok(Value::List(xs.rev()))
 }
 
 pub fn map(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
     let vm = unsafe { &mut *vm };
-    let xs = match &args[0] {
-        Value::List(xs) => xs,
-        other => return raise!("map[1] expected a list, but found `{}`", other),
-    };
-    let fun = &args[1];
+    let xs = list("map", &args[0])?;
+    // the list is built backwards and then reversed
+    reserve(xs.len().saturating_mul(2))?;
 
-    let xs = xs
-        .iter()
-        .map(|it| {
-            vm.push(it);
-            vm.push(fun.clone());
-            if let Err(e) = vm.call(1) {
-                raise!("{}", e)?
-            }
-            Ok(vm.pop())
-        })
-        .try_fold(List::new(), |xs, x| match x {
-            Ok(x) => Ok(xs.prepend(x)),
-            Err(e) => Err(e),
-        })?;
+    let mut ys = List::new();
+    for x in xs.iter() {
+        ys = ys.prepend(vm.call_with(&args[1], &[x])?);
+    }
 
-    Ok(Value::List(xs.rev()))
+    Ok(Value::List(ys.rev()))
 }
 
 pub fn fold(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
     let vm = unsafe { &mut *vm };
+    let xs = list("fold", &args[0])?;
 
-    let xs = match &args[0] {
-        Value::List(xs) => xs,
-        other => raise!("fold[2] expected a list, but found `{}`", other)?,
-    };
     let mut acc = args[1].clone();
-    let fun = args[2].clone();
-
-    for it in xs.iter() {
-        vm.push(acc);
-        vm.push(it);
-        vm.push(fun.clone());
-        if let Err(e) = vm.call(2) {
-            return raise!("{}", e);
-        }
-        acc = vm.pop();
+    for x in xs.iter() {
+        acc = vm.call_with(&args[2], &[acc, x])?;
     }
 
     Ok(acc)
@@ -65,30 +105,354 @@ pub fn fold(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value>
 
 pub fn filter(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
     let vm = unsafe { &mut *vm };
-
-    let xs = match &args[0] {
-        Value::List(xs) => xs,
-        other => raise!("filter[1] expected a list, but found `{}`", other)?,
-    };
-    let fun = &args[1];
+    let xs = list("filter", &args[0])?;
+    reserve(xs.len().saturating_mul(2))?;
 
     let mut ys = List::new();
+    for x in xs.iter() {
+        if vm.call_with(&args[1], std::slice::from_ref(&x))?.to_bool() {
+            ys = ys.prepend(x);
+        }
+    }
+
+    Ok(Value::List(ys.rev()))
+}
+
+pub fn flat_map(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("flat_map", &args[0])?;
 
+    let mut ys = vec![];
     for x in xs.iter() {
-        vm.push(x.clone());
-        vm.push(fun.clone());
+        match vm.call_with(&args[1], &[x])? {
+            Value::List(zs) => {
+                reserve(ys.len().saturating_add(zs.len()))?;
+                ys.extend(zs.iter())
+            }
+            other => raise!(
+                "flat_map() expected the function to return a list, found `{}`",
+                other
+            )?,
+        }
+    }
+
+    Ok(Value::List(List::from_vec(ys)))
+}
 
-        if let Err(e) = vm.call(1) {
-            return raise!("{}", e);
+pub fn any(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("any?", &args[0])?;
+
+    for x in xs.iter() {
+        if vm.call_with(&args[1], &[x])?.to_bool() {
+            return Ok(Value::Bool(true));
         }
+    }
 
-        let res = vm.pop();
-        if res.to_bool() {
-            ys = ys.prepend(x);
+    Ok(Value::Bool(false))
+}
+
+pub fn all(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("all?", &args[0])?;
+
+    for x in xs.iter() {
+        if !vm.call_with(&args[1], &[x])?.to_bool() {
+            return Ok(Value::Bool(false));
         }
     }
 
-    Ok(Value::List(ys.rev()))
+    Ok(Value::Bool(true))
+}
+
+pub fn find(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("find", &args[0])?;
+
+    for x in xs.iter() {
+        if vm.call_with(&args[1], std::slice::from_ref(&x))?.to_bool() {
+            return Ok(x);
+        }
+    }
+
+    Ok(nil())
+}
+
+pub fn partition(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("partition", &args[0])?;
+    reserve(xs.len().saturating_add(2))?;
+
+    let (mut yes, mut no) = (vec![], vec![]);
+    for x in xs.iter() {
+        if vm.call_with(&args[1], std::slice::from_ref(&x))?.to_bool() {
+            yes.push(x);
+        } else {
+            no.push(x);
+        }
+    }
+
+    Ok(pair(
+        Value::List(List::from_vec(yes)),
+        Value::List(List::from_vec(no)),
+    ))
+}
+
+/// Hashes numbers, strings, symbols and booleans, values that are equal have the same hash
+fn hash(value: &Value) -> Option<u64> {
+    let mut state = DefaultHasher::new();
+    match value {
+        Value::Str(s) => s.as_str().hash(&mut state),
+        Value::Sym(s) => s.hash(&mut state),
+        Value::Bool(b) => b.hash(&mut state),
+        // numbers of different kinds can be equal, but they convert to the same float then
+        n => (number::to_float(n)? + 0.0).to_bits().hash(&mut state),
+    }
+    Some(state.finish())
+}
+
+/// The indexes of the distinct values found so far, values that can be hashed are looked up
+/// by their hash and the rest are compared with every other value that can't be hashed
+#[derive(Default)]
+struct Seen {
+    hashed: HashMap<u64, Vec<usize>>,
+    other: Vec<usize>,
+}
+
+impl Seen {
+    /// Returns the index of a value equal to `value`, or records `value` as the value at `next`
+    fn find<'a>(
+        &mut self,
+        value: &Value,
+        next: usize,
+        get: impl Fn(usize) -> &'a Value,
+    ) -> Option<usize> {
+        let indexes = match hash(value) {
+            Some(hash) => self.hashed.entry(hash).or_default(),
+            None => &mut self.other,
+        };
+
+        let found = indexes.iter().copied().find(|&idx| get(idx).equals(value));
+        if found.is_none() {
+            indexes.push(next);
+        }
+        found
+    }
+}
+
+pub fn group_by(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("group_by", &args[0])?;
+    // every element can be in a group of its own, which takes a pair and a list
+    reserve(xs.len().saturating_mul(4))?;
+
+    let mut groups: Vec<(Value, Vec<Value>)> = vec![];
+    let mut seen = Seen::default();
+    for x in xs.iter() {
+        let key = vm.call_with(&args[1], std::slice::from_ref(&x))?;
+        match seen.find(&key, groups.len(), |idx| &groups[idx].0) {
+            Some(idx) => groups[idx].1.push(x),
+            None => groups.push((key, vec![x])),
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|(key, group)| pair(key, Value::List(List::from_vec(group))))
+        .collect();
+
+    Ok(Value::List(List::from_vec(groups)))
+}
+
+pub fn sort(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("sort", &args[0])?;
+    reserve(xs.len())?;
+    let sorted = merge_sort(xs.to_vec(), &mut |x, y| x.compare(y))?;
+    Ok(Value::List(List::from_vec(sorted)))
+}
+
+pub fn sort_by(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    let xs = list("sort_by", &args[0])?;
+    reserve(xs.len())?;
+    let fun = &args[1];
+
+    let sorted = merge_sort(xs.to_vec(), &mut |x, y| match vm
+        .call_with(fun, &[x.clone(), y.clone()])?
+    {
+        n @ (Value::Int(_) | Value::BigInt(_) | Value::Ratio(_) | Value::Num(_)) => {
+            n.compare(&Value::Int(0))
+        }
+        other => raise!(
+            "sort_by() expected the comparator to return a number, found `{}`",
+            other
+        ),
+    })?;
+
+    Ok(Value::List(List::from_vec(sorted)))
+}
+
+pub fn min(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("min", &args[0])?;
+
+    let mut min = match xs.head() {
+        Some(x) => x,
+        None => return Ok(nil()),
+    };
+    for x in xs.tail().iter() {
+        if x.compare(&min)?.is_lt() {
+            min = x;
+        }
+    }
+
+    Ok(min)
+}
+
+pub fn max(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("max", &args[0])?;
+
+    let mut max = match xs.head() {
+        Some(x) => x,
+        None => return Ok(nil()),
+    };
+    for x in xs.tail().iter() {
+        if x.compare(&max)?.is_gt() {
+            max = x;
+        }
+    }
+
+    Ok(max)
+}
+
+pub fn sum(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("sum", &args[0])?;
+    xs.iter().try_fold(Value::Int(0), |acc, x| acc + x)
+}
+
+pub fn uniq(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("uniq", &args[0])?;
+    reserve(xs.len())?;
+
+    let mut uniq: Vec<Value> = vec![];
+    let mut seen = Seen::default();
+    for x in xs.iter() {
+        if seen.find(&x, uniq.len(), |idx| &uniq[idx]).is_none() {
+            uniq.push(x);
+        }
+    }
+
+    Ok(Value::List(List::from_vec(uniq)))
+}
+
+pub fn index_of(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("index_of", &args[0])?;
+
+    match xs.iter().position(|x| x.equals(&args[1])) {
+        Some(idx) => Ok(Value::Int(idx as i64)),
+        None => Ok(nil()),
+    }
+}
+
+pub fn contains(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("contains?", &args[0])?;
+    Ok(Value::Bool(xs.iter().any(|x| x.equals(&args[1]))))
+}
+
+pub fn len(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("len", &args[0])?;
+    Ok(Value::Int(xs.len() as i64))
+}
+
+pub fn concat(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("concat", &args[0])?;
+    let ys = list("concat", &args[1])?;
+    reserve(xs.len())?;
+    Ok(Value::List(xs.concat(ys)))
+}
+
+pub fn take(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("take", &args[0])?;
+    let n = count("take", &args[1])?;
+    reserve(xs.len().min(n))?;
+    Ok(Value::List(List::from_vec(xs.iter().take(n).collect())))
+}
+
+pub fn drop(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let mut xs = list("drop", &args[0])?.clone();
+    for _ in 0..count("drop", &args[1])? {
+        if xs.is_empty() {
+            break;
+        }
+        xs = xs.tail();
+    }
+    Ok(Value::List(xs))
+}
+
+pub fn zip(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("zip", &args[0])?;
+    let ys = list("zip", &args[1])?;
+    reserve(xs.len().min(ys.len()).saturating_mul(3))?;
+
+    let pairs = xs.iter().zip(ys.iter()).map(|(x, y)| pair(x, y)).collect();
+    Ok(Value::List(List::from_vec(pairs)))
+}
+
+pub fn enumerate(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("enumerate", &args[0])?;
+    reserve(xs.len().saturating_mul(3))?;
+
+    let pairs = xs
+        .iter()
+        .enumerate()
+        .map(|(idx, x)| pair(Value::Int(idx as i64), x))
+        .collect();
+    Ok(Value::List(List::from_vec(pairs)))
+}
+
+pub fn join(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let xs = list("join", &args[0])?;
+    let sep = match &args[1] {
+        Value::Str(sep) => sep.as_str(),
+        other => raise!("join() expected a string separator, but found `{}`", other)?,
+    };
+
+    let res = xs
+        .iter()
+        .map(|x| match x {
+            Value::Str(s) => s.to_string(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(sep);
+
+    gc::reserve_refs::<String>(1, res.len())?;
+    Ok(Value::Str(crate::gc::GcRef::new(res)))
+}
+
+/// The largest list `range` creates, even without a memory limit
+const MAX_RANGE: i64 = 1 << 26;
+
+pub fn range(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let (start, end) = match (&args[0], &args[1]) {
+        (Value::Int(start), Value::Int(end)) => (*start, *end),
+        (start, end) => raise!(
+            "range() expected two integers, but found `{}` and `{}`",
+            start,
+            end
+        )?,
+    };
+
+    let len = end.saturating_sub(start).max(0);
+    gc::reserve_refs::<Node>(usize::try_from(len).unwrap_or(usize::MAX), 0)?;
+    if len > MAX_RANGE {
+        raise!(
+            "range() can't create more than {} elements, found {}",
+            MAX_RANGE,
+            len
+        )?;
+    }
+
+    Ok(Value::List((start..end).rev().map(Value::Int).collect()))
 }
 
 pub fn head(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
@@ -110,7 +474,7 @@ pub fn tail(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value>
 
 pub fn get(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
     let n = match &args[1] {
-        Value::Num(n) if n.fract() == 0.0 && *n >= 0.0 => *n as usize,
+        Value::Int(n) if *n >= 0 => *n as usize,
         other => raise!(
             "nth[1] expected a valid positive integer, but found {}",
             other
@@ -25,7 +25,7 @@ impl List {
 
     /// Checks if the list is empty
     pub fn is_empty(&self) -> bool {
-        self.head == None
+        self.head.is_none()
     }
 
     /// Prepends a value to the end, returning the list
@@ -42,10 +42,7 @@ impl List {
     #[must_use]
     pub fn tail(&self) -> Self {
         let tail = self.head.as_ref().map(|node| node.next.clone());
-        let tail = match tail {
-            Some(v) => v,
-            None => None,
-        };
+        let tail = tail.unwrap_or_default();
 
         Self { head: tail }
     }
@@ -73,7 +70,7 @@ impl List {
     pub fn len(&self) -> usize {
         let mut xs = self.head.as_ref();
         let mut count = 0;
-        while xs != None {
+        while xs.is_some() {
             xs = xs.unwrap().next.as_ref();
             count += 1;
         }
@@ -92,12 +89,27 @@ impl List {
     }
 
     /// Iterate over all elements of `self`
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             next: self.head.as_deref(),
         }
     }
 
+    /// Creates a list with the elements of `vec`, keeping their order
+    pub fn from_vec(vec: Vec<Value>) -> Self {
+        vec.into_iter().rev().collect()
+    }
+
+    /// Returns a list with the elements of `self` followed by the elements of `other`, the
+    /// nodes of `other` are shared
+    #[must_use]
+    pub fn concat(&self, other: &List) -> Self {
+        self.to_vec()
+            .into_iter()
+            .rev()
+            .fold(other.clone(), |xs, x| xs.prepend(x))
+    }
+
     /// Reverses `self` without consuming it
     #[must_use]
     pub fn rev(&self) -> Self {
@@ -111,6 +123,12 @@ impl List {
     }
 }
 
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl std::fmt::Display for List {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[")?;
@@ -4,21 +4,34 @@ use std::{
     ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub, Deref},
 };
 
+pub mod doc;
 pub mod fun;
 pub mod instance;
 pub mod list;
 pub mod symbol;
 pub mod table;
+pub mod traits;
 pub mod yextype;
 pub mod str;
 pub mod mutable;
-use crate::{error::InterpretResult, gc::GcRef, raise};
+pub mod number;
+pub mod variant;
+use crate::{
+    error::InterpretResult,
+    gc::{self, GcRef},
+    raise,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+use number::Op;
 
 use fun::Fn;
 use instance::Instance;
 use list::List;
 use symbol::Symbol;
-use yextype::YexType;
+use traits::Trait;
+use yextype::{builtin, YexType};
 use mutable::Mutable;
 
 use self::{table::Table};
@@ -34,8 +47,14 @@ impl From<bool> for Value {
 }
 
 /// Immediate values that can be consumed
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Default)]
 pub enum Value {
+    /// 64 bits signed integers
+    Int(i64),
+    /// Arbitrary-precision integers, only used for values that don't fit in `Int`
+    BigInt(GcRef<BigInt>),
+    /// Exact fractions, only used for values that aren't integers
+    Ratio(GcRef<BigRational>),
     /// float-precision numbers
     Num(f64),
     /// Strings
@@ -56,7 +75,10 @@ pub enum Value {
     Mutable(GcRef<Mutable>),
     /// Yex instances
     Instance(GcRef<Instance>),
+    /// Yex traits
+    Trait(GcRef<Trait>),
     /// null
+    #[default]
     Nil,
 }
 
@@ -69,10 +91,14 @@ impl Clone for Value {
             Str(str) => Str(GcRef::clone(str)),
             Fn(f) => Fn(GcRef::clone(f)),
             Bool(b) => Bool(*b),
+            Int(n) => Int(*n),
+            BigInt(n) => BigInt(GcRef::clone(n)),
+            Ratio(n) => Ratio(GcRef::clone(n)),
             Num(n) => Num(*n),
             Sym(s) => Sym(*s),
             Type(t) => Type(t.clone()),
             Instance(i) => Instance(i.clone()),
+            Trait(t) => Trait(t.clone()),
             Table(t) => Table(t.clone()),
             Mutable(t) => Mutable(GcRef::clone(t)),
             Nil => Nil,
@@ -91,29 +117,86 @@ impl Value {
     pub fn len(&self) -> usize {
         match self {
             Value::List(xs) => xs.len(),
+            Value::Int(_) => mem::size_of::<i64>(),
+            Value::BigInt(n) => n.to_bytes_le().1.len(),
+            Value::Ratio(n) => n.numer().to_bytes_le().1.len() + n.denom().to_bytes_le().1.len(),
             Value::Num(_) => mem::size_of::<f64>(),
             Value::Sym(_) => mem::size_of::<Symbol>(),
-            Value::Str(s) => s.len(),
-            Value::Fn(f) => mem::size_of_val(&f),
+            // counted in characters, like the methods of strings
+            Value::Str(s) => s.chars().count(),
+            Value::Fn(f) => mem::size_of_val(f),
             Value::Bool(_) => mem::size_of::<bool>(),
-            Value::Type(t) => mem::size_of_val(&t),
-            Value::Instance(i) => mem::size_of_val(&i),
-            Value::Table(t) => mem::size_of_val(&t),
+            Value::Type(t) => mem::size_of_val(t),
+            Value::Instance(i) => mem::size_of_val(i),
+            Value::Trait(t) => mem::size_of_val(t),
+            Value::Table(t) => mem::size_of_val(t),
             Value::Mutable(_) => mem::size_of::<Mutable>(),
             Value::Nil => 4,
         }
     }
 
-    /// Compares the left and the right value
-    pub fn ord_cmp(&self, rhs: &Self) -> InterpretResult<Ordering> {
-        let (left, right) = match (self, rhs) {
-            (Self::Num(left), Self::Num(right)) => (left, right),
-            (left, right) => return crate::raise!("Can't compare `{}` and `{}`", left, right),
-        };
+    /// Compares two values: numbers by their numeric value, strings lexicographically, symbols
+    /// by their name, booleans with `false` before `true`, and lists element-wise, with a shorter
+    /// list coming first when it's a prefix of the other. Comparing values of different types
+    /// raises an error
+    pub fn compare(&self, rhs: &Self) -> InterpretResult<Ordering> {
+        if let Some(ord) = number::compare(self, rhs) {
+            return ord;
+        }
 
-        match left.partial_cmp(right) {
-            Some(ord) => Ok(ord),
-            None => raise!("Error applying cmp"),
+        match (self, rhs) {
+            (Value::Str(a), Value::Str(b)) => Ok(a.as_str().cmp(b.as_str())),
+            (Value::Sym(a), Value::Sym(b)) => Ok(a.as_str().cmp(b.as_str())),
+            (Value::Bool(a), Value::Bool(b)) => Ok(a.cmp(b)),
+            (Value::Nil, Value::Nil) => Ok(Ordering::Equal),
+            (Value::List(xs), Value::List(ys)) => {
+                for (x, y) in xs.iter().zip(ys.iter()) {
+                    match x.compare(&y)? {
+                        Ordering::Equal => continue,
+                        ord => return Ok(ord),
+                    }
+                }
+                Ok(xs.len().cmp(&ys.len()))
+            }
+            _ => raise!(
+                "Can't compare `{}` ({}) and `{}` ({})",
+                self,
+                self.type_of().name,
+                rhs,
+                rhs.type_of().name
+            ),
+        }
+    }
+
+    /// Checks if two values are equal, integers and floats are compared by their numeric value
+    pub fn equals(&self, rhs: &Self) -> bool {
+        match number::compare(self, rhs) {
+            Some(ord) => matches!(ord, Ok(Ordering::Equal)),
+            None => self == rhs,
+        }
+    }
+
+    /// Raises `self` to the power of `rhs`
+    pub fn pow(self, rhs: Self) -> ConstantErr {
+        match number::pow(&self, &rhs) {
+            Some(res) => res,
+            None => raise!(E0205; "Can't apply `**` operator between {} and {}", self, rhs),
+        }
+    }
+
+    /// Divides `self` by `rhs`, rounding the result towards negative infinity
+    pub fn floor_div(self, rhs: Self) -> ConstantErr {
+        match number::arith(Op::FloorDiv, &self, &rhs) {
+            Some(res) => res,
+            None => raise!(E0205; "Can't apply `~/` operator between {} and {}", self, rhs),
+        }
+    }
+
+    /// Applies a bitwise not to `self`
+    pub fn bit_not(self) -> ConstantErr {
+        match number::bit_not(&self) {
+            Some(n) => Ok(n),
+            None => raise!(E0205; "Can't apply bitwise `~~~` on {}", self),
         }
     }
 
@@ -126,6 +209,9 @@ impl Value {
             Sym(_) => true,
             Str(s) if s.is_empty() => false,
             Str(_) => true,
+            Int(0) => false,
+            Int(_) => true,
+            BigInt(_) | Ratio(_) => true,
             Num(n) if *n == 0.0 => false,
             Num(_) => true,
             Nil => false,
@@ -134,10 +220,46 @@ impl Value {
             Value::Type(_) => true,
             Table(_) => true,
             Value::Instance(_) => true,
+            Value::Trait(_) => true,
             Mutable(_) => true, //who cares?
         }
     }
 
+    /// Returns the documentation of a function, a type or the type of an instance
+    pub fn doc(&self) -> Option<doc::Doc> {
+        match self {
+            Value::Fn(f) => f.doc.clone(),
+            Value::Type(ty) => ty.doc.clone(),
+            Value::Instance(obj) => obj.ty.doc.clone(),
+            Value::Trait(t) => t.doc.clone(),
+            _ => None,
+        }
+    }
+
+    /// Checks if the value has the type named in an annotation, `Any` matches every value,
+    /// and the variants of a type and the types that extend it, like the number types
+    /// extending `Num`, match the type. The variants with fields are built by calling them,
+    /// so they match `Fn`
+    pub fn has_type(&self, name: Symbol) -> bool {
+        match (self, name.as_str()) {
+            (_, "Any") => return true,
+            (Value::Type(ty), "Fn") if ty.variant_of.is_some() => return true,
+            _ => {}
+        }
+
+        let ty = self.type_of();
+        ty.name == name || ty.variant_of == Some(name) || ty.extends(name)
+    }
+
+    /// returns the name of the variant the value is an instance of, `Circle` for an instance
+    /// of the `Circle` variant of `type Shape = Circle(r) | Rect(w, h) end`
+    pub fn tag(&self) -> Option<Symbol> {
+        match self {
+            Value::Instance(i) if i.ty.variant_of.is_some() => Some(i.ty.name),
+            _ => None,
+        }
+    }
+
     /// returns the type of the value
     pub fn type_of(&self) -> GcRef<YexType> {
         use Value::*;
@@ -148,26 +270,22 @@ impl Value {
             _ => {}
         };
 
-        let ty = match self {
-            List(_) => YexType::list(),
-            Fn(_) => YexType::fun(),
-            Num(_) => YexType::num(),
-            Str(_) => YexType::str(),
-            Bool(_) => YexType::bool(),
-            Nil => YexType::nil(),
-            Sym(_) => YexType::sym(),
-            Table(_) => YexType::table(),
-            Mutable(_) => YexType::mutable(),
+        match self {
+            List(_) => builtin(|it| &it.list),
+            Fn(_) => builtin(|it| &it.fun),
+            Int(_) => builtin(|it| &it.int),
+            BigInt(_) => builtin(|it| &it.bigint),
+            Ratio(_) => builtin(|it| &it.ratio),
+            Num(_) => builtin(|it| &it.float),
+            Str(_) => builtin(|it| &it.str),
+            Bool(_) => builtin(|it| &it.bool),
+            Nil => builtin(|it| &it.nil),
+            Sym(_) => builtin(|it| &it.sym),
+            Table(_) => builtin(|it| &it.table),
+            Mutable(_) => builtin(|it| &it.mutable),
+            Trait(_) => builtin(|it| &it.r#trait),
             Type(_) | Instance(_) => unreachable!(),
-        };
-
-        GcRef::new(ty)
-    }
-}
-
-impl Default for Value {
-    fn default() -> Self {
-        Self::Nil
+        }
     }
 }
 
@@ -188,9 +306,25 @@ impl std::fmt::Display for Value {
             List(xs) => format!("{}", *xs),
             Str(s) => "\"".to_owned() + s + "\"",
             Sym(s) => format!("{}", s),
-            Num(n) => n.to_string(),
+            Int(n) => n.to_string(),
+            BigInt(n) => n.to_string(),
+            Ratio(n) => n.to_string(),
+            Num(n) => format!("{:?}", n),
             Type(t) => format!("<type({})>", t.name),
+            // variants are shown the way they are built, like `Rect(1, 2)` or `None`
+            Instance(i) if i.ty.variant_of.is_some() && i.ty.params.is_empty() => {
+                i.ty.name.to_string()
+            }
+            Instance(i) if i.ty.variant_of.is_some() => {
+                let fields =
+                    i.ty.params
+                        .iter()
+                        .map(|it| i.fields.get(it).unwrap_or(Nil).to_string())
+                        .collect::<Vec<_>>();
+                format!("{}({})", i.ty.name, fields.join(", "))
+            }
             Instance(i) => format!("<instance({})>", i.ty.name),
+            Trait(t) => format!("<trait({})>", t.name),
             Table(t) => format!("{t}"),
             Mutable(t) => format!("Mutable<{}>", t.get()),
             Bool(b) => b.to_string(),
@@ -203,10 +337,16 @@ impl Add for Value {
     type Output = ConstantErr;
 
     fn add(self, rhs: Self) -> Self::Output {
+        if let Some(res) = number::arith(Op::Add, &self, &rhs) {
+            return res;
+        }
+
         match (self, rhs) {
-            (Self::Num(x), Self::Num(y)) => Ok(Self::Num(x + y)),
-            (Self::Str(x), Self::Str(y)) => Ok(Self::Str(GcRef::new(x.to_string() + &y))),
-            (s, r) => raise!("Can't apply `+` operator between {} and {}", s, r),
+            (Self::Str(x), Self::Str(y)) => {
+                gc::reserve_refs::<String>(1, x.len().saturating_add(y.len()))?;
+                Ok(Self::Str(GcRef::new(x.to_string() + &y)))
+            }
+            (s, r) => raise!(E0205; "Can't apply `+` operator between {} and {}", s, r),
         }
     }
 }
@@ -215,9 +355,9 @@ impl Sub for Value {
     type Output = ConstantErr;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        match (self, &rhs) {
-            (Self::Num(x), Self::Num(y)) => Ok(Self::Num(x - y)),
-            (s, r) => raise!("Can't apply `-` operator between {} and {}", s, r),
+        match number::arith(Op::Sub, &self, &rhs) {
+            Some(res) => res,
+            None => raise!(E0205; "Can't apply `-` operator between {} and {}", self, rhs),
         }
     }
 }
@@ -226,9 +366,9 @@ impl Mul for Value {
     type Output = ConstantErr;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        match (self, &rhs) {
-            (Self::Num(x), Self::Num(y)) => Ok(Self::Num(x * y)),
-            (s, r) => raise!("Can't apply `*` operator between {} and {}", s, r),
+        match number::arith(Op::Mul, &self, &rhs) {
+            Some(res) => res,
+            None => raise!(E0205; "Can't apply `*` operator between {} and {}", self, rhs),
         }
     }
 }
@@ -237,9 +377,20 @@ impl Div for Value {
     type Output = ConstantErr;
 
     fn div(self, rhs: Self) -> Self::Output {
-        match (self, &rhs) {
-            (Self::Num(x), Self::Num(y)) => Ok(Self::Num(x / y)),
-            (s, r) => raise!("Can't apply `/` operator between {} and {}", s, r),
+        match number::arith(Op::Div, &self, &rhs) {
+            Some(res) => res,
+            None => raise!(E0205; "Can't apply `/` operator between {} and {}", self, rhs),
+        }
+    }
+}
+
+impl Rem for Value {
+    type Output = ConstantErr;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        match number::arith(Op::Rem, &self, &rhs) {
+            Some(res) => res,
+            None => raise!(E0205; "Can't apply `%` between {} and {}", self, rhs),
         }
     }
 }
@@ -248,9 +399,9 @@ impl Neg for Value {
     type Output = ConstantErr;
 
     fn neg(self) -> Self::Output {
-        match self {
-            Self::Num(n) => Ok(Self::Num(-n)),
-            s => raise!("Can't apply unary `-` operator on {}", s),
+        match number::neg(&self) {
+            Some(n) => Ok(n),
+            None => raise!(E0205; "Can't apply unary `-` operator on {}", self),
         }
     }
 }
@@ -267,12 +418,7 @@ impl BitXor for Value {
     type Output = ConstantErr;
 
     fn bitxor(self, rhs: Self) -> Self::Output {
-        use Value::*;
-
-        match (self, rhs) {
-            (Num(x), Num(y)) => Ok(Num(((x.round() as i64) ^ (y.round() as i64)) as f64)),
-            (x, y) => raise!("Can't apply bitwise `^` between {} and {}", x, y),
-        }
+        number::bitwise("^^^", self, rhs, |x, y| Some(x ^ y), |x, y| Some(x ^ y))
     }
 }
 
@@ -280,12 +426,7 @@ impl BitAnd for Value {
     type Output = ConstantErr;
 
     fn bitand(self, rhs: Self) -> Self::Output {
-        use Value::*;
-
-        match (self, rhs) {
-            (Num(x), Num(y)) => Ok(Num(((x.round() as i64) & (y.round() as i64)) as f64)),
-            (x, y) => raise!("Can't apply bitwise `&` between {} and {}", x, y),
-        }
+        number::bitwise("&&&", self, rhs, |x, y| Some(x & y), |x, y| Some(x & y))
     }
 }
 
@@ -293,12 +434,7 @@ impl BitOr for Value {
     type Output = ConstantErr;
 
     fn bitor(self, rhs: Self) -> Self::Output {
-        use Value::*;
-
-        match (self, rhs) {
-            (Num(x), Num(y)) => Ok(Num(((x.round() as i64) | (y.round() as i64)) as f64)),
-            (x, y) => raise!("Can't apply bitwise `|` between {} and {}", x, y),
-        }
+        number::bitwise("|||", self, rhs, |x, y| Some(x | y), |x, y| Some(x | y))
     }
 }
 
@@ -306,12 +442,13 @@ impl Shr for Value {
     type Output = ConstantErr;
 
     fn shr(self, rhs: Self) -> Self::Output {
-        use Value::*;
-
-        match (self, rhs) {
-            (Num(x), Num(y)) => Ok(Num(((x.round() as i64) >> (y.round() as i64)) as f64)),
-            (x, y) => raise!("Can't apply bitwise `>>` between {} and {}", x, y),
-        }
+        number::bitwise(
+            ">>>",
+            self,
+            rhs,
+            |x, y| u32::try_from(y).ok().and_then(|y| x.checked_shr(y)),
+            |x, y| y.to_usize().map(|y| x >> y),
+        )
     }
 }
 
@@ -319,25 +456,7 @@ impl Shl for Value {
     type Output = ConstantErr;
 
     fn shl(self, rhs: Self) -> Self::Output {
-        use Value::*;
-
-        match (self, rhs) {
-            (Num(x), Num(y)) => Ok(Num(((x.round() as i64) << (y.round() as i64)) as f64)),
-            (x, y) => raise!("Can't apply bitwise `<<` between {} and {}", x, y),
-        }
-    }
-}
-
-impl Rem for Value {
-    type Output = ConstantErr;
-
-    fn rem(self, rhs: Self) -> Self::Output {
-        use Value::*;
-
-        match (self, rhs) {
-            (Num(x), Num(y)) => Ok(Num(x % y)),
-            (x, y) => raise!("Can't apply `%` between {} and {}", x, y),
-        }
+        number::shl(self, rhs)
     }
 }
 
@@ -368,6 +487,7 @@ macro_rules! impl_get {
     };
 }
 impl_get!(String: Str(s) => s.to_string());
+impl_get!(i64: Int);
 impl_get!(f64: Num);
 impl_get!(bool: Bool);
 impl_get!(GcRef<YexType>: Type);
@@ -0,0 +1,362 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Pow, ToPrimitive, Zero};
+
+use crate::{
+    error::InterpretResult,
+    gc::{self, GcRef},
+    raise, Value,
+};
+
+/// The largest number of bits an integer or the parts of a rational can have, about 80
+/// million decimal digits
+pub const MAX_BITS: u64 = 1 << 28;
+
+/// Arithmetic operators supported by every kind of number
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    FloorDiv,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Rem => "%",
+            Op::FloorDiv => "~/",
+        }
+    }
+}
+
+/// A number operand, ordered by how it's promoted when mixed with other numbers:
+/// integers become big integers on overflow, integers are promoted to rationals when mixed with
+/// them and every number is promoted to a float when mixed with a float
+enum Number {
+    Int(i64),
+    Big(BigInt),
+    Ratio(BigRational),
+    Float(f64),
+}
+
+impl Number {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(n) => Some(Self::Int(*n)),
+            Value::BigInt(n) => Some(Self::Big((**n).clone())),
+            Value::Ratio(n) => Some(Self::Ratio((**n).clone())),
+            Value::Num(n) => Some(Self::Float(*n)),
+            _ => None,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Int(_) => 0,
+            Self::Big(_) => 1,
+            Self::Ratio(_) => 2,
+            Self::Float(_) => 3,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            Self::Int(n) => *n == 0,
+            Self::Big(n) => n.is_zero(),
+            Self::Ratio(n) => n.is_zero(),
+            Self::Float(n) => *n == 0.0,
+        }
+    }
+
+    /// The number of bits needed to store the number exactly, zero for floats
+    fn bits(&self) -> u64 {
+        match self {
+            Self::Int(n) => u64::from(u64::BITS - n.unsigned_abs().leading_zeros()),
+            Self::Big(n) => n.bits(),
+            Self::Ratio(n) => n.numer().bits() + n.denom().bits(),
+            Self::Float(_) => 0,
+        }
+    }
+
+    fn into_big(self) -> BigInt {
+        match self {
+            Self::Int(n) => BigInt::from(n),
+            Self::Big(n) => n,
+            Self::Ratio(n) => n.to_integer(),
+            Self::Float(n) => BigInt::from(n as i64),
+        }
+    }
+
+    fn into_ratio(self) -> BigRational {
+        match self {
+            Self::Ratio(n) => n,
+            Self::Float(n) => BigRational::from_float(n).unwrap_or_default(),
+            n => BigRational::from_integer(n.into_big()),
+        }
+    }
+
+    fn into_float(self) -> f64 {
+        match self {
+            Self::Int(n) => n as f64,
+            Self::Big(n) => n.to_f64().unwrap_or(f64::NAN),
+            Self::Ratio(n) => n.to_f64().unwrap_or(f64::NAN),
+            Self::Float(n) => n,
+        }
+    }
+}
+
+/// Converts a number to a float, numbers that are equal always convert to the same float
+pub fn to_float(value: &Value) -> Option<f64> {
+    Number::from_value(value).map(Number::into_float)
+}
+
+/// Converts a big integer to a value, using `Value::Int` when it fits in 64 bits
+pub fn big(n: BigInt) -> Value {
+    match n.to_i64() {
+        Some(n) => Value::Int(n),
+        None => Value::BigInt(GcRef::new(n)),
+    }
+}
+
+/// Converts a rational to a value, using an integer when the denominator is one
+pub fn ratio(n: BigRational) -> Value {
+    if n.is_integer() {
+        big(n.to_integer())
+    } else {
+        Value::Ratio(GcRef::new(n))
+    }
+}
+
+/// Checks that a number of `bits` bits can be created, raising an error before computing it
+/// if it's larger than [`MAX_BITS`] or doesn't fit in the memory limit
+fn reserve_bits(bits: u64) -> InterpretResult<()> {
+    if bits > MAX_BITS {
+        raise!(
+            "Integer too large, the result would have more than {} bits",
+            MAX_BITS
+        )?;
+    }
+    gc::reserve_refs::<BigRational>(1, (bits / 8) as usize)
+}
+
+fn int_op(op: Op, x: i64, y: i64) -> Option<i64> {
+    match op {
+        Op::Add => x.checked_add(y),
+        Op::Sub => x.checked_sub(y),
+        Op::Mul => x.checked_mul(y),
+        // the integer division rounds down like `~/`, so it agrees with `%`
+        Op::Div | Op::FloorDiv => x.checked_div(y).map(|_| Integer::div_floor(&x, &y)),
+        Op::Rem => x.checked_rem(y).map(|_| Integer::mod_floor(&x, &y)),
+    }
+}
+
+fn big_op(op: Op, x: BigInt, y: BigInt) -> BigInt {
+    match op {
+        Op::Add => x + y,
+        Op::Sub => x - y,
+        Op::Mul => x * y,
+        Op::Div | Op::FloorDiv => Integer::div_floor(&x, &y),
+        Op::Rem => Integer::mod_floor(&x, &y),
+    }
+}
+
+fn ratio_op(op: Op, x: BigRational, y: BigRational) -> BigRational {
+    match op {
+        Op::Add => x + y,
+        Op::Sub => x - y,
+        Op::Mul => x * y,
+        Op::Div => x / y,
+        Op::Rem => x.clone() - y.clone() * (x / y).floor(),
+        Op::FloorDiv => (x / y).floor(),
+    }
+}
+
+fn float_op(op: Op, x: f64, y: f64) -> f64 {
+    match op {
+        Op::Add => x + y,
+        Op::Sub => x - y,
+        Op::Mul => x * y,
+        Op::Div => x / y,
+        Op::Rem => match x % y {
+            r if r != 0.0 && (r < 0.0) != (y < 0.0) => r + y,
+            r => r,
+        },
+        Op::FloorDiv => (x / y).floor(),
+    }
+}
+
+/// Applies an arithmetic operator on two numbers, returns `None` if one of the operands isn't
+/// a number
+pub fn arith(op: Op, lhs: &Value, rhs: &Value) -> Option<InterpretResult<Value>> {
+    let (x, y) = (Number::from_value(lhs)?, Number::from_value(rhs)?);
+    let rank = x.rank().max(y.rank());
+
+    if matches!(op, Op::Div | Op::Rem | Op::FloorDiv) && rank < 3 && y.is_zero() {
+        return Some(raise!(E0206; "Division by zero applying `{}`", op.symbol()));
+    }
+
+    if rank == 1 || rank == 2 {
+        // the result of every operator has at most as many bits as both operands together
+        if let Err(e) = reserve_bits(x.bits() + y.bits()) {
+            return Some(Err(e));
+        }
+    }
+
+    let res = match (x, y) {
+        (Number::Int(x), Number::Int(y)) => match int_op(op, x, y) {
+            Some(n) => Value::Int(n),
+            None => big(big_op(op, BigInt::from(x), BigInt::from(y))),
+        },
+        (x, y) if rank == 1 => big(big_op(op, x.into_big(), y.into_big())),
+        (x, y) if rank == 2 => ratio(ratio_op(op, x.into_ratio(), y.into_ratio())),
+        (x, y) => Value::Num(float_op(op, x.into_float(), y.into_float())),
+    };
+
+    Some(Ok(res))
+}
+
+/// Raises a number to the power of another, integer and rational bases raised to an integer
+/// exponent are kept exact, returns `None` if one of the operands isn't a number
+pub fn pow(lhs: &Value, rhs: &Value) -> Option<InterpretResult<Value>> {
+    let (x, y) = (Number::from_value(lhs)?, Number::from_value(rhs)?);
+
+    let exp = match &y {
+        Number::Int(n) => i32::try_from(*n).ok(),
+        _ => None,
+    };
+
+    // the result has at most `exp * log2(|x|)` bits, `0`, `1` and `-1` never grow
+    if let Some(exp) = exp.filter(|_| x.bits() > 1) {
+        if let Err(e) = reserve_bits(x.bits().saturating_mul(u64::from(exp.unsigned_abs()))) {
+            return Some(Err(e));
+        }
+    }
+
+    let res = match (x, exp) {
+        (Number::Float(x), _) => Value::Num(x.powf(y.into_float())),
+        (x, Some(exp)) if exp < 0 && x.is_zero() => {
+            return Some(raise!(E0206; "Division by zero applying `**`"))
+        }
+        (Number::Int(x), Some(exp)) if exp >= 0 => match x.checked_pow(exp as u32) {
+            Some(n) => Value::Int(n),
+            None => big(BigInt::from(x).pow(exp as u32)),
+        },
+        (Number::Big(x), Some(exp)) if exp >= 0 => big(x.pow(exp as u32)),
+        (x, Some(exp)) => ratio(x.into_ratio().pow(exp)),
+        (Number::Int(_) | Number::Big(_) | Number::Ratio(_), None)
+            if matches!(y, Number::Int(_) | Number::Big(_)) =>
+        {
+            return Some(raise!("Exponent {} is too large", rhs))
+        }
+        (x, None) => Value::Num(x.into_float().powf(y.into_float())),
+    };
+
+    Some(Ok(res))
+}
+
+/// Applies a bitwise not to an integer, returns `None` if the value isn't an integer
+pub fn bit_not(value: &Value) -> Option<Value> {
+    match value {
+        Value::Int(n) => Some(Value::Int(!n)),
+        Value::BigInt(n) => Some(big(!&**n)),
+        _ => None,
+    }
+}
+
+/// Negates a number, returns `None` if the value isn't a number
+pub fn neg(value: &Value) -> Option<Value> {
+    let res = match Number::from_value(value)? {
+        Number::Int(n) => match n.checked_neg() {
+            Some(n) => Value::Int(n),
+            None => big(-BigInt::from(n)),
+        },
+        Number::Big(n) => big(-n),
+        Number::Ratio(n) => ratio(-n),
+        Number::Float(n) => Value::Num(-n),
+    };
+
+    Some(res)
+}
+
+/// Compares two numbers, returns `None` if one of the operands isn't a number
+pub fn compare(lhs: &Value, rhs: &Value) -> Option<InterpretResult<Ordering>> {
+    let (x, y) = (Number::from_value(lhs)?, Number::from_value(rhs)?);
+    let rank = x.rank().max(y.rank());
+
+    let res = match (x, y) {
+        (Number::Int(x), Number::Int(y)) => Ok(x.cmp(&y)),
+        (x, y) if rank == 1 => Ok(x.into_big().cmp(&y.into_big())),
+        (x, y) if rank == 2 => Ok(x.into_ratio().cmp(&y.into_ratio())),
+        (x, y) => match x.into_float().partial_cmp(&y.into_float()) {
+            Some(ord) => Ok(ord),
+            None => raise!("Error applying cmp"),
+        },
+    };
+
+    Some(res)
+}
+
+/// Shifts an integer to the left, checking the size of the result before computing it
+pub fn shl(lhs: Value, rhs: Value) -> InterpretResult<Value> {
+    if let (Some(x), Some(y)) = (Number::from_value(&lhs), Number::from_value(&rhs)) {
+        let shift = match y {
+            Number::Int(y) => u64::try_from(y).unwrap_or(0),
+            Number::Big(y) if y > BigInt::zero() => u64::MAX,
+            _ => 0,
+        };
+        if matches!(x, Number::Int(_) | Number::Big(_)) && !x.is_zero() && shift > 0 {
+            reserve_bits(x.bits().saturating_add(shift))?;
+        }
+    }
+
+    bitwise(
+        "<<<",
+        lhs,
+        rhs,
+        |x, y| {
+            let y = u32::try_from(y).ok().filter(|y| *y < i64::BITS)?;
+            let n = x << y;
+            (n >> y == x).then_some(n)
+        },
+        |x, y| y.to_usize().map(|y| x << y),
+    )
+}
+
+/// Applies a bitwise operator on two integers, `int` is tried first and `big` is used when it
+/// returns `None` or when one of the operands is a big integer
+pub fn bitwise(
+    op: &str,
+    lhs: Value,
+    rhs: Value,
+    int: fn(i64, i64) -> Option<i64>,
+    big_op: fn(BigInt, &BigInt) -> Option<BigInt>,
+) -> InterpretResult<Value> {
+    let (x, y) = match (&lhs, &rhs) {
+        (Value::Int(x), Value::Int(y)) => {
+            if let Some(n) = int(*x, *y) {
+                return Ok(Value::Int(n));
+            }
+            (BigInt::from(*x), BigInt::from(*y))
+        }
+        (Value::Int(_) | Value::BigInt(_), Value::Int(_) | Value::BigInt(_)) => (
+            Number::from_value(&lhs).unwrap().into_big(),
+            Number::from_value(&rhs).unwrap().into_big(),
+        ),
+        (x, y) => return raise!(E0205; "Can't apply bitwise `{}` between {} and {}", op, x, y),
+    };
+
+    match big_op(x, &y) {
+        Some(n) => Ok(big(n)),
+        None => raise!(E0205; "Invalid operands for bitwise `{}`: {} and {}", op, lhs, rhs),
+    }
+}
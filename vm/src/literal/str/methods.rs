@@ -1,12 +1,32 @@
-use crate::{error::InterpretResult, literal::TryGet, Value, VirtualMachine, gc::GcRef, List};
+use crate::{
+    error::InterpretResult,
+    gc::{self, GcRef},
+    literal::{list::Node, nil, TryGet},
+    raise, List, Symbol, Value, VirtualMachine,
+};
 
 pub fn split(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
-    let string: String = args[0].get()?;
-    let pat: String = args[1].get()?;
-    let arr = string.trim().split(&pat).map(|x| {
-        Value::Str(GcRef::new(x.to_string()))
-    }).filter(|x| <Value as TryGet<String>>::get(x).unwrap()!= "").collect::<List>().rev();
-    Ok(Value::List(arr))
+    let string = self::string("split", &args[0])?;
+    let pat = self::string("split", &args[1])?;
+    let keep_empty = match args.get(2) {
+        None => false,
+        Some(Value::Bool(keep)) => *keep,
+        Some(other) => raise!("split() expected a boolean, but found `{}`", other)?,
+    };
+
+    let parts = if pat.is_empty() {
+        string.chars().count() + 2
+    } else {
+        string.matches(pat).count() + 1
+    };
+    reserve_strings(parts, string.len())?;
+    if keep_empty {
+        Ok(strings(string.split(pat)))
+    } else {
+        Ok(strings(
+            string.trim().split(pat).filter(|it| !it.is_empty()),
+        ))
+    }
 }
 
 pub fn contains(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
@@ -20,5 +40,242 @@ pub fn replace(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Valu
     let from: String = args[1].get()?;
     let to: String = args[2].get()?;
 
+    let matches = if from.is_empty() {
+        string.chars().count() + 1
+    } else {
+        string.matches(&from).count()
+    };
+    let len = matches
+        .checked_mul(to.len())
+        .and_then(|it| it.checked_add(string.len()));
+    reserve("replace", len)?;
     Ok(Value::Str(GcRef::new(string.replace(&from, &to))))
 }
+
+fn string<'a>(name: &str, value: &'a Value) -> InterpretResult<&'a str> {
+    match value {
+        Value::Str(s) => Ok(s.as_str()),
+        other => raise!("{}() expected a string, but found `{}`", name, other),
+    }
+}
+
+fn count(name: &str, value: &Value) -> InterpretResult<usize> {
+    match value {
+        Value::Int(n) if *n >= 0 => Ok(*n as usize),
+        other => raise!(
+            "{}() expected a valid positive integer, but found `{}`",
+            name,
+            other
+        ),
+    }
+}
+
+fn fill(name: &str, value: &Value) -> InterpretResult<char> {
+    let mut chars = string(name, value)?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => raise!(
+            "{}() expected a single character, but found `{}`",
+            name,
+            value
+        ),
+    }
+}
+
+fn new(s: impl Into<String>) -> Value {
+    Value::Str(GcRef::new(s.into()))
+}
+
+fn strings<'a>(xs: impl Iterator<Item = &'a str>) -> Value {
+    Value::List(List::from_vec(xs.map(new).collect()))
+}
+
+pub fn len(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("len", &args[0])?;
+    Ok(Value::Int(string.chars().count() as i64))
+}
+
+pub fn chars(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("chars", &args[0])?;
+    reserve_strings(string.chars().count(), string.len())?;
+    let chars = string.chars().map(|c| new(c.to_string())).collect();
+    Ok(Value::List(List::from_vec(chars)))
+}
+
+pub fn bytes(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("bytes", &args[0])?;
+    gc::reserve_refs::<Node>(string.len(), 0)?;
+    let bytes = string.bytes().map(|b| Value::Int(b.into())).collect();
+    Ok(Value::List(List::from_vec(bytes)))
+}
+
+pub fn upper(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("upper", &args[0])?.to_uppercase();
+    reserve("upper", Some(string.len()))?;
+    Ok(new(string))
+}
+
+pub fn lower(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("lower", &args[0])?.to_lowercase();
+    reserve("lower", Some(string.len()))?;
+    Ok(new(string))
+}
+
+pub fn trim(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(new(string("trim", &args[0])?.trim()))
+}
+
+pub fn trim_start(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(new(string("trim_start", &args[0])?.trim_start()))
+}
+
+pub fn trim_end(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(new(string("trim_end", &args[0])?.trim_end()))
+}
+
+pub fn starts_with(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("starts_with?", &args[0])?;
+    let prefix = self::string("starts_with?", &args[1])?;
+    Ok(Value::Bool(string.starts_with(prefix)))
+}
+
+pub fn ends_with(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("ends_with?", &args[0])?;
+    let suffix = self::string("ends_with?", &args[1])?;
+    Ok(Value::Bool(string.ends_with(suffix)))
+}
+
+pub fn find(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("find", &args[0])?;
+    let substr = self::string("find", &args[1])?;
+
+    // the index is returned in characters, not in bytes
+    match string.find(substr) {
+        Some(idx) => Ok(Value::Int(string[..idx].chars().count() as i64)),
+        None => Ok(nil()),
+    }
+}
+
+pub fn slice(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("slice", &args[0])?;
+    let start = count("slice", &args[1])?;
+    let end = count("slice", &args[2])?;
+
+    let res = string
+        .chars()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .collect::<String>();
+    Ok(new(res))
+}
+
+/// The largest string, in bytes, the methods that grow strings create
+const MAX_LEN: usize = 1 << 28;
+
+/// Checks that a string of `len` bytes, `None` if the length overflows, can be created before
+/// allocating it
+fn reserve(name: &str, len: Option<usize>) -> InterpretResult<()> {
+    match len {
+        Some(len) if len <= MAX_LEN => gc::reserve_refs::<String>(1, len),
+        _ => raise!(
+            "{}() can't create strings longer than {} bytes",
+            name,
+            MAX_LEN
+        ),
+    }
+}
+
+pub fn repeat(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("repeat", &args[0])?;
+    let n = count("repeat", &args[1])?;
+    reserve("repeat", string.len().checked_mul(n))?;
+    Ok(new(string.repeat(n)))
+}
+
+/// Checks that a list of at most `count` strings taking `len` bytes together can be created
+/// before allocating it
+fn reserve_strings(count: usize, len: usize) -> InterpretResult<()> {
+    let size = gc::size_of_ref::<Node>() + gc::size_of_ref::<String>();
+    gc::reserve(count.saturating_mul(size).saturating_add(len))
+}
+
+/// Returns how many `fill` characters pad `string` to `width` characters
+fn padding(name: &str, string: &str, width: usize, fill: char) -> InterpretResult<usize> {
+    let padding = width.saturating_sub(string.chars().count());
+    let len = padding
+        .checked_mul(fill.len_utf8())
+        .and_then(|it| it.checked_add(string.len()));
+    reserve(name, len)?;
+    Ok(padding)
+}
+
+pub fn pad_left(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("pad_left", &args[0])?;
+    let width = count("pad_left", &args[1])?;
+    let fill = fill("pad_left", &args[2])?;
+
+    let padding = padding("pad_left", string, width, fill)?;
+
+    let mut res = std::iter::repeat_n(fill, padding).collect::<String>();
+    res.push_str(string);
+    Ok(new(res))
+}
+
+pub fn pad_right(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("pad_right", &args[0])?;
+    let width = count("pad_right", &args[1])?;
+    let fill = fill("pad_right", &args[2])?;
+
+    let padding = padding("pad_right", string, width, fill)?;
+
+    let mut res = string.to_string();
+    res.extend(std::iter::repeat_n(fill, padding));
+    Ok(new(res))
+}
+
+pub fn lines(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("lines", &args[0])?;
+    reserve_strings(string.lines().count(), string.len())?;
+    Ok(strings(string.lines()))
+}
+
+pub fn reverse(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let string = string("reverse", &args[0])?;
+    reserve("reverse", Some(string.len()))?;
+    Ok(new(string.chars().rev().collect::<String>()))
+}
+
+pub fn to_sym(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    // symbols are stored with their leading colon, like `:name`
+    Ok(Value::Sym(Symbol::new(format!(
+        ":{}",
+        string("to_sym", &args[0])?
+    ))))
+}
+
+pub fn join(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let sep = string("join", &args[0])?;
+    let xs = match &args[1] {
+        Value::List(xs) => xs,
+        other => raise!("join() expected a list, but found `{}`", other)?,
+    };
+
+    let len = xs.iter().try_fold(0usize, |len, x| match x {
+        Value::Str(s) => len.checked_add(s.len())?.checked_add(sep.len()),
+        _ => Some(len),
+    });
+    reserve("join", len)?;
+
+    let mut res = String::new();
+    for (idx, x) in xs.iter().enumerate() {
+        if idx > 0 {
+            res.push_str(sep);
+        }
+        match x {
+            Value::Str(s) => res.push_str(&s),
+            other => raise!("join() expected a list of strings, but found `{}`", other)?,
+        }
+    }
+
+    Ok(new(res))
+}
@@ -37,16 +37,22 @@ impl std::fmt::Debug for Symbol {
     }
 }
 
+const fn hash(bytes: &[u8]) -> usize {
+    let mut hash: usize = 2166136261;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        hash ^= bytes[idx] as usize;
+        hash = hash.wrapping_mul(16777619);
+        idx += 1;
+    }
+    hash
+}
+
 impl Symbol {
     /// Creates a new symbol
     pub fn new<T: Into<String>>(str: T) -> Self {
         let str = str.into();
-
-        let mut hash: usize = 2166136261;
-        for b in str.bytes() {
-            hash ^= b as usize;
-            hash = hash.wrapping_mul(16777619);
-        }
+        let hash = hash(str.as_bytes());
 
         Self {
             string: Box::leak(str.into_boxed_str()),
@@ -54,6 +60,14 @@ impl Symbol {
         }
     }
 
+    /// Creates a symbol from a string that is already static, without allocating
+    pub const fn from_static(string: &'static str) -> Self {
+        Self {
+            string,
+            hash: hash(string.as_bytes()),
+        }
+    }
+
     /// Returns the intern symbol str
     pub fn as_str(&self) -> &str {
         self.string
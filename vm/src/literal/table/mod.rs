@@ -4,17 +4,10 @@ use super::TryGet;
 use crate::{List, Symbol, Value};
 
 pub mod methods;
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Default)]
 pub struct Table {
     pub items: List,
 }
-impl Default for Table {
-    fn default() -> Self {
-        Self {
-            items: List::new(),
-        }
-    }
-}
 impl Table {
     #[inline]
     pub fn new() -> Table {
@@ -45,6 +38,20 @@ impl Table {
                 xs.index(1)
             })
     }
+    /// Gets the value of the symbol key written as `name`, without interning a symbol for it
+    #[must_use]
+    pub fn get_str(&self, name: &str) -> Option<Value> {
+        self.items
+            .iter()
+            .find(|item| {
+                let list: List = item.get().unwrap();
+                matches!(list.index(0), Value::Sym(key) if key.as_str() == name)
+            })
+            .map(|x| {
+                let xs: List = x.get().unwrap();
+                xs.index(1)
+            })
+    }
 }
 impl Display for Table {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
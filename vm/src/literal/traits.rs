@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::{
+    env::EnvTable, error::InterpretResult, gc::GcRef, literal::doc::Doc, raise, Fn, Symbol, Value,
+    YexType,
+};
+
+#[derive(Debug, PartialEq)]
+/// A Yex trait, the methods that several types share.
+pub struct Trait {
+    /// The name of the trait.
+    pub name: Symbol,
+    /// The methods of the trait with their number of parameters, `this` included.
+    pub methods: Vec<(Symbol, usize)>,
+    /// The methods with a default implementation, used by the types that don't define them.
+    pub defaults: EnvTable,
+    /// The documentation of the trait, taken from its `///` comment.
+    pub doc: Option<Doc>,
+}
+
+impl Trait {
+    /// Creates a new trait.
+    pub fn new(name: Symbol, methods: Vec<(Symbol, usize)>) -> Self {
+        Self {
+            name,
+            methods,
+            defaults: EnvTable::new(),
+            doc: None,
+        }
+    }
+
+    #[must_use]
+    /// Adds the default implementations of some of the methods.
+    pub fn with_defaults(mut self, defaults: EnvTable) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    #[must_use]
+    /// Attaches documentation to the trait.
+    pub fn with_doc(mut self, doc: impl Into<Doc>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    /// Returns the number of parameters of the method called `name`.
+    pub fn arity(&self, name: Symbol) -> Option<usize> {
+        self.methods
+            .iter()
+            .find(|(it, _)| *it == name)
+            .map(|(_, arity)| *arity)
+    }
+}
+
+#[derive(Debug, Default)]
+/// The methods added to a type by its `impl` blocks, and the traits they implement.
+pub struct Impls {
+    /// The methods of every trait implemented by the type.
+    pub methods: EnvTable,
+    /// The names of the traits implemented by the type.
+    pub traits: Vec<Symbol>,
+}
+
+/// Implements a trait for a type with the methods of an `impl` block, which must be the methods
+/// of the trait, the defaults of the trait are used for the ones that are missing.
+pub fn implement(
+    impls: &mut HashMap<Symbol, Impls>,
+    tr: &Trait,
+    ty: &YexType,
+    methods: Vec<(Symbol, GcRef<Fn>)>,
+) -> InterpretResult<()> {
+    for (name, method) in &methods {
+        match tr.arity(*name) {
+            None => raise!(E0212; "`{}` isn't a method of `{}`", name, tr.name)?,
+            Some(arity) if arity != method.arity => raise!(
+                E0212;
+                "The method `{}` of `{}` takes {} parameters, but the one of `{}` takes {}",
+                name,
+                tr.name,
+                arity,
+                ty.name,
+                method.arity
+            )?,
+            Some(_) => {}
+        }
+    }
+
+    let missing = tr
+        .methods
+        .iter()
+        .filter(|(name, _)| {
+            !methods.iter().any(|(it, _)| it == name) && tr.defaults.get(name).is_none()
+        })
+        .map(|(name, _)| format!("`{}`", name))
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        raise!(
+            E0212;
+            "`{}` doesn't implement `{}`, {} missing",
+            ty.name,
+            tr.name,
+            match missing.len() {
+                1 => format!("the method {} is", missing[0]),
+                _ => format!("the methods {} are", missing.join(", ")),
+            }
+        )?;
+    }
+
+    let entry = impls.entry(ty.name).or_default();
+    for (name, _) in &tr.methods {
+        let method = match methods.iter().find(|(it, _)| it == name) {
+            Some((_, method)) => Value::Fn(method.clone()),
+            None => tr.defaults.get(name).unwrap(),
+        };
+        entry.methods.insert(*name, method);
+    }
+    entry.traits.push(tr.name);
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::{error::InterpretResult, raise, Value, VirtualMachine};
+
+use super::{construct, field};
+
+fn is(args: &[Value], variant: &str) -> Value {
+    Value::Bool(args[0].tag().is_some_and(|it| it.as_str() == variant))
+}
+
+pub fn some(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(is(&args, "Some"))
+}
+
+pub fn none(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(is(&args, "None"))
+}
+
+pub fn ok(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(is(&args, "Ok"))
+}
+
+pub fn err(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(is(&args, "Err"))
+}
+
+// `Some` and `Ok` keep their value in the `value` field, `None` and `Err` don't have it
+
+pub fn unwrap(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    match field(&args[0], "value") {
+        Some(value) => Ok(value),
+        None => raise!("Called `unwrap` on `{}`", args[0]),
+    }
+}
+
+pub fn unwrap_or(_: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    Ok(field(&args[0], "value").unwrap_or_else(|| args[1].clone()))
+}
+
+pub fn map(vm: *mut VirtualMachine, args: Vec<Value>) -> InterpretResult<Value> {
+    let vm = unsafe { &mut *vm };
+    match field(&args[0], "value") {
+        Some(value) => {
+            let value = vm.call_with(&args[1], &[value])?;
+            Ok(construct(args[0].type_of(), vec![value]))
+        }
+        None => Ok(args[0].clone()),
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{gc::GcRef, EnvTable, Symbol, Value, YexType};
+
+use super::instance::Instance;
+
+pub mod methods;
+
+/// Creates an instance of a variant from its fields, without running its initializer
+pub fn construct(ty: GcRef<YexType>, fields: Vec<Value>) -> Value {
+    let mut table = EnvTable::new();
+    for (name, value) in ty.params.iter().zip(fields) {
+        table.insert(*name, value);
+    }
+    Value::Instance(GcRef::new(Instance::new(ty, table)))
+}
+
+/// Returns the field called `name` of the variant instance `value`
+pub fn field(value: &Value, name: &str) -> Option<Value> {
+    match value {
+        Value::Instance(inst) => inst.fields.get(&Symbol::from(name)),
+        _ => None,
+    }
+}
@@ -1,8 +1,32 @@
-use crate::{env::EnvTable, error::InterpretResult, gc::GcRef, Symbol, Value, VirtualMachine, raise};
+use crate::{
+    env::EnvTable, error::InterpretResult, gc::GcRef, raise, Symbol, Value, VirtualMachine,
+};
 
-use super::{fun::Fn, instance::Instance, list, table, str, mutable::{self}};
+use super::{
+    doc::Doc,
+    fun::{Fn, FnKind, NativeFn},
+    instance::Instance,
+    list,
+    mutable::{self},
+    str, table, variant,
+};
 
-#[derive(Debug, PartialEq)]
+/// A builtin method: its name, number of parameters, implementation and documentation
+type Native = (&'static str, usize, NativeFn, &'static str);
+
+/// Builds the methods of a builtin type from its static table
+fn natives(natives: &'static [Native]) -> EnvTable {
+    let mut methods = EnvTable::new();
+    for (name, arity, fun, doc) in natives {
+        methods.insert(
+            Symbol::from(*name),
+            Value::Fn(GcRef::new(Fn::new_native(*arity, *fun).with_doc(*doc))),
+        );
+    }
+    methods
+}
+
+#[derive(Debug, PartialEq, Clone)]
 /// A Yex user-defined type.
 pub struct YexType {
     /// The name of the type.
@@ -11,8 +35,20 @@ pub struct YexType {
     pub fields: EnvTable,
     /// The parameters that the type needs to be instantiated.
     pub params: Vec<Symbol>,
+    /// The annotated types of the parameters, checked when the virtual machine checks types.
+    pub annotations: Vec<Option<Symbol>>,
     /// The method that runs after the type is instantiated.
     pub initializer: Option<GcRef<Fn>>,
+    /// The documentation of the type, taken from its `///` comment.
+    pub doc: Option<Doc>,
+    /// The variants of the type, each one a type of its own, empty for single shape types.
+    pub variants: Vec<GcRef<YexType>>,
+    /// The name of the type this one is a variant of.
+    pub variant_of: Option<Symbol>,
+    /// The symbol that `tag` returns for the values of a variant, like `:Circle`.
+    pub tag: Option<Symbol>,
+    /// The type this one extends, whose methods it inherits.
+    pub parent: Option<GcRef<YexType>>,
 }
 
 impl YexType {
@@ -22,7 +58,13 @@ impl YexType {
             name,
             fields,
             params,
+            annotations: Vec::new(),
             initializer: None,
+            doc: None,
+            variants: Vec::new(),
+            variant_of: None,
+            tag: None,
+            parent: None,
         }
     }
 
@@ -33,46 +75,239 @@ impl YexType {
         self
     }
 
-    /// Creates a new List type.
-    pub fn list() -> Self {
-        let mut methods = EnvTable::new();
+    #[must_use]
+    /// Annotates the types of the parameters.
+    pub fn with_annotations(mut self, annotations: Vec<Option<Symbol>>) -> Self {
+        self.annotations = annotations;
+        self
+    }
 
-        methods.insert(
-            Symbol::from("head"),
-            Value::Fn(GcRef::new(Fn::new_native(1, list::methods::head))),
-        );
+    #[must_use]
+    /// Attaches documentation to the type.
+    pub fn with_doc(mut self, doc: impl Into<Doc>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
 
-        methods.insert(
-            Symbol::from("tail"),
-            Value::Fn(GcRef::new(Fn::new_native(1, list::methods::tail))),
-        );
+    #[must_use]
+    /// Adds the variants of the type.
+    pub fn with_variants(mut self, variants: Vec<GcRef<YexType>>) -> Self {
+        self.variants = variants;
+        self
+    }
 
-        methods.insert(
-            Symbol::from("map"),
-            Value::Fn(GcRef::new(Fn::new_native(2, list::methods::map))),
-        );
+    #[must_use]
+    /// Makes the type a variant of the type called `name`.
+    pub fn with_variant_of(mut self, name: Symbol) -> Self {
+        self.variant_of = Some(name);
+        // symbols keep their colon, like when they are written in the source
+        self.tag = Some(Symbol::new(format!(":{}", self.name)));
+        self
+    }
 
-        methods.insert(
-            Symbol::from("filter"),
-            Value::Fn(GcRef::new(Fn::new_native(2, list::methods::filter))),
-        );
+    #[must_use]
+    /// Makes the type extend `parent`.
+    pub fn with_parent(mut self, parent: GcRef<YexType>) -> Self {
+        self.parent = Some(parent);
+        self
+    }
 
-        methods.insert(
-            Symbol::from("fold"),
-            Value::Fn(GcRef::new(Fn::new_native(3, list::methods::fold))),
-        );
+    /// Checks if the type extends the type called `name`, directly or through its parent.
+    pub fn extends(&self, name: Symbol) -> bool {
+        let mut parent = self.parent.as_ref();
+        while let Some(ty) = parent {
+            if ty.name == name {
+                return true;
+            }
+            parent = ty.parent.as_ref();
+        }
+        false
+    }
 
-        methods.insert(
-            Symbol::from("rev"),
-            Value::Fn(GcRef::new(Fn::new_native(2, list::methods::rev))),
-        );
+    /// Returns the variant called `name`.
+    pub fn variant(&self, name: Symbol) -> Option<GcRef<YexType>> {
+        self.variants.iter().find(|it| it.name == name).cloned()
+    }
 
-        methods.insert(
-            Symbol::from("get"),
-            Value::Fn(GcRef::new(Fn::new_native(2, list::methods::get))),
-        );
+    /// Creates a new List type.
+    pub fn list() -> Self {
+        use list::methods::*;
+
+        static NATIVES: [Native; 28] = [
+            (
+                "head",
+                1,
+                head,
+                "xs.head()\nReturns the first element, or `nil` if the list is empty",
+            ),
+            (
+                "tail",
+                1,
+                tail,
+                "xs.tail()\nReturns every element but the first",
+            ),
+            (
+                "map",
+                2,
+                map,
+                "xs.map(f)\nApplies `f` to every element",
+            ),
+            (
+                "filter",
+                2,
+                filter,
+                "xs.filter(f)\nKeeps the elements for which `f` returns true",
+            ),
+            (
+                "fold",
+                3,
+                fold,
+                "xs.fold(acc, f)\nReduces the list from the left, calling `f(acc, x)` for every element",
+            ),
+            (
+                "rev",
+                1,
+                rev,
+                "xs.rev()\nReverses the list",
+            ),
+            (
+                "get",
+                2,
+                get,
+                "xs.get(n)\nReturns the element at index `n`, or `nil`",
+            ),
+            (
+                "len",
+                1,
+                len,
+                "xs.len()\nReturns the number of elements",
+            ),
+            (
+                "concat",
+                2,
+                concat,
+                "xs.concat(ys)\nAppends `ys` to the end of the list",
+            ),
+            (
+                "take",
+                2,
+                take,
+                "xs.take(n)\nReturns the first `n` elements",
+            ),
+            (
+                "drop",
+                2,
+                drop,
+                "xs.drop(n)\nReturns the list without its first `n` elements",
+            ),
+            (
+                "zip",
+                2,
+                zip,
+                "xs.zip(ys)\nPairs the elements of both lists, stopping at the shortest one",
+            ),
+            (
+                "enumerate",
+                1,
+                enumerate,
+                "xs.enumerate()\nPairs every element with its index",
+            ),
+            (
+                "flat_map",
+                2,
+                flat_map,
+                "xs.flat_map(f)\nApplies `f` to every element and concatenates the returned lists",
+            ),
+            (
+                "any?",
+                2,
+                any,
+                "xs.any?(f)\nReturns true if `f` returns true for some element",
+            ),
+            (
+                "all?",
+                2,
+                all,
+                "xs.all?(f)\nReturns true if `f` returns true for every element",
+            ),
+            (
+                "find",
+                2,
+                find,
+                "xs.find(f)\nReturns the first element for which `f` returns true, or `nil`",
+            ),
+            (
+                "index_of",
+                2,
+                index_of,
+                "xs.index_of(x)\nReturns the index of the first element equal to `x`, or `nil`",
+            ),
+            (
+                "contains?",
+                2,
+                contains,
+                "xs.contains?(x)\nReturns true if some element is equal to `x`",
+            ),
+            (
+                "sort",
+                1,
+                sort,
+                "xs.sort()\nSorts the list in ascending order, keeping equal elements in order",
+            ),
+            (
+                "sort_by",
+                2,
+                sort_by,
+                "xs.sort_by(f)\nSorts the list with the comparator `f`, which returns a negative number, zero or a positive number",
+            ),
+            (
+                "uniq",
+                1,
+                uniq,
+                "xs.uniq()\nRemoves the repeated elements, keeping the first occurrence",
+            ),
+            (
+                "group_by",
+                2,
+                group_by,
+                "xs.group_by(f)\nGroups the elements by the key returned by `f`, as a list of `[key, elements]` pairs",
+            ),
+            (
+                "partition",
+                2,
+                partition,
+                "xs.partition(f)\nSplits the list into `[matching, rest]` according to `f`",
+            ),
+            (
+                "sum",
+                1,
+                sum,
+                "xs.sum()\nAdds up the elements",
+            ),
+            (
+                "min",
+                1,
+                min,
+                "xs.min()\nReturns the smallest element, or `nil` if the list is empty",
+            ),
+            (
+                "max",
+                1,
+                max,
+                "xs.max()\nReturns the greatest element, or `nil` if the list is empty",
+            ),
+            (
+                "join",
+                2,
+                join,
+                "xs.join(sep)\nConverts the elements to strings and joins them with `sep`",
+            ),
+        ];
+
+        let methods = natives(&NATIVES);
 
         Self::new(Symbol::from("List"), methods, vec![])
+            .with_doc("An immutable linked list")
             .with_initializer(GcRef::new(Fn::new_native(1, list::methods::init)))
     }
     /// Creates a new Table type.
@@ -81,73 +316,262 @@ impl YexType {
 
         methods.insert(
             Symbol::from("get"),
-            Value::Fn(GcRef::new(Fn::new_native(2, table::methods::get))),
+            Value::Fn(GcRef::new(Fn::new_native(2, table::methods::get).with_doc(
+                "t.get(key)\nReturns the value stored under the symbol `key`, or `nil`",
+            ))),
         );
         methods.insert(
             Symbol::from("insert"),
-            Value::Fn(GcRef::new(Fn::new_native(3, table::methods::insert))),
+            Value::Fn(GcRef::new(
+                Fn::new_native(3, table::methods::insert).with_doc(
+                    "t.insert(key, value)\nReturns a new table with `value` stored under `key`",
+                ),
+            )),
         );
         Self::new(Symbol::from("Table"), methods, vec![])
+            .with_doc("An immutable map from symbols to values")
             .with_initializer(GcRef::new(Fn::new_native(1, table::methods::init)))
     }
-    /// Creates a new Num type.
+    /// Creates a new Num type, the parent of every number type.
     pub fn num() -> Self {
         let methods = EnvTable::new();
         Self::new(Symbol::from("Num"), methods, vec![])
+            .with_doc("Any number, the parent type of `Int`, `BigInt`, `Ratio` and `Float`")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| Ok(Value::Int(0)))))
+    }
+
+    /// Creates a new Int type.
+    pub fn int() -> Self {
+        let methods = EnvTable::new();
+        Self::new(Symbol::from("Int"), methods, vec![])
+            .with_doc("A 64-bit integer, promoted to `BigInt` on overflow")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| Ok(Value::Int(0)))))
+            .with_parent(GcRef::new(Self::num()))
+    }
+
+    /// Creates a new BigInt type.
+    pub fn bigint() -> Self {
+        let methods = EnvTable::new();
+        Self::new(Symbol::from("BigInt"), methods, vec![])
+            .with_doc("An arbitrary precision integer, an `Int` that doesn't fit in 64 bits")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| Ok(Value::Int(0)))))
+            .with_parent(GcRef::new(Self::int()))
+    }
+
+    /// Creates a new Ratio type.
+    pub fn ratio() -> Self {
+        let methods = EnvTable::new();
+        Self::new(Symbol::from("Ratio"), methods, vec![])
+            .with_doc("An exact fraction of two integers")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| Ok(Value::Int(0)))))
+            .with_parent(GcRef::new(Self::num()))
+    }
+
+    /// Creates a new Float type.
+    pub fn float() -> Self {
+        let methods = EnvTable::new();
+        Self::new(Symbol::from("Float"), methods, vec![])
+            .with_doc("A 64-bit floating point number")
             .with_initializer(GcRef::new(Fn::new_native(1, |_, _| Ok(Value::Num(0.0)))))
+            .with_parent(GcRef::new(Self::num()))
     }
 
     /// Creates a new Sym type.
     pub fn sym() -> Self {
         let methods = EnvTable::new();
-        Self::new(Symbol::from("Sym"), methods, vec![]).with_initializer(GcRef::new(
-            Fn::new_native(1, |_, _| Ok(Value::Sym(Symbol::from("nil")))),
-        ))
+        Self::new(Symbol::from("Sym"), methods, vec![])
+            .with_doc("An interned name, written like `:name`")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| {
+                Ok(Value::Sym(Symbol::from("nil")))
+            })))
     }
 
     /// Creates a new Str type.
     pub fn str() -> Self {
-        let mut methods = EnvTable::new();
+        use str::methods::*;
+
+        static NATIVES: [Native; 21] = [
+            (
+                "contains",
+                2,
+                contains,
+                "s.contains(sub)\nReturns true if the string contains `sub`",
+            ),
+            (
+                "replace",
+                3,
+                replace,
+                "s.replace(from, to)\nReplaces every occurrence of `from` with `to`",
+            ),
+            (
+                "len",
+                1,
+                len,
+                "s.len()\nReturns the number of characters",
+            ),
+            (
+                "chars",
+                1,
+                chars,
+                "s.chars()\nReturns the characters of the string",
+            ),
+            (
+                "bytes",
+                1,
+                bytes,
+                "s.bytes()\nReturns the UTF-8 bytes of the string",
+            ),
+            (
+                "upper",
+                1,
+                upper,
+                "s.upper()\nConverts the string to uppercase",
+            ),
+            (
+                "lower",
+                1,
+                lower,
+                "s.lower()\nConverts the string to lowercase",
+            ),
+            (
+                "trim",
+                1,
+                trim,
+                "s.trim()\nRemoves the leading and trailing whitespace",
+            ),
+            (
+                "trim_start",
+                1,
+                trim_start,
+                "s.trim_start()\nRemoves the leading whitespace",
+            ),
+            (
+                "trim_end",
+                1,
+                trim_end,
+                "s.trim_end()\nRemoves the trailing whitespace",
+            ),
+            (
+                "starts_with?",
+                2,
+                starts_with,
+                "s.starts_with?(prefix)\nReturns true if the string starts with `prefix`",
+            ),
+            (
+                "ends_with?",
+                2,
+                ends_with,
+                "s.ends_with?(suffix)\nReturns true if the string ends with `suffix`",
+            ),
+            (
+                "find",
+                2,
+                find,
+                "s.find(sub)\nReturns the character index of the first `sub`, or `nil`",
+            ),
+            (
+                "slice",
+                3,
+                slice,
+                "s.slice(start, end)\nReturns the characters from `start` up to `end`",
+            ),
+            (
+                "repeat",
+                2,
+                repeat,
+                "s.repeat(n)\nRepeats the string `n` times",
+            ),
+            (
+                "pad_left",
+                3,
+                pad_left,
+                "s.pad_left(width, c)\nPads the start of the string with `c` up to `width` characters",
+            ),
+            (
+                "pad_right",
+                3,
+                pad_right,
+                "s.pad_right(width, c)\nPads the end of the string with `c` up to `width` characters",
+            ),
+            (
+                "lines",
+                1,
+                lines,
+                "s.lines()\nSplits the string into its lines",
+            ),
+            (
+                "reverse",
+                1,
+                reverse,
+                "s.reverse()\nReverses the characters of the string",
+            ),
+            (
+                "to_sym",
+                1,
+                to_sym,
+                "s.to_sym()\nConverts the string to a symbol",
+            ),
+            (
+                "join",
+                2,
+                join,
+                "sep.join(xs)\nJoins a list of strings with `sep`",
+            ),
+        ];
+
+        let mut methods = natives(&NATIVES);
+        // `split` is the only method whose last argument is optional
         methods.insert(
             Symbol::from("split"),
-            Value::Fn(GcRef::new(Fn::new_native(2, str::methods::split))),
-        );
-        methods.insert(
-            Symbol::from("contains"),
-            Value::Fn(GcRef::new(Fn::new_native(2, str::methods::contains))),
-        );
-        methods.insert(
-            Symbol::from("replace"),
-            Value::Fn(GcRef::new(Fn::new_native(3, str::methods::replace))),
+            Value::Fn(GcRef::new(
+                Fn::new_native(3, split).with_optional(1).with_doc(
+                    "s.split(sep, keep_empty)\nSplits the string by `sep`, the string is trimmed \
+                     and the empty parts are dropped unless `keep_empty` is true",
+                ),
+            )),
         );
 
-        Self::new(Symbol::from("Str"), methods, vec![]).with_initializer(GcRef::new(
-            Fn::new_native(1, |_, _| Ok(Value::Str(GcRef::new(String::from(""))))),
-        ))
+        Self::new(Symbol::from("Str"), methods, vec![])
+            .with_doc("A UTF-8 string")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| {
+                Ok(Value::Str(GcRef::new(String::from(""))))
+            })))
     }
 
     /// Creates a new Bool type.
     pub fn bool() -> Self {
         let methods = EnvTable::new();
         Self::new(Symbol::from("Bool"), methods, vec![])
+            .with_doc("`true` or `false`")
             .with_initializer(Fn::new_native(1, |_, _| Ok(Value::Bool(false))).to_gcref())
     }
 
     /// Creates a new Fn type.
     pub fn fun() -> Self {
         let methods = EnvTable::new();
-        Self::new(Symbol::from("Fn"), methods, vec![]).with_initializer(GcRef::new(
-            Fn::new_native(1, |_, _| {
+        Self::new(Symbol::from("Fn"), methods, vec![])
+            .with_doc("A function, either native or written in Yex")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| {
                 Ok(Value::Fn(GcRef::new(Fn::new_native(0, |_, _| {
                     Ok(Value::Nil)
                 }))))
-            }),
-        ))
+            })))
+    }
+    /// Creates a new Trait type.
+    pub fn r#trait() -> Self {
+        let methods = EnvTable::new();
+        Self::new(Symbol::from("Trait"), methods, vec![])
+            .with_doc("The methods that several types share, implemented with `impl`")
+            .with_initializer(GcRef::new(Fn::new_native(1, |_, _| {
+                raise!("Traits are declared with `trait`, not instantiated")
+            })))
     }
+
     /// Creates a new Nil type.
     pub fn nil() -> Self {
         let methods = EnvTable::new();
         Self::new(Symbol::from("Nil"), methods, vec![])
+            .with_doc("The type of `nil`, the absence of a value")
             .with_initializer(GcRef::new(Fn::new_native(1, |_, _| Ok(Value::Nil))))
     }
     ///Creates a Mutable type
@@ -156,15 +580,189 @@ impl YexType {
 
         methods.insert(
             Symbol::from("value"),
-            Value::Fn(GcRef::new(Fn::new_native(1, mutable::methods::get))),
+            Value::Fn(GcRef::new(
+                Fn::new_native(1, mutable::methods::get)
+                    .with_doc("m.value()\nReturns the current value"),
+            )),
         );
         methods.insert(
             Symbol::from("set"),
-            Value::Fn(GcRef::new(Fn::new_native(2,mutable::methods::set))),
+            Value::Fn(GcRef::new(
+                Fn::new_native(2, mutable::methods::set)
+                    .with_doc("m.set(value)\nReplaces the current value"),
+            )),
         );
         Self::new(Symbol::from("Mutable"), methods, vec![])
+            .with_doc("A reference to a value that can be replaced")
             .with_initializer(GcRef::new(Fn::new_native(1, mutable::methods::init)))
     }
+
+    /// Creates a type with variants, every variant shares the methods of the type
+    fn variants(name: &str, variants: &[(&str, &[&str])], methods: &'static [Native]) -> Self {
+        let methods = natives(methods);
+
+        let variants = variants
+            .iter()
+            .map(|(variant, params)| {
+                let params = params.iter().map(Symbol::from).collect();
+                GcRef::new(
+                    Self::new(Symbol::from(variant), methods.clone(), params)
+                        .with_variant_of(Symbol::from(name)),
+                )
+            })
+            .collect();
+
+        Self::new(Symbol::from(name), methods, vec![]).with_variants(variants)
+    }
+
+    /// Creates a new Option type.
+    pub fn option() -> Self {
+        use variant::methods::*;
+
+        Self::variants(
+            "Option",
+            &[("Some", &["value"]), ("None", &[])],
+            &[
+                (
+                    "some?",
+                    1,
+                    some,
+                    "opt.some?()\nReturns true if the option is a `Some`",
+                ),
+                (
+                    "none?",
+                    1,
+                    none,
+                    "opt.none?()\nReturns true if the option is `None`",
+                ),
+                (
+                    "unwrap",
+                    1,
+                    unwrap,
+                    "opt.unwrap()\nReturns the value of a `Some`, raising an error for `None`",
+                ),
+                (
+                    "unwrap_or",
+                    2,
+                    unwrap_or,
+                    "opt.unwrap_or(default)\nReturns the value of a `Some`, or `default` for `None`",
+                ),
+                (
+                    "map",
+                    2,
+                    map,
+                    "opt.map(f)\nApplies `f` to the value of a `Some`, keeping `None` as it is",
+                ),
+            ],
+        )
+        .with_doc("A value that may be missing, either `Some(value)` or `None`")
+    }
+
+    /// Creates a new Result type.
+    pub fn result() -> Self {
+        use variant::methods::*;
+
+        Self::variants(
+            "Result",
+            &[("Ok", &["value"]), ("Err", &["error"])],
+            &[
+                (
+                    "ok?",
+                    1,
+                    ok,
+                    "res.ok?()\nReturns true if the result is an `Ok`",
+                ),
+                (
+                    "err?",
+                    1,
+                    err,
+                    "res.err?()\nReturns true if the result is an `Err`",
+                ),
+                (
+                    "unwrap",
+                    1,
+                    unwrap,
+                    "res.unwrap()\nReturns the value of an `Ok`, raising an error for an `Err`",
+                ),
+                (
+                    "unwrap_or",
+                    2,
+                    unwrap_or,
+                    "res.unwrap_or(default)\nReturns the value of an `Ok`, or `default` for an `Err`",
+                ),
+                (
+                    "map",
+                    2,
+                    map,
+                    "res.map(f)\nApplies `f` to the value of an `Ok`, keeping an `Err` as it is",
+                ),
+            ],
+        )
+        .with_doc("The outcome of something that can fail, either `Ok(value)` or `Err(error)`")
+    }
+}
+
+macro_rules! builtin_types {
+    ($($name:ident),* $(,)?) => {
+        /// The builtin types of the values, built once per thread and shared by all of them
+        pub(crate) struct Builtins {
+            $(pub $name: GcRef<YexType>,)*
+        }
+
+        thread_local! {
+            static BUILTINS: Builtins = Builtins {
+                $($name: GcRef::new(YexType::$name()),)*
+            };
+        }
+    };
+}
+
+builtin_types!(
+    list, table, num, int, bigint, ratio, float, sym, str, bool, fun, r#trait, nil, mutable,
+);
+
+/// Returns the shared builtin type picked by `pick`, like `builtin(|it| &it.list)`
+pub(crate) fn builtin(pick: fn(&Builtins) -> &GcRef<YexType>) -> GcRef<YexType> {
+    BUILTINS.with(|it| pick(it).clone())
+}
+
+/// Returns a copy of a type that extends `parent`, which must be a user type with the fields
+/// of the parent among its own.
+pub fn extend(ty: &YexType, parent: Value) -> InterpretResult<YexType> {
+    let parent = match parent {
+        Value::Type(parent) => parent,
+        value => raise!(E0213; "`{}` can only extend a type, found `{}`", ty.name, value)?,
+    };
+
+    if !parent.variants.is_empty() || parent.variant_of.is_some() {
+        raise!(E0213; "`{}` can't extend `{}`, which has variants", ty.name, parent.name)?;
+    }
+    // the builtin types are the only ones with native initializers
+    if let Some(FnKind::Native(_)) = parent.initializer.as_ref().map(|it| &*it.body) {
+        raise!(E0213; "`{}` can't extend the builtin type `{}`", ty.name, parent.name)?;
+    }
+
+    let missing = parent
+        .params
+        .iter()
+        .filter(|it| !ty.params.contains(it))
+        .map(|it| format!("`{}`", it))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        raise!(
+            E0213;
+            "`{}` extends `{}`, but doesn't have {} {}",
+            ty.name,
+            parent.name,
+            match missing.len() {
+                1 => "its field",
+                _ => "its fields",
+            },
+            missing.join(", ")
+        )?;
+    }
+
+    Ok(ty.clone().with_parent(parent))
 }
 
 /// Instantiates a type with the given parameters.
@@ -174,20 +772,57 @@ pub fn instantiate(
     ty: GcRef<YexType>,
     args: Vec<Value>,
 ) -> InterpretResult<()> {
+    if !ty.variants.is_empty() {
+        let names = ty
+            .variants
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        raise!(
+            E0211;
+            "`{}` can't be instantiated, use one of its variants: {}",
+            ty.name,
+            names.join(", ")
+        )?;
+    }
+
     if args.len() != ty.params.len() {
-        raise!("Wrong number of arguments for type instantiation")?;
+        raise!(E0202; "Wrong number of arguments for type instantiation")?;
     }
 
     let mut fields = EnvTable::new();
     for (i, arg) in args.iter().enumerate() {
+        match ty.annotations.get(i) {
+            Some(Some(annotation)) if vm.checks_types() && !arg.has_type(*annotation) => {
+                raise!(
+                    E0210;
+                    "Expected a value of type `{}` for the field `{}` of `{}`, found `{}` of type `{}`",
+                    annotation,
+                    ty.params[i],
+                    ty.name,
+                    arg,
+                    arg.type_of().name
+                )?;
+            }
+            _ => {}
+        }
         fields.insert(ty.params[i], arg.clone());
     }
 
     let inst = Value::Instance(GcRef::new(Instance::new(ty.clone(), fields)));
     vm.push(inst);
 
-    if let Some(initializer) = &ty.initializer {
-        vm.push(Value::Fn(initializer.clone()));
+    // the initializers of the types it extends run first, each one receives the value returned
+    // by the previous one
+    let mut initializers = Vec::new();
+    let mut current = Some(&ty);
+    while let Some(it) = current {
+        initializers.extend(it.initializer.clone());
+        current = it.parent.as_ref();
+    }
+
+    for initializer in initializers.into_iter().rev() {
+        vm.push(Value::Fn(initializer));
         vm.call(1)?;
     }
 
@@ -1,4 +1,4 @@
-use crate::Symbol;
+use crate::{Span, Symbol};
 
 /// OpCodes for the virtualMachine
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
@@ -102,6 +102,16 @@ pub enum OpCode {
     /// The stack layout after running it: [result]
     Div,
 
+    /// Raises the first value on the stack top to the power of the second
+    /// The stack layout before running this opcode: [const1, const2]
+    /// The stack layout after running it: [result]
+    Pow,
+
+    /// Divide the two values on the stack top, rounding the result towards negative infinity
+    /// The stack layout before running this opcode: [const1, const2]
+    /// The stack layout after running it: [result]
+    FloorDiv,
+
     /// Negates the value on the stack top
     /// The stack layout before running this opcode: [const]
     /// The stack layout after running it: [result]
@@ -137,6 +147,11 @@ pub enum OpCode {
     /// The stack layout after running it: [result]
     BitAnd,
 
+    /// Apply a bitwise not to the value on the stack top
+    /// The stack layout before running this opcode: [const]
+    /// The stack layout after running it: [result]
+    BitNot,
+
     /// Apply bit-or operation on the two values on the stack top
     /// The stack layout before running this opcode: [const1, const2]
     /// The stack layout after running it: [result]
@@ -171,26 +186,50 @@ pub enum OpCode {
     /// The stack layout before running this opcode: [instance, ...args]
     /// The stack layout after running it: [return-value]
     Invk(Symbol, usize),
+
+    /// Calls a method of the type that the type of a method extends, starting the lookup there
+    /// The stack layout before running this opcode: [type, instance, ...args]
+    /// The stack layout after running it: [return-value]
+    Super(Symbol, usize),
+
+    /// Makes a type extend another, inheriting its methods and its initializer
+    /// The stack layout before running this opcode: [parent, type]
+    /// The stack layout after running it: [extended-type]
+    Extend,
+
+    /// Implements a trait for a type, with the methods of an `impl` block and their names
+    /// The stack layout before running this opcode: [trait, type, ...(name, method)]
+    /// The stack layout after running it: []
+    Impl(usize),
+
+    /// Checks that the value on the stack top has the type of an annotation, only when the
+    /// virtual machine checks types
+    /// The stack layout before running this opcode: [value]
+    /// The stack layout after running it: [value]
+    Expect(Symbol),
 }
 
-/// Stocks the [`crate::OpCode`] with the line and the column of it on the original source code,
-/// make it possible to be used for error handling
+/// Stocks the [`crate::OpCode`] with the line, the column and the span of it on the original
+/// source code, make it possible to be used for error handling
 #[derive(Clone, Copy, Eq)]
 pub struct OpCodeMetadata {
     /// Source's code line
     pub line: usize,
     /// Source's code column
     pub column: usize,
+    /// The range of the source code that the opcode was compiled from
+    pub span: Span,
     /// Actual opcode
     pub opcode: OpCode,
 }
 
 impl OpCodeMetadata {
     /// Creates a new [`OpCodeMetadata`]
-    pub fn new(line: usize, column: usize, opcode: OpCode) -> Self {
+    pub fn new(line: usize, column: usize, span: Span, opcode: OpCode) -> Self {
         Self {
             line,
             column,
+            span,
             opcode,
         }
     }
@@ -0,0 +1,366 @@
+use std::{
+    iter::{Enumerate, Peekable},
+    str::Chars,
+};
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    gc::{self, GcRef},
+    literal::table::Table,
+    raise, BigInt, InterpretResult, List, Value,
+};
+
+/// The largest width and precision of a format spec
+const MAX_WIDTH: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Display,
+    Debug,
+    Hex,
+    UpperHex,
+    Octal,
+    Binary,
+}
+
+/// A parsed `{:...}` format spec: `[[fill]align][0][width][.precision][type]`
+#[derive(Debug)]
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Kind,
+}
+
+enum Arg {
+    Next,
+    Index(usize),
+    /// The name of a symbol key, looked up without interning it
+    Name(String),
+}
+
+enum Args {
+    List(Vec<Value>),
+    Table(Table),
+}
+
+struct Formatter<'a> {
+    chars: Peekable<Enumerate<Chars<'a>>>,
+    len: usize,
+    args: Args,
+    next: usize,
+}
+
+fn align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+impl<'a> Formatter<'a> {
+    /// Raises an error pointing to the character at `idx`
+    fn throw_at<T>(&self, idx: usize, msg: &str) -> InterpretResult<T> {
+        // columns are reported starting from 1, counting characters
+        raise!("format: {} at column {}", msg, idx + 1)
+    }
+
+    /// Raises an error pointing to the next character
+    fn throw<T>(&mut self, msg: &str) -> InterpretResult<T> {
+        let idx = match self.chars.peek() {
+            Some(&(idx, _)) => idx,
+            None => self.len,
+        };
+        self.throw_at(idx, msg)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if let Some(&(_, it)) = self.chars.peek() {
+            if it == c {
+                self.chars.next();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let mut digits = String::new();
+        while let Some(c @ '0'..='9') = self.peek() {
+            digits.push(c);
+            self.chars.next();
+        }
+        // numbers too large for an `usize` are still numbers, they are rejected by `bounded`
+        match digits.parse() {
+            Ok(n) => Some(n),
+            Err(_) if !digits.is_empty() => Some(usize::MAX),
+            Err(_) => None,
+        }
+    }
+
+    /// Parses a width or a precision, which can't be larger than [`MAX_WIDTH`]
+    fn bounded(&mut self, what: &str) -> InterpretResult<Option<usize>> {
+        let idx = match self.chars.peek() {
+            Some(&(idx, _)) => idx,
+            None => self.len,
+        };
+
+        match self.number() {
+            Some(n) if n > MAX_WIDTH => {
+                self.throw_at(idx, &format!("{} can't be larger than {}", what, MAX_WIDTH))
+            }
+            n => Ok(n),
+        }
+    }
+
+    fn arg(&mut self) -> InterpretResult<Arg> {
+        if let Some(n) = self.number() {
+            return Ok(Arg::Index(n));
+        }
+
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+            name.push(c);
+            self.chars.next();
+        }
+
+        // symbols are stored with their leading colon, like `:name`
+        if name.is_empty() {
+            Ok(Arg::Next)
+        } else {
+            Ok(Arg::Name(format!(":{}", name)))
+        }
+    }
+
+    fn spec(&mut self) -> InterpretResult<Spec> {
+        let mut spec = Spec {
+            fill: ' ',
+            align: None,
+            zero: false,
+            width: 0,
+            precision: None,
+            kind: Kind::Display,
+        };
+
+        if !self.eat(':') {
+            return Ok(spec);
+        }
+
+        // the fill character can only be known by looking at the character after it
+        let mut lookahead = self.chars.clone();
+        match (lookahead.next(), lookahead.next()) {
+            (Some((_, fill)), Some((_, c))) if align(c).is_some() => {
+                spec.fill = fill;
+                spec.align = align(c);
+                self.chars.next();
+                self.chars.next();
+            }
+            (Some((_, c)), _) if align(c).is_some() => {
+                spec.align = align(c);
+                self.chars.next();
+            }
+            _ => {}
+        }
+
+        if self.peek() == Some('0') {
+            spec.zero = true;
+            self.chars.next();
+        }
+
+        if let Some(width) = self.bounded("the width")? {
+            spec.width = width;
+        }
+
+        if self.eat('.') {
+            match self.bounded("the precision")? {
+                Some(precision) => spec.precision = Some(precision),
+                None => self.throw("expected a precision after `.`")?,
+            }
+        }
+
+        spec.kind = match self.peek() {
+            Some('?') => Kind::Debug,
+            Some('x') => Kind::Hex,
+            Some('X') => Kind::UpperHex,
+            Some('o') => Kind::Octal,
+            Some('b') => Kind::Binary,
+            _ => return Ok(spec),
+        };
+        self.chars.next();
+
+        Ok(spec)
+    }
+
+    fn value(&mut self, start: usize, arg: Arg) -> InterpretResult<Value> {
+        let value = match (&self.args, arg) {
+            (Args::List(xs), Arg::Next) => {
+                self.next += 1;
+                xs.get(self.next - 1).cloned()
+            }
+            (Args::List(xs), Arg::Index(n)) => xs.get(n).cloned(),
+            (Args::Table(table), Arg::Name(name)) => table.get_str(&name),
+            (Args::List(_), Arg::Name(name)) => {
+                return self.throw_at(
+                    start,
+                    &format!("named argument `{}` needs a table of arguments", name),
+                )
+            }
+            (Args::Table(_), _) => {
+                return self.throw_at(start, "positional arguments need a list of arguments")
+            }
+        };
+
+        match value {
+            Some(value) => Ok(value),
+            None => self.throw_at(start, "missing argument"),
+        }
+    }
+
+    fn render(&self, start: usize, value: &Value, spec: &Spec) -> InterpretResult<String> {
+        let int = |value: &Value| match value {
+            Value::Int(n) => Some(BigInt::from(*n)),
+            Value::BigInt(n) => Some((**n).clone()),
+            _ => None,
+        };
+
+        let text = match (spec.kind, value) {
+            (Kind::Debug, value) => format!("{:#?}", value),
+            (Kind::Display, Value::Str(s)) => match spec.precision {
+                Some(precision) => s.chars().take(precision).collect(),
+                None => s.to_string(),
+            },
+            (
+                Kind::Display,
+                n @ (Value::Int(_) | Value::BigInt(_) | Value::Ratio(_) | Value::Num(_)),
+            ) if spec.precision.is_some() => {
+                let n = match n {
+                    Value::Int(n) => *n as f64,
+                    Value::BigInt(n) => n.to_f64().unwrap_or(f64::NAN),
+                    Value::Ratio(n) => n.to_f64().unwrap_or(f64::NAN),
+                    Value::Num(n) => *n,
+                    _ => unreachable!(),
+                };
+                format!("{:.*}", spec.precision.unwrap(), n)
+            }
+            (Kind::Display, value) => value.to_string(),
+            (kind, value) => match int(value) {
+                Some(n) => match kind {
+                    Kind::Hex => format!("{:x}", n),
+                    Kind::UpperHex => format!("{:X}", n),
+                    Kind::Octal => format!("{:o}", n),
+                    _ => format!("{:b}", n),
+                },
+                None => {
+                    return self.throw_at(
+                        start,
+                        &format!("can't format {} as a hex, octal or binary number", value),
+                    )
+                }
+            },
+        };
+
+        Ok(pad(text, value, spec))
+    }
+
+    fn run(&mut self) -> InterpretResult<String> {
+        let mut res = String::new();
+
+        while let Some((idx, c)) = self.chars.next() {
+            match c {
+                '{' if self.eat('{') => res.push('{'),
+                '}' if self.eat('}') => res.push('}'),
+                '{' => {
+                    let arg = self.arg()?;
+                    let spec = self.spec()?;
+                    if !self.eat('}') {
+                        self.throw("expected `}` to close the placeholder")?;
+                    }
+
+                    let value = self.value(idx, arg)?;
+                    res.push_str(&self.render(idx, &value, &spec)?);
+                }
+                '}' => self.throw_at(idx, "unmatched `}`, use `}}` to write a brace")?,
+                c => res.push(c),
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+fn pad(text: String, value: &Value, spec: &Spec) -> String {
+    let len = text.chars().count();
+    if len >= spec.width {
+        return text;
+    }
+
+    let padding = spec.width - len;
+    let number = matches!(
+        value,
+        Value::Int(_) | Value::BigInt(_) | Value::Ratio(_) | Value::Num(_)
+    );
+
+    // `{:08}` pads numbers with zeros after the sign
+    if spec.zero && spec.align.is_none() && number {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        return format!("{}{}{}", sign, "0".repeat(padding), digits);
+    }
+
+    let fill = |n| std::iter::repeat_n(spec.fill, n).collect::<String>();
+    let align = match spec.align {
+        Some(align) => align,
+        None if number => Align::Right,
+        None => Align::Left,
+    };
+
+    match align {
+        Align::Left => text + &fill(padding),
+        Align::Right => fill(padding) + &text,
+        Align::Center => fill(padding / 2) + &text + &fill(padding - padding / 2),
+    }
+}
+
+/// Formats a string, replacing the `{}` placeholders with the given arguments, which can be a
+/// list for positional placeholders (`{}`, `{0}`) or a table for named ones (`{name}`)
+pub fn format(args: &[Value]) -> InterpretResult<Value> {
+    let format = match &args[0] {
+        Value::Str(s) => s,
+        other => raise!("format() expected a string, but found `{}`", other)?,
+    };
+
+    let args = match &args[1] {
+        Value::List(xs) => Args::List(List::to_vec(xs)),
+        Value::Table(table) => Args::Table(table.clone()),
+        other => raise!("format() expected a list or a table, but found `{}`", other)?,
+    };
+
+    let mut formatter = Formatter {
+        chars: format.chars().enumerate().peekable(),
+        len: format.chars().count(),
+        args,
+        next: 0,
+    };
+
+    let res = formatter.run()?;
+    gc::reserve_refs::<String>(1, res.len())?;
+    Ok(Value::Str(GcRef::new(res)))
+}
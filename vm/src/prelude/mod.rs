@@ -1,39 +1,51 @@
+mod format;
+
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+
 use crate::{
+    builder::Capability,
     env::EnvTable,
     gc::GcRef,
-    literal::{fun::FnKind, nil, TryGet, Value},
-    raise, InterpretResult, List, YexType,
+    literal::{doc::Doc, fun::Fn, list, nil, number, variant, yextype::builtin, TryGet, Value},
+    raise, BigInt, BigRational, ErrorKind, InterpretResult, VirtualMachine, YexType,
 };
-use std::io::Write;
 
-fn println(args: &[Value]) -> InterpretResult<Value> {
-    match &args[0] {
-        Value::Str(s) => println!("{}", &**s),
-        other => println!("{}", other),
+fn println(vm: &mut VirtualMachine, args: &[Value]) -> InterpretResult<Value> {
+    let res = match &args[0] {
+        Value::Str(s) => writeln!(vm.stdout(), "{}", &**s),
+        other => writeln!(vm.stdout(), "{}", other),
     };
+
+    if res.is_err() {
+        raise!("Error writing to stdout")?;
+    }
     Ok(nil())
 }
 
-fn print(args: &[Value]) -> InterpretResult<Value> {
-    match &args[0] {
-        Value::Str(s) => print!("{}", &**s),
-        other => print!("{}", other),
+fn print(vm: &mut VirtualMachine, args: &[Value]) -> InterpretResult<Value> {
+    let res = match &args[0] {
+        Value::Str(s) => write!(vm.stdout(), "{}", &**s),
+        other => write!(vm.stdout(), "{}", other),
     };
+
+    if res.is_err() {
+        raise!("Error writing to stdout")?;
+    }
     Ok(nil())
 }
 
-fn input(args: &[Value]) -> InterpretResult<Value> {
-    match &args[0] {
-        Value::Str(s) => print!("{}", **s),
-        other => print!("{}", other),
+fn input(vm: &mut VirtualMachine, args: &[Value]) -> InterpretResult<Value> {
+    let res = match &args[0] {
+        Value::Str(s) => write!(vm.stdout(), "{}", **s),
+        other => write!(vm.stdout(), "{}", other),
     };
 
-    if std::io::stdout().flush().is_err() {
+    if res.is_err() || vm.stdout().flush().is_err() {
         raise!("Error flushing stdout")?;
     }
 
     let mut input = String::new();
-    if std::io::stdin().read_line(&mut input).is_err() {
+    if vm.stdin().read_line(&mut input).is_err() {
         raise!("Error reading line")?;
     }
 
@@ -53,6 +65,36 @@ fn r#typeof(args: &[Value]) -> InterpretResult<Value> {
     Ok(Value::Type(args[0].type_of()))
 }
 
+fn tag(args: &[Value]) -> InterpretResult<Value> {
+    match &args[0] {
+        Value::Instance(inst) => Ok(inst.ty.tag.map_or(Value::Nil, Value::Sym)),
+        _ => Ok(Value::Nil),
+    }
+}
+
+fn variants(args: &[Value]) -> InterpretResult<Value> {
+    match &args[0] {
+        Value::Type(ty) => Ok(Value::List(
+            ty.variants.iter().rev().cloned().map(Value::Type).collect(),
+        )),
+        other => raise!("variants() expected a type, found {}", other),
+    }
+}
+
+fn is_a(args: &[Value]) -> InterpretResult<Value> {
+    match &args[1] {
+        Value::Type(ty) => Ok(Value::Bool(args[0].has_type(ty.name))),
+        other => raise!("is_a?() expected a type, found {}", other),
+    }
+}
+
+fn implements(vm: &mut VirtualMachine, args: &[Value]) -> InterpretResult<Value> {
+    match &args[1] {
+        Value::Trait(tr) => Ok(Value::Bool(vm.implements(&args[0], tr))),
+        other => raise!("implements?() expected a trait, found {}", other),
+    }
+}
+
 fn inspect(args: &[Value]) -> InterpretResult<Value> {
     Ok(Value::Str(GcRef::new(format!("{:#?}", &args[0]))))
 }
@@ -60,79 +102,121 @@ fn inspect(args: &[Value]) -> InterpretResult<Value> {
 fn num(args: &[Value]) -> InterpretResult<Value> {
     let str = match &args[0] {
         Value::Sym(symbol) => symbol.as_str(),
-        Value::Str(str) => &*str,
-        n @ Value::Num(..) => return Ok(n.clone()),
+        Value::Str(str) => str,
+        n @ (Value::Int(..) | Value::BigInt(..) | Value::Ratio(..) | Value::Num(..)) => {
+            return Ok(n.clone())
+        }
         other => raise!("Expected a string or a symbol, found {}", other)?,
     };
 
+    if let Ok(n) = str.parse::<BigInt>() {
+        return Ok(number::big(n));
+    }
+
+    if let Ok(n) = str.parse::<BigRational>() {
+        return Ok(number::ratio(n));
+    }
+
     match str.parse::<f64>() {
         Ok(n) => Ok(Value::Num(n)),
         Err(e) => raise!("{:?}", e),
     }
 }
 
-fn exit(args: &[Value]) -> InterpretResult<Value> {
-    let code: f64 = args[0].get()?;
-    if code.fract() != 0.0 {
-        raise!("Expected an integer, found {}", code)?;
+fn int(args: &[Value]) -> InterpretResult<Value> {
+    match &args[0] {
+        n @ (Value::Int(..) | Value::BigInt(..)) => Ok(n.clone()),
+        Value::Ratio(n) => Ok(number::big(n.to_integer())),
+        Value::Num(n) => match BigInt::from_f64(n.trunc()) {
+            Some(n) => Ok(number::big(n)),
+            None => raise!("Can't convert {} to an integer", n),
+        },
+        Value::Str(str) => match str.parse::<BigInt>() {
+            Ok(n) => Ok(number::big(n)),
+            Err(e) => raise!("{}", e),
+        },
+        other => raise!("Expected a number or a string, found {}", other),
     }
-    std::process::exit(code as i32);
 }
 
-fn raise(args: &[Value]) -> InterpretResult<Value> {
+fn float(args: &[Value]) -> InterpretResult<Value> {
     match &args[0] {
-        Value::Str(s) => raise!("{}", &**s),
-        other => raise!("{}", other),
+        Value::Int(n) => Ok(Value::Num(*n as f64)),
+        Value::BigInt(n) => Ok(Value::Num(n.to_f64().unwrap_or(f64::NAN))),
+        Value::Ratio(n) => Ok(Value::Num(n.to_f64().unwrap_or(f64::NAN))),
+        n @ Value::Num(..) => Ok(n.clone()),
+        Value::Str(str) => match str.parse::<f64>() {
+            Ok(n) => Ok(Value::Num(n)),
+            Err(e) => raise!("{:?}", e),
+        },
+        other => raise!("Expected a number or a string, found {}", other),
     }
 }
 
-fn format(args: &[Value]) -> InterpretResult<Value> {
-    let format: String = args[0].get()?;
-    let args: List = args[1].get()?;
-    let mut idx = 0;
+fn ratio(args: &[Value]) -> InterpretResult<Value> {
+    let to_big = |value: &Value| match value {
+        Value::Int(n) => Ok(BigInt::from(*n)),
+        Value::BigInt(n) => Ok((**n).clone()),
+        other => raise!("Expected an integer, found {}", other),
+    };
 
-    let res = format
-        .chars()
-        .map(|it| {
-            if it == '&' {
-                idx += 1;
-                format!("{}", args.index(idx))
-            } else {
-                it.to_string()
-            }
-        })
-        .collect::<String>();
+    let (numer, denom) = (to_big(&args[0])?, to_big(&args[1])?);
+    if denom.is_zero() {
+        raise!(E0206; "Division by zero applying `ratio`")?;
+    }
 
-    Ok(Value::Str(GcRef::new(res)))
+    Ok(number::ratio(BigRational::new(numer, denom)))
 }
 
-pub fn prelude() -> EnvTable {
+fn doc(args: &[Value]) -> InterpretResult<Value> {
+    Ok(match args[0].doc() {
+        Some(Doc::Owned(doc)) => Value::Str(doc),
+        Some(Doc::Static(doc)) => Value::Str(GcRef::new(doc.to_string())),
+        None => Value::Nil,
+    })
+}
+
+fn compare(args: &[Value]) -> InterpretResult<Value> {
+    Ok(Value::Int(args[0].compare(&args[1])? as i64))
+}
+
+fn exit(args: &[Value]) -> InterpretResult<Value> {
+    let code: i64 = args[0].get()?;
+    let code = match i32::try_from(code) {
+        Ok(code) => code,
+        Err(_) => raise!("Invalid exit code {}", code)?,
+    };
+    raise!(@ErrorKind::Exit(code); "exited with code {}", code)
+}
+
+fn raise(args: &[Value]) -> InterpretResult<Value> {
+    match &args[0] {
+        Value::Str(s) => raise!("{}", &**s),
+        other => raise!("{}", other),
+    }
+}
+
+/// Returns the builtins that belong to the given capability group
+pub fn prelude(capability: Capability) -> EnvTable {
     let mut prelude = EnvTable::with_capacity(64);
+    // builtin docs start with the signature of the function, followed by its description
     macro_rules! insert_fn {
-        ($name: expr, $fn: expr) => {
-            insert_fn!($name, $fn, 1)
-        };
-        ($name: expr, $fn: expr, $arity:expr) => {
+        ($name: expr, $fn: expr, $arity:expr, $doc: expr) => {
             prelude.insert(
                 $crate::Symbol::new($name),
-                Value::Fn(GcRef::new(crate::literal::fun::Fn {
-                    arity: $arity,
-                    body: GcRef::new(FnKind::Native(|_, it| $fn(&*it))),
-                    args: $crate::StackVec::new(),
-                })),
+                Value::Fn(GcRef::new(
+                    Fn::new_native($arity, |_, it| $fn(&*it)).with_doc($doc),
+                )),
             )
         };
 
-        (@vm $name: expr, $fn: expr, $arity:expr) => {
+        (@vm $name: expr, $fn: expr, $arity:expr, $doc: expr) => {
             prelude.insert(
                 $crate::Symbol::new($name),
-                Value::Fn(GcRef::new(crate::literal::Fn {
-                    arity: $arity,
-                    body: GcRef::new(FnKind::Native(|vm, it| {
-                        $fn(unsafe { vm.as_mut().unwrap() }, &*it)
-                    })),
-                    args: $crate::StackVec::new(),
-                })),
+                Value::Fn(GcRef::new(
+                    Fn::new_native($arity, |vm, it| $fn(unsafe { vm.as_mut().unwrap() }, &*it))
+                        .with_doc($doc),
+                )),
             )
         };
     }
@@ -143,25 +227,164 @@ pub fn prelude() -> EnvTable {
         };
     }
 
-    insert_fn!("println", println);
-    insert_fn!("print", print);
-    insert_fn!("input", input);
-    insert_fn!("str", str);
-    insert_fn!("typeof", r#typeof);
-    insert_fn!("inspect", inspect);
-    insert_fn!("num", num);
-    insert_fn!("exit", exit);
-    insert_fn!("raise", raise);
-    insert_fn!("format", format, 2);
-
-    insert!("Nil", Value::Type(GcRef::new(YexType::nil())));
-    insert!("Bool", Value::Type(GcRef::new(YexType::bool())));
-    insert!("Num", Value::Type(GcRef::new(YexType::num())));
-    insert!("Str", Value::Type(GcRef::new(YexType::str())));
-    insert!("List", Value::Type(GcRef::new(YexType::list())));
-    insert!("Sym", Value::Type(GcRef::new(YexType::sym())));
-    insert!("Fn", Value::Type(GcRef::new(YexType::fun())));
-    insert!("Table", Value::Type(GcRef::new(YexType::table())));
-    insert!("Mutable", Value::Type(GcRef::new(YexType::mutable())));
+    match capability {
+        Capability::Core => {
+            insert_fn!("str", str, 1, "str(value)\nConverts a value to a string");
+            insert_fn!(
+                "typeof",
+                r#typeof,
+                1,
+                "typeof(value)\nReturns the type of a value"
+            );
+            insert_fn!(
+                "tag",
+                tag,
+                1,
+                "tag(value)\nReturns the name of the variant a value was built with, as a symbol, \
+                 or `nil` for the values that aren't variants"
+            );
+            insert_fn!(
+                "variants",
+                variants,
+                1,
+                "variants(type)\nReturns the variants of a type"
+            );
+            insert_fn!(
+                "is_a?",
+                is_a,
+                2,
+                "is_a?(value, type)\nReturns true if a value has a type, one of its variants or a \
+                 type that extends it"
+            );
+            insert_fn!(
+                @vm "implements?",
+                implements,
+                2,
+                "implements?(value, trait)\nReturns true if the type of a value implements a trait"
+            );
+            insert_fn!(
+                "inspect",
+                inspect,
+                1,
+                "inspect(value)\nReturns the internal representation of a value"
+            );
+            insert_fn!(
+                "num",
+                num,
+                1,
+                "num(value)\nParses a string or a symbol into an integer, a ratio or a float"
+            );
+            insert_fn!(
+                "int",
+                int,
+                1,
+                "int(value)\nConverts a number or a string to an integer, truncating floats"
+            );
+            insert_fn!(
+                "float",
+                float,
+                1,
+                "float(value)\nConverts a number or a string to a float"
+            );
+            insert_fn!(
+                "ratio",
+                ratio,
+                2,
+                "ratio(numer, denom)\nCreates the exact fraction `numer / denom`"
+            );
+            insert_fn!(
+                "raise",
+                raise,
+                1,
+                "raise(message)\nRaises an error with the given message"
+            );
+            insert_fn!(
+                "format",
+                format::format,
+                2,
+                "format(string, args)\nReplaces the `{}` placeholders of the string with \
+                 the arguments, taken from a list or, for named placeholders, a table"
+            );
+            insert_fn!(
+                "compare",
+                compare,
+                2,
+                "compare(a, b)\nReturns `-1`, `0` or `1` when `a` is smaller, equal or greater \
+                 than `b`"
+            );
+            insert_fn!(
+                "doc",
+                doc,
+                1,
+                "doc(value)\nReturns the documentation of a function or a type, or `nil`"
+            );
+            insert!(
+                "range",
+                Value::Fn(GcRef::new(
+                    Fn::new_native(2, list::methods::range).with_doc(
+                        "range(start, end)\nReturns the integers from `start` up to, but not \
+                         including, `end`"
+                    )
+                ))
+            );
+
+            insert!("Nil", Value::Type(builtin(|it| &it.nil)));
+            insert!("Bool", Value::Type(builtin(|it| &it.bool)));
+            insert!("Num", Value::Type(builtin(|it| &it.num)));
+            insert!("Int", Value::Type(builtin(|it| &it.int)));
+            insert!("Float", Value::Type(builtin(|it| &it.float)));
+            insert!("BigInt", Value::Type(builtin(|it| &it.bigint)));
+            insert!("Ratio", Value::Type(builtin(|it| &it.ratio)));
+            insert!("Str", Value::Type(builtin(|it| &it.str)));
+            insert!("List", Value::Type(builtin(|it| &it.list)));
+            insert!("Sym", Value::Type(builtin(|it| &it.sym)));
+            insert!("Fn", Value::Type(builtin(|it| &it.fun)));
+            insert!("Table", Value::Type(builtin(|it| &it.table)));
+            insert!("Mutable", Value::Type(builtin(|it| &it.mutable)));
+            insert!("Trait", Value::Type(builtin(|it| &it.r#trait)));
+
+            for ty in [YexType::option(), YexType::result()] {
+                for variant in &ty.variants {
+                    // the variants without fields are values, not constructors
+                    let value = if variant.params.is_empty() {
+                        variant::construct(variant.clone(), vec![])
+                    } else {
+                        Value::Type(variant.clone())
+                    };
+                    prelude.insert(variant.name, value);
+                }
+                prelude.insert(ty.name, Value::Type(GcRef::new(ty)));
+            }
+        }
+        Capability::Io => {
+            insert_fn!(
+                @vm "println",
+                println,
+                1,
+                "println(value)\nPrints a value followed by a new line"
+            );
+            insert_fn!(
+                @vm "print",
+                print,
+                1,
+                "print(value)\nPrints a value without adding a new line"
+            );
+            insert_fn!(
+                @vm "input",
+                input,
+                1,
+                "input(prompt)\nPrints the prompt and reads a line from the input"
+            );
+        }
+        Capability::Process => {
+            insert_fn!(
+                "exit",
+                exit,
+                1,
+                "exit(code)\nStops the program with the given exit code"
+            );
+        }
+        Capability::Fs | Capability::Time => {}
+    }
     prelude
 }
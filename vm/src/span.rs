@@ -0,0 +1,66 @@
+use crate::Symbol;
+
+/// The file given to spans whose source has no name
+const UNNAMED: Symbol = Symbol::from_static("<input>");
+
+/// A range of bytes in a source file, used to point at the exact code that an expression, a
+/// statement or an opcode comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The path of the file, or `<input>` if the source has no name
+    pub file: Symbol,
+    /// The byte offset where the range starts
+    pub start: usize,
+    /// The byte offset right after the end of the range
+    pub end: usize,
+}
+
+impl Span {
+    /// Creates a new [`Span`]
+    pub const fn new(file: Symbol, start: usize, end: usize) -> Self {
+        Self { file, start, end }
+    }
+
+    /// The file given to sources without a name
+    pub const fn unnamed() -> Symbol {
+        UNNAMED
+    }
+
+    /// Returns the length of the range in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Checks if the range is empty
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the smallest span that covers both spans, which must belong to the same file
+    #[must_use]
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Returns the source text that the span covers, or `None` if it's out of the bounds of
+    /// `src`
+    pub fn text<'a>(&self, src: &'a str) -> Option<&'a str> {
+        src.get(self.start..self.end)
+    }
+}
+
+impl Default for Span {
+    fn default() -> Self {
+        Self::new(UNNAMED, 0, 0)
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}..{}", self.file, self.start, self.end)
+    }
+}
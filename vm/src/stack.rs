@@ -53,7 +53,7 @@ impl<T, const S: usize> StackVec<T, S> {
 
     #[track_caller]
     /// Returns an iterator of mutable references to the elements
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + DoubleEndedIterator {
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T> {
         self.array[0..self.len]
             .iter_mut()
             .map(|it| unsafe { it.assume_init_mut() })
@@ -61,7 +61,7 @@ impl<T, const S: usize> StackVec<T, S> {
 
     #[track_caller]
     /// Returns an iterator of references to the elements
-    pub fn iter(&self) -> impl Iterator<Item = &T> + DoubleEndedIterator {
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
         self.array[0..self.len]
             .iter()
             .map(|it| unsafe { it.assume_init_ref() })
@@ -160,7 +160,11 @@ impl<T, const S: usize> Iterator for IntoIter<T, S> {
     fn next(&mut self) -> Option<Self::Item> {
         if self.next < self.array.len() {
             let item = unsafe {
-                (self.array.array.get_unchecked_mut(self.next).as_ptr() as *const T).read()
+                self.array
+                    .array
+                    .get_unchecked_mut(self.next)
+                    .as_ptr()
+                    .read()
             };
             self.next += 1;
             Some(item)
@@ -0,0 +1,52 @@
+//! The `yex check` command, which reports the syntax errors, the undefined names, the unused
+//! or shadowed locals and the type errors of source files without running them
+use std::{fs, path::PathBuf};
+
+use vm::Symbol;
+
+use crate::diagnostic::{render_all, Sources};
+
+const USAGE: &str = "usage: yex check <files or directories...>";
+
+/// Runs `yex check` with the arguments that follow `check`, returning the process exit code,
+/// which is only non-zero if an error was found, warnings don't fail the check
+pub fn run(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        return 1;
+    }
+
+    let mut files = Vec::new();
+    for root in args.iter().map(PathBuf::from) {
+        if let Err(e) = crate::sources(&root, &mut files) {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+
+    let mut code = 0;
+    let mut sources = Sources::new();
+    let mut diagnostics = Vec::new();
+
+    for file in files {
+        let src = match fs::read_to_string(&file) {
+            Ok(src) => src,
+            Err(e) => {
+                eprintln!("error reading {}: {}", file.display(), e);
+                code = 1;
+                continue;
+            }
+        };
+
+        let path = file.display().to_string();
+        diagnostics.extend(front::check(&path, src.as_str()));
+        sources.insert(Symbol::new(path), src);
+    }
+
+    if diagnostics.iter().any(|it| it.is_error()) {
+        code = 1;
+    }
+
+    eprint!("{}", render_all(&diagnostics, &sources));
+    code
+}
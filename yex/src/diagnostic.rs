@@ -0,0 +1,221 @@
+//! Renders errors like rustc does, with the lines of source code they point to
+use std::collections::HashMap;
+
+use front::plural;
+use vm::{Diagnostic, Span, Symbol};
+
+/// The sources that diagnostics can point to, by file name
+pub type Sources = HashMap<Symbol, String>;
+
+/// A range of a single source line that gets underlined
+struct Mark<'a> {
+    line: usize,
+    /// The display column where the underline starts, zero-based
+    start: usize,
+    /// The width of the underline, at least one
+    width: usize,
+    primary: bool,
+    message: &'a str,
+}
+
+/// Returns the display width of a piece of a source line
+fn width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+/// Finds the line, the start column and the width of a span in its source, `None` if the span
+/// doesn't fit in it. Spans across multiple lines are underlined until the end of their first
+/// line
+fn locate(src: &str, span: Span) -> Option<(usize, usize, usize)> {
+    if span.start > span.end || !src.is_char_boundary(span.start) || span.end > src.len() {
+        return None;
+    }
+
+    let line_start = src[..span.start].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = src[span.start..]
+        .find('\n')
+        .map_or(src.len(), |idx| span.start + idx);
+    let line = src[..span.start].matches('\n').count() + 1;
+
+    let start = width(&src[line_start..span.start]);
+    let end = span.end.min(line_end);
+    let len = src.get(span.start..end).map_or(0, width);
+    Some((line, start, len.max(1)))
+}
+
+/// Returns the text of a line, without its line break, and with its tabs expanded
+fn line_text(src: &str, line: usize) -> String {
+    let text = src.split('\n').nth(line - 1).unwrap_or_default();
+    text.trim_end_matches('\r').replace('\t', "    ")
+}
+
+/// Renders a diagnostic, showing the lines of code it points to when its file is in `sources`
+pub fn render(diagnostic: &Diagnostic, sources: &Sources) -> String {
+    let out = snippet(diagnostic, sources);
+    hint(out, &[diagnostic.code])
+}
+
+/// Renders a list of diagnostics, followed by a summary of them and a single hint about
+/// `yex explain` for all of their codes
+pub fn render_all<'a>(
+    diagnostics: impl IntoIterator<Item = &'a Diagnostic>,
+    sources: &Sources,
+) -> String {
+    let diagnostics = diagnostics.into_iter().collect::<Vec<_>>();
+    match diagnostics[..] {
+        [] => return String::new(),
+        [diagnostic] if diagnostic.is_error() => return render(diagnostic, sources),
+        _ => {}
+    }
+
+    let mut out = diagnostics
+        .iter()
+        .map(|it| snippet(it, sources))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let errors = diagnostics.iter().filter(|it| it.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    let summary = match (errors, warnings) {
+        (0, _) => format!("warning: {} emitted", plural(warnings, "warning")),
+        (_, 0) => format!(
+            "error: aborting due to {}",
+            plural(errors, "previous error")
+        ),
+        _ => format!(
+            "error: aborting due to {}; {} emitted",
+            plural(errors, "previous error"),
+            plural(warnings, "warning")
+        ),
+    };
+    out.push_str(&format!("\n{}\n", summary));
+
+    let mut codes = diagnostics.iter().map(|it| it.code).collect::<Vec<_>>();
+    codes.sort_unstable();
+    codes.dedup();
+    hint(out, &codes)
+}
+
+/// Renders the header and the source lines of a diagnostic, with its notes
+fn snippet(diagnostic: &Diagnostic, sources: &Sources) -> String {
+    let mut out = format!(
+        "{}[{}]: {}\n",
+        diagnostic.severity, diagnostic.code, diagnostic.message
+    );
+
+    let file = diagnostic.span.file;
+    let src = sources.get(&file).map(String::as_str);
+
+    let mut marks = Vec::new();
+    if let Some(src) = src {
+        if let Some((line, start, width)) = locate(src, diagnostic.span) {
+            marks.push(Mark {
+                line,
+                start,
+                width,
+                primary: true,
+                message: "",
+            });
+        }
+
+        for label in diagnostic.labels.iter().filter(|it| it.span.file == file) {
+            if let Some((line, start, width)) = locate(src, label.span) {
+                marks.push(Mark {
+                    line,
+                    start,
+                    width,
+                    primary: false,
+                    message: &label.message,
+                });
+            }
+        }
+    }
+
+    let (src, primary) = match (src, marks.first()) {
+        (Some(src), Some(primary)) if primary.primary => (src, primary),
+        _ => {
+            out.push_str(&format!(
+                " --> {}:{}:{}\n",
+                file, diagnostic.line, diagnostic.column
+            ));
+            return notes(out, diagnostic, 0);
+        }
+    };
+
+    let gutter = marks
+        .iter()
+        .map(|it| it.line)
+        .max()
+        .unwrap_or(1)
+        .to_string()
+        .len();
+    out.push_str(&format!(
+        "{:w$}--> {}:{}:{}\n",
+        "",
+        file,
+        primary.line,
+        primary.start + 1,
+        w = gutter
+    ));
+    out.push_str(&format!("{:w$} |\n", "", w = gutter));
+
+    marks.sort_by_key(|it| (it.line, it.start));
+
+    let mut last = None;
+    for mark in &marks {
+        if last != Some(mark.line) {
+            if last.is_some_and(|last| mark.line > last + 1) {
+                out.push_str("...\n");
+            }
+            let text = format!(
+                "{:>w$} | {}",
+                mark.line,
+                line_text(src, mark.line),
+                w = gutter
+            );
+            out.push_str(text.trim_end());
+            out.push('\n');
+            last = Some(mark.line);
+        }
+
+        let underline = if mark.primary { "^" } else { "-" }.repeat(mark.width);
+        let text = format!("{}{} {}", " ".repeat(mark.start), underline, mark.message);
+        out.push_str(&format!("{:w$} | {}\n", "", text.trim_end(), w = gutter));
+    }
+
+    notes(out, diagnostic, gutter)
+}
+
+/// Appends the help notes of a diagnostic
+fn notes(mut out: String, diagnostic: &Diagnostic, gutter: usize) -> String {
+    if !diagnostic.help.is_empty() {
+        out.push_str(&format!("{:w$} |\n", "", w = gutter));
+    }
+
+    for help in &diagnostic.help {
+        out.push_str(&format!("{:w$} = help: {}\n", "", help, w = gutter));
+    }
+    out
+}
+
+/// Appends the hint about `yex explain` for the codes that have an explanation
+fn hint(mut out: String, codes: &[&str]) -> String {
+    let codes = codes
+        .iter()
+        .filter(|it| crate::explain::explanation(it).is_some())
+        .collect::<Vec<_>>();
+
+    match codes[..] {
+        [] => {}
+        [code] => out.push_str(&format!(
+            "\nfor more information about this error, try `yex explain {}`\n",
+            code
+        )),
+        [first, ..] => out.push_str(&format!(
+            "\nsome errors have detailed explanations: {}\nfor more information about an error, try `yex explain {}`\n",
+            codes.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(", "),
+            first
+        )),
+    }
+    out
+}
@@ -0,0 +1,636 @@
+//! The `yex doc` command, which generates Markdown or HTML documentation from the `///` doc
+//! comments of a project and from the builtin prelude
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use front::outline::{outline, DefItem, Item};
+use vm::{Capability, Diagnostic, Symbol, Value};
+
+use crate::diagnostic::{render_all, Sources};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+/// A documented function, method or type
+struct Entry {
+    /// The name used to link to the entry, like `map` or `List.map`
+    name: String,
+    signature: String,
+    doc: Option<String>,
+    /// Where the entry is defined, as `path:line`
+    source: Option<String>,
+    members: Vec<Entry>,
+}
+
+struct Section {
+    title: Option<String>,
+    entries: Vec<Entry>,
+}
+
+struct Page {
+    /// The path of the page relative to the output directory, without the extension
+    name: String,
+    title: String,
+    sections: Vec<Section>,
+}
+
+impl Page {
+    fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.sections.iter().flat_map(|it| &it.entries)
+    }
+
+    /// Returns the link to a path relative to the output directory from this page, which is
+    /// at most one directory deep
+    fn relative(&self, path: &str) -> String {
+        let dir = match self.name.rsplit_once('/') {
+            Some((dir, _)) => dir,
+            None => return path.to_string(),
+        };
+
+        match path.strip_prefix(dir).and_then(|it| it.strip_prefix('/')) {
+            Some(sibling) => sibling.to_string(),
+            None => format!("../{}", path),
+        }
+    }
+}
+
+/// The directory of the pages of the source files, so they never replace the index or the
+/// prelude page
+const SOURCES_DIR: &str = "src";
+
+fn anchor(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '.' => String::from("-"),
+            '?' => String::from("_q"),
+            c if c.is_alphanumeric() || c == '_' => c.to_string(),
+            _ => String::from("-"),
+        })
+        .collect()
+}
+
+fn escape_html(str: &str) -> String {
+    str.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Splits a builtin doc into its signature line and its description
+fn split_builtin_doc(name: &str, doc: Option<String>) -> (String, Option<String>) {
+    match doc {
+        Some(doc) => match doc.split_once('\n') {
+            Some((signature, doc)) => (signature.to_string(), Some(doc.to_string())),
+            None => (name.to_string(), Some(doc)),
+        },
+        None => (name.to_string(), None),
+    }
+}
+
+fn builtin_entry(name: &str, value: &Value) -> Entry {
+    let (mut signature, doc) = split_builtin_doc(name, value.doc().map(|it| it.to_string()));
+
+    if let Value::Type(ty) = value {
+        if !ty.variants.is_empty() {
+            let variants = ty
+                .variants
+                .iter()
+                .map(|it| {
+                    let params = it
+                        .params
+                        .iter()
+                        .map(|it| it.to_string())
+                        .collect::<Vec<_>>();
+                    variant_signature(it.name.as_str(), &params)
+                })
+                .collect::<Vec<_>>();
+            signature = format!("type {} = {}", name, variants.join(" | "));
+        }
+    }
+
+    let members = match value {
+        Value::Type(ty) => {
+            let mut members = ty
+                .fields
+                .iter()
+                .map(|(method, value)| {
+                    let (signature, doc) =
+                        split_builtin_doc(method.as_str(), value.doc().map(|it| it.to_string()));
+                    Entry {
+                        name: format!("{}.{}", name, method),
+                        signature,
+                        doc,
+                        source: None,
+                        members: vec![],
+                    }
+                })
+                .collect::<Vec<_>>();
+            members.sort_by(|a, b| a.name.cmp(&b.name));
+            members
+        }
+        _ => vec![],
+    };
+
+    Entry {
+        name: name.to_string(),
+        signature,
+        doc,
+        source: None,
+        members,
+    }
+}
+
+fn prelude_page() -> Page {
+    let mut sections = Vec::new();
+    let mut types = Vec::new();
+
+    for capability in Capability::ALL {
+        let mut entries = Vec::new();
+
+        for (name, value) in capability.builtins().iter() {
+            // the variants are documented with the type they belong to
+            let variant = match &value {
+                Value::Type(ty) => ty.variant_of.is_some(),
+                other => other.tag().is_some(),
+            };
+            if variant {
+                continue;
+            }
+
+            let entry = builtin_entry(name.as_str(), &value);
+            match value {
+                Value::Type(_) => types.push(entry),
+                _ => entries.push(entry),
+            }
+        }
+
+        // the groups without builtins yet aren't documented
+        if entries.is_empty() {
+            continue;
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        sections.push(Section {
+            title: Some(format!("Functions (`{}` capability)", capability)),
+            entries,
+        });
+    }
+
+    types.sort_by(|a, b| a.name.cmp(&b.name));
+    sections.push(Section {
+        title: Some(String::from("Types")),
+        entries: types,
+    });
+
+    Page {
+        name: String::from("prelude"),
+        title: String::from("Prelude"),
+        sections,
+    }
+}
+
+fn def_signature(def: &DefItem) -> String {
+    match (&def.params, &def.ret) {
+        (Some(params), Some(ret)) => format!("def {}({}) -> {}", def.name, params.join(", "), ret),
+        (Some(params), None) => format!("def {}({})", def.name, params.join(", ")),
+        (None, _) => format!("def {}", def.name),
+    }
+}
+
+/// Returns the signature of a variant, its fields are omitted if it doesn't have any
+fn variant_signature(name: &str, params: &[String]) -> String {
+    if params.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}({})", name, params.join(", "))
+    }
+}
+
+fn def_entry(name: String, def: &DefItem, path: &str) -> Entry {
+    Entry {
+        name,
+        signature: def_signature(def),
+        doc: def.doc.clone(),
+        source: Some(format!("{}:{}", path, def.line)),
+        members: vec![],
+    }
+}
+
+fn file_page(name: String, path: &str, items: Vec<Item>) -> Page {
+    let entries = items
+        .iter()
+        .map(|item| match item {
+            Item::Def(def) => def_entry(def.name.clone(), def, path),
+            Item::Trait(tr) => Entry {
+                name: tr.name.clone(),
+                signature: format!("trait {}", tr.name),
+                doc: tr.doc.clone(),
+                source: Some(format!("{}:{}", path, tr.line)),
+                members: tr
+                    .methods
+                    .iter()
+                    .map(|def| def_entry(format!("{}.{}", tr.name, def.name), def, path))
+                    .collect(),
+            },
+            Item::Type(ty) => Entry {
+                name: ty.name.clone(),
+                signature: if let Some(parent) = &ty.parent {
+                    format!(
+                        "type {}({}) extends {}",
+                        ty.name,
+                        ty.params.join(", "),
+                        parent
+                    )
+                } else if ty.variants.is_empty() {
+                    format!("type {}({})", ty.name, ty.params.join(", "))
+                } else {
+                    let variants = ty
+                        .variants
+                        .iter()
+                        .map(|it| variant_signature(&it.name, &it.params))
+                        .collect::<Vec<_>>();
+                    format!("type {} = {}", ty.name, variants.join(" | "))
+                },
+                doc: ty.doc.clone(),
+                source: Some(format!("{}:{}", path, ty.line)),
+                members: ty
+                    .init
+                    .iter()
+                    .chain(&ty.methods)
+                    .map(|def| def_entry(format!("{}.{}", ty.name, def.name), def, path))
+                    .collect(),
+            },
+        })
+        .collect();
+
+    Page {
+        name,
+        title: path.to_string(),
+        sections: vec![Section {
+            title: None,
+            entries,
+        }],
+    }
+}
+
+/// Returns the page name of a source file, its path relative to `root` joined by dots, in the
+/// directory of the sources
+fn page_name(root: &Path, file: &Path) -> String {
+    let relative = match file.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative,
+        _ => Path::new(file.file_name().unwrap_or_default()),
+    };
+
+    let name = relative
+        .with_extension("")
+        .components()
+        .map(|it| it.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(".");
+    format!("{}/{}", SOURCES_DIR, name)
+}
+
+struct Renderer {
+    format: Format,
+    /// Maps every documented name to its page and anchor
+    links: HashMap<String, (String, String)>,
+}
+
+impl Renderer {
+    fn new(format: Format, pages: &[Page]) -> Self {
+        let mut links = HashMap::new();
+        for page in pages {
+            for entry in page.entries() {
+                for entry in std::iter::once(entry).chain(&entry.members) {
+                    links
+                        .entry(entry.name.clone())
+                        .or_insert_with(|| (page.name.clone(), anchor(&entry.name)));
+                }
+            }
+        }
+
+        Self { format, links }
+    }
+
+    fn href(&self, page: &Page, name: &str) -> Option<String> {
+        // definitions of the current page shadow the ones from other pages
+        if let Some(entry) = page
+            .entries()
+            .flat_map(|it| std::iter::once(it).chain(&it.members))
+            .find(|it| it.name == name)
+        {
+            return Some(format!("#{}", anchor(&entry.name)));
+        }
+
+        let (target, anchor) = self.links.get(name)?;
+        let target = format!("{}.{}", target, self.format.extension());
+        Some(format!("{}#{}", page.relative(&target), anchor))
+    }
+
+    /// Renders a code span, linking it if it names a documented item, like `map`, `List.map`
+    /// or `map(f)`
+    fn code(&self, page: &Page, code: &str) -> String {
+        let name = code.split('(').next().unwrap_or(code);
+        let href = self.href(page, code).or_else(|| self.href(page, name));
+
+        match (self.format, href) {
+            (Format::Markdown, Some(href)) => format!("[`{}`]({})", code, href),
+            (Format::Markdown, None) => format!("`{}`", code),
+            (Format::Html, Some(href)) => {
+                format!(
+                    "<a href=\"{}\"><code>{}</code></a>",
+                    href,
+                    escape_html(code)
+                )
+            }
+            (Format::Html, None) => format!("<code>{}</code>", escape_html(code)),
+        }
+    }
+
+    /// Renders doc text, cross-linking the names written between backticks
+    fn text(&self, page: &Page, text: &str) -> String {
+        let plain = |part: &str| match self.format {
+            Format::Markdown => part.to_string(),
+            Format::Html => escape_html(part),
+        };
+
+        let parts = text.split('`').collect::<Vec<_>>();
+        // an unclosed backtick is kept as it is
+        let closed = parts.len() - (parts.len() + 1) % 2;
+
+        let mut res = String::new();
+        for (idx, part) in parts.iter().enumerate() {
+            if idx >= closed {
+                res.push('`');
+                res.push_str(&plain(part));
+            } else if idx % 2 == 1 {
+                res.push_str(&self.code(page, part));
+            } else {
+                res.push_str(&plain(part));
+            }
+        }
+        res
+    }
+
+    fn doc(&self, page: &Page, doc: &str) -> String {
+        match self.format {
+            Format::Markdown => format!("{}\n\n", self.text(page, doc)),
+            Format::Html => doc
+                .split("\n\n")
+                .map(|paragraph| format!("<p>{}</p>\n", self.text(page, paragraph)))
+                .collect(),
+        }
+    }
+
+    fn entry(&self, page: &Page, entry: &Entry, level: usize, out: &mut String) {
+        let id = anchor(&entry.name);
+
+        match self.format {
+            Format::Markdown => {
+                out.push_str(&format!("<a id=\"{}\"></a>\n\n", id));
+                out.push_str(&format!("{} `{}`\n\n", "#".repeat(level), entry.signature));
+            }
+            Format::Html => out.push_str(&format!(
+                "<h{0} id=\"{1}\"><code>{2}</code></h{0}>\n",
+                level,
+                id,
+                escape_html(&entry.signature)
+            )),
+        }
+
+        if let Some(doc) = &entry.doc {
+            out.push_str(&self.doc(page, doc));
+        }
+
+        if let Some(source) = &entry.source {
+            match self.format {
+                Format::Markdown => out.push_str(&format!("*Defined at `{}`*\n\n", source)),
+                Format::Html => out.push_str(&format!(
+                    "<p class=\"source\">Defined at <code>{}</code></p>\n",
+                    escape_html(source)
+                )),
+            }
+        }
+
+        for member in &entry.members {
+            self.entry(page, member, level + 1, out);
+        }
+    }
+
+    fn heading(&self, text: &str, level: usize) -> String {
+        match self.format {
+            Format::Markdown => format!("{} {}\n\n", "#".repeat(level), text),
+            Format::Html => format!("<h{0}>{1}</h{0}>\n", level, self.text_only(text)),
+        }
+    }
+
+    /// Renders text without cross-linking it
+    fn text_only(&self, text: &str) -> String {
+        text.split('`')
+            .enumerate()
+            .map(|(idx, part)| match idx % 2 {
+                0 => escape_html(part),
+                _ => format!("<code>{}</code>", escape_html(part)),
+            })
+            .collect()
+    }
+
+    fn wrap(&self, title: &str, body: String) -> String {
+        match self.format {
+            Format::Markdown => body,
+            Format::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+                 <style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                escape_html(title),
+                STYLE,
+                body
+            ),
+        }
+    }
+
+    fn page(&self, page: &Page) -> String {
+        let mut out = self.heading(&page.title, 1);
+        if self.format == Format::Html {
+            out.push_str(&format!(
+                "<p><a href=\"{}\">Index</a></p>\n",
+                page.relative("index.html")
+            ));
+        }
+
+        for section in &page.sections {
+            let level = match &section.title {
+                Some(title) => {
+                    out.push_str(&self.heading(title, 2));
+                    3
+                }
+                None => 2,
+            };
+
+            for entry in &section.entries {
+                self.entry(page, entry, level, &mut out);
+            }
+        }
+
+        self.wrap(&page.title, out)
+    }
+
+    fn index(&self, pages: &[Page]) -> String {
+        let mut out = self.heading("Documentation", 1);
+
+        for page in pages {
+            let href = format!("{}.{}", page.name, self.format.extension());
+            let names = page
+                .sections
+                .iter()
+                .flat_map(|it| &it.entries)
+                .map(|it| match self.format {
+                    Format::Markdown => {
+                        format!("[`{0}`]({1}#{2})", it.name, href, anchor(&it.name))
+                    }
+                    Format::Html => format!(
+                        "<a href=\"{}#{}\"><code>{}</code></a>",
+                        href,
+                        anchor(&it.name),
+                        escape_html(&it.name)
+                    ),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            match self.format {
+                Format::Markdown => {
+                    out.push_str(&format!("- [{}]({}): {}\n", page.title, href, names))
+                }
+                Format::Html => out.push_str(&format!(
+                    "<p><a href=\"{}\">{}</a>: {}</p>\n",
+                    href,
+                    escape_html(&page.title),
+                    names
+                )),
+            }
+        }
+
+        self.wrap("Documentation", out)
+    }
+}
+
+const STYLE: &str =
+    "body { font-family: sans-serif; max-width: 50em; margin: auto; padding: 1em; } \
+                     code { background: #f4f4f4; padding: 0 0.2em; } \
+                     .source { color: #777; font-size: 0.9em; }";
+
+const USAGE: &str = "usage: yex doc <files or directories...> [-o <output directory>] [--html]";
+
+/// Runs `yex doc` with the arguments that follow `doc`, returning the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let mut out = PathBuf::from("docs");
+    let mut format = Format::Markdown;
+    let mut roots = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--out" => match args.next() {
+                Some(dir) => out = PathBuf::from(dir),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return 1;
+                }
+            },
+            "--html" => format = Format::Html,
+            "--markdown" => format = Format::Markdown,
+            _ => roots.push(PathBuf::from(arg)),
+        }
+    }
+
+    if roots.is_empty() {
+        eprintln!("{}", USAGE);
+        return 1;
+    }
+
+    let mut pages = vec![prelude_page()];
+    // the source file of every page, two roots can have files with the same relative path
+    let mut documented = HashMap::new();
+
+    for root in &roots {
+        let mut files = Vec::new();
+        if let Err(e) = crate::sources(root, &mut files) {
+            eprintln!("{}", e);
+            return 1;
+        }
+
+        for file in files {
+            let path = file.display().to_string();
+            let src = match fs::read_to_string(&file) {
+                Ok(src) => src,
+                Err(e) => {
+                    eprintln!("error reading {}: {}", path, e);
+                    return 1;
+                }
+            };
+
+            let items = match outline(src.as_str()) {
+                Ok(items) => items,
+                Err(errors) => {
+                    let file = Symbol::new(&path);
+                    let errors = errors.into_iter().map(|it| it.with_file(file));
+                    let diagnostics = errors.map(Diagnostic::from).collect::<Vec<_>>();
+                    let sources = Sources::from([(file, src)]);
+                    eprint!("{}", render_all(&diagnostics, &sources));
+                    return 1;
+                }
+            };
+
+            let name = page_name(root, &file);
+            if let Some(other) = documented.insert(name.clone(), path.clone()) {
+                eprintln!(
+                    "error: {} and {} would both be documented in {}.{}",
+                    other,
+                    path,
+                    name,
+                    format.extension()
+                );
+                return 1;
+            }
+            pages.push(file_page(name, &path, items));
+        }
+    }
+
+    let renderer = Renderer::new(format, &pages);
+    let ext = format.extension();
+
+    let mut files = vec![(format!("index.{}", ext), renderer.index(&pages))];
+    for page in &pages {
+        files.push((format!("{}.{}", page.name, ext), renderer.page(page)));
+    }
+
+    for (name, content) in files {
+        let path = out.join(name);
+        let dir = path.parent().unwrap_or(&out);
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("error creating {}: {}", dir.display(), e);
+            return 1;
+        }
+        if let Err(e) = fs::write(&path, content) {
+            eprintln!("error writing {}: {}", path.display(), e);
+            return 1;
+        }
+    }
+
+    0
+}
@@ -0,0 +1,526 @@
+//! The `yex explain` command, which describes the errors by their code
+
+/// The explanation of every error code, the first line is a short summary
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        "An unknown character was found.
+
+The lexer found a character that can't start any token, like `@` or `$` outside of a
+string:
+
+    def price = $10
+
+Remove the character, or put it inside a string.",
+    ),
+    (
+        "E0002",
+        "A delimiter was opened but never closed.
+
+A string, a block comment, a string interpolation, a `type` or a pair of parentheses or
+brackets reached the end of the file without its closing delimiter:
+
+    def greeting = \"hello
+    println([1, 2, 3
+
+Parentheses and brackets are also unclosed when a `def`, a `type` or an `end` is found
+inside of them. The error points to where the delimiter should be closed, and a label points
+to the delimiter that is still open. Add the missing `\"`, `*/`, `}`, `end`, `)` or `]`.",
+    ),
+    (
+        "E0003",
+        "A string contains an invalid escape sequence.
+
+The valid escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\$`, and the Unicode escapes
+`\\xNN`, `\\uNNNN` and `\\UNNNNNNNN`, which take exactly 2, 4 and 8 hex digits:
+
+    println(\"\\q\")       // unknown escape
+    println(\"\\u41\")     // `\\u` needs 4 digits, write `\\u0041`
+
+To write a backslash, escape it as `\\\\`, or use a raw string like `r\"\\q\"`.",
+    ),
+    (
+        "E0004",
+        "A number literal is malformed.
+
+Numbers with a radix prefix need at least one digit after it:
+
+    def mask = 0x
+    def bits = 0b_
+
+Write the digits after `0x`, `0o` or `0b`, or remove the prefix.",
+    ),
+    (
+        "E0005",
+        "A string interpolation is empty.
+
+A `${` inside a string starts an embedded expression, which can't be empty:
+
+    println(\"cost: ${}\")
+
+Write the expression between the braces, or escape the dollar sign as `\\$` to write a
+literal `${`.",
+    ),
+    (
+        "E0100",
+        "An unexpected token was found.
+
+The parser found a token that can't appear where it is, like a missing `then` in an `if` or
+a missing comma between arguments:
+
+    if x > 1 x else 0
+    println(1 2)
+
+Check the syntax around the token that the error points to.",
+    ),
+    (
+        "E0101",
+        "A name was expected.
+
+Definitions, parameters, fields and methods need a name, but another token was found:
+
+    def 1 = 2
+    let (x) = 1 in x
+
+Names start with a letter or `_`, followed by letters, digits and `_`, and can end in `?`,
+`!` or `'`.",
+    ),
+    (
+        "E0102",
+        "A type contains an invalid method.
+
+Everything defined inside a `type` must be a method, and methods receive the instance as
+their first parameter, called `this`:
+
+    type Point(x, y)
+      def norm() = this.x * this.x + this.y * this.y
+    end
+
+Add `this` as the first parameter: `def norm(this) = ...`. The `init` method is the only
+one that doesn't need it.",
+    ),
+    (
+        "E0103",
+        "`super` was used outside of the methods of a type that extends another.
+
+`super.method(args)` calls a method of the type written after `extends`, so it can only
+appear in the methods of a type declared with `extends`:
+
+    type User(name)
+      def greet(this) = super.greet()
+    end
+
+Call the method on `this` instead, or make the type extend the one that has the method.",
+    ),
+    (
+        "E0104",
+        "A type declares two variants with the same name.
+
+Every variant is a global of its own, so the second one would replace the first:
+
+    type Shape = Circle(r) | Circle(w) end
+
+Rename one of the variants, or merge their fields.",
+    ),
+    (
+        "E0200",
+        "An error was raised while running the program.
+
+This is the code of the errors raised by the builtins, like a string that can't be parsed
+as a number, and by the `raise` function:
+
+    num(\"one\")
+    raise(\"something went wrong\")
+
+The message of the error explains what happened.",
+    ),
+    (
+        "E0201",
+        "An undefined variable was used.
+
+The program read a variable that wasn't defined by a `def`, a `let`, a parameter or the
+prelude:
+
+    def area(r) = pi * r * r
+
+Check the spelling of the name, and make sure it's defined before the code that uses it
+runs. `yex check` finds the undefined names without running the program.",
+    ),
+    (
+        "E0202",
+        "A function was called with the wrong number of arguments.
+
+Every function, method and type expects a fixed number of arguments:
+
+    def add(a, b) = a + b
+    add(1, 2, 3)
+
+Pass as many arguments as the function has parameters, without counting `this` for
+methods.",
+    ),
+    (
+        "E0203",
+        "A value that isn't a function was called.
+
+Only functions can be called with parentheses:
+
+    def x = 10
+    x(1)
+
+Check that the name refers to a function, types are instantiated with `new`. Only the
+variants of a type, like `Circle` in `type Shape = Circle(r) | Empty end`, are called to
+build them.",
+    ),
+    (
+        "E0204",
+        "An undefined field or method was used.
+
+The value doesn't have the field or the method after the dot:
+
+    type Point(x, y) end
+    def origin = new Point(0, 0)
+    origin.z
+
+Check the spelling of the name, and the type of the value with `typeof`.",
+    ),
+    (
+        "E0205",
+        "An operator was applied to values of the wrong types.
+
+The arithmetic and bitwise operators only work on some combinations of values, usually
+numbers:
+
+    1 + nil
+    \"a\" * 2
+
+Convert the values first, like with `str`, `int` or `float`.",
+    ),
+    (
+        "E0206",
+        "A number was divided by zero.
+
+Integer and exact division, remainder and `ratio` can't have a zero divisor:
+
+    10 / 0
+    ratio(1, 0)
+
+Check the divisor before dividing. Floats divide by zero into infinity or NaN instead.",
+    ),
+    (
+        "E0207",
+        "A builtin from a capability that wasn't granted was used.
+
+Embedders can create a virtual machine with only some capabilities, like `core` and `io`,
+and the builtins of the other ones aren't available:
+
+    exit(1)
+
+Grant the capability when building the virtual machine, or avoid the builtin.",
+    ),
+    (
+        "E0208",
+        "The program ran out of instructions.
+
+Embedders can limit the number of instructions a program runs, and the program went over
+the limit. Make the program do less work, or raise the limit.",
+    ),
+    (
+        "E0209",
+        "The program was interrupted.
+
+The embedder stopped the execution of the program, usually because it took too long.",
+    ),
+    (
+        "E0210",
+        "A value doesn't have the type of its annotation.
+
+The program runs with `--check-types`, and a value passed to an annotated parameter or field,
+or returned from a function with an annotated return type, has another type:
+
+    def twice(n: Num) -> Num = n * 2
+    twice(\"2\")
+
+Pass a value of the annotated type, or change the annotation. `yex check` finds the values
+that can't have the right type without running the program.",
+    ),
+    (
+        "E0211",
+        "A type with variants was instantiated.
+
+A type declared with variants only has the values of its variants, so it can't be
+instantiated itself:
+
+    type Shape = Circle(r) | Rect(w, h) end
+    new Shape()
+
+Build one of its variants instead, like `Circle(1)`.",
+    ),
+    (
+        "E0212",
+        "An `impl` block doesn't match its trait.
+
+The methods of an `impl` block must be methods of the trait, with the same parameters, and
+every method of the trait without a default implementation must be defined:
+
+    trait Show =
+      def show(this)
+    end
+
+    impl Show for Int
+      def shw(this) = \"int\"
+    end
+
+Fix the name or the parameters of the method, or add the ones that are missing.",
+    ),
+    (
+        "E0213",
+        "A type can't extend the type written after `extends`.
+
+A type can only extend a type declared with `type` that doesn't have variants, and it must
+have the fields of that type among its own, since the inherited methods use them:
+
+    type User(name) end
+    type Admin(perms) extends User end
+
+Add the missing fields, like `type Admin(name, perms) extends User`.",
+    ),
+    (
+        "E0214",
+        "The program used more memory than it was allowed to.
+
+Embedders can limit the memory a program allocates, and the program went over the limit,
+or would have, like when building a huge list with `range`. Make the program use less
+memory, or raise the limit.",
+    ),
+    (
+        "E0215",
+        "The program nested too many calls.
+
+A function called itself, or called other functions, more times than the virtual machine
+can keep track of at once. Each call waiting for another one to return takes a frame, and
+only 1000 of them can be running by default. Every frame has room for a few values and locals,
+so calls that keep many of them around can overflow sooner:
+
+    def count(n) = if n == 0 then 0 else 1 + count(n - 1)
+    count(5000)
+
+Write the recursion as a tail call with `become`, which doesn't take a new frame, or use a
+loop over a list. Embedders can raise the limit with `VmBuilder::max_depth`.",
+    ),
+    (
+        "E0301",
+        "A name that isn't defined anywhere was used.
+
+`yex check` found a variable that isn't a local, a `def`, a `type` or a builtin:
+
+    def area(r) = pi * r * r
+
+Functions can't use the locals of the functions around them, so the parameters and the
+`let`s of a function aren't defined inside of the lambdas written in it:
+
+    def add_all(xs, n) = xs.map(fn(x) = x + n)
+
+Check the spelling of the name, the error suggests similar names that are defined. Running
+the program would fail with `E0201` once the name is used.",
+    ),
+    (
+        "W0302",
+        "A local variable is never used.
+
+`yex check` found a `let` binding or a parameter that the code never reads:
+
+    def area(w, h) = w * w
+
+Use the variable or remove it. Locals whose name starts with `_`, like `_h`, are never
+reported, and neither is the `this` parameter of the methods.",
+    ),
+    (
+        "W0303",
+        "A local variable shadows another one.
+
+`yex check` found a `let` binding or a parameter with the same name as another local of the
+same function, which can't be used anymore after it:
+
+    def total(xs) =
+      let sum = xs.fold(0, fn(a, b) = a + b)
+      in let sum = sum * 2
+      in sum
+
+Rename one of the locals. Shadowing is allowed, so the program still runs as written.",
+    ),
+    (
+        "E0304",
+        "A value is used with a type it can't have.
+
+`yex check` found a value whose type, known from a literal or an annotation, doesn't match
+how it's used:
+
+    def greet(name: Str) = \"hello, ${name}\"
+    greet(42)
+
+    1 + \"a\"
+
+Numbers are added to numbers and strings to strings, convert the values with `str` or `num`
+to mix them. Values that aren't annotated can have any type, so they are never reported.",
+    ),
+    (
+        "E0305",
+        "A value that can never be a function is called.
+
+`yex check` found a call to a value whose type is known and isn't `Fn`:
+
+    let count = 3 in count(1)
+
+Running the program would fail with `E0203` once the call is made.",
+    ),
+    (
+        "E0306",
+        "A function or a type receives too many arguments.
+
+`yex check` found a call with more arguments than the parameters of the function, or a `new`
+that doesn't pass one value for each parameter of the type:
+
+    def half(n) = n / 2
+    half(4, 2)
+
+    type Point(x, y) end
+    new Point(1)
+
+Calling a function with fewer arguments is fine, it returns a function that takes the rest.",
+    ),
+    (
+        "E0307",
+        "An annotation names a type that doesn't exist.
+
+Annotations use the builtin types, like `Int`, `Float`, `Str`, `List` or `Fn`, `Num` for any
+number, `Any` for any value, and the types declared with `type`:
+
+    def area(r: Nmber) = r * r
+
+Check the spelling of the type, the error suggests similar types that exist.",
+    ),
+    (
+        "E0308",
+        "An `impl` block doesn't match its trait.
+
+`yex check` found an `impl` block of a trait declared in the file that defines a method the
+trait doesn't have, a method with another number of parameters, or that leaves out a method
+without a default implementation:
+
+    trait Show =
+      def show(this)
+    end
+
+    impl Show for Int
+      def show(this, prefix) = prefix + \"int\"
+    end
+
+Running the program would fail with `E0212` once the block is reached.",
+    ),
+    (
+        "missed_tail_call",
+        "A recursive call could reuse the frame of the function.
+
+`yex lint` found a function that calls itself as the last thing it does:
+
+    def count(n, acc) = if n == 0 then acc else count(n - 1, acc + 1)
+
+Every call takes a new frame, so a deep recursion runs out of stack. Write the call with
+`become` to replace the frame of the function instead:
+
+    def count(n, acc) = if n == 0 then acc else become count(n - 1, acc + 1)",
+    ),
+    (
+        "nil_comparison",
+        "A value is compared with `nil` using `==` or `!=`.
+
+`yex lint` found a comparison like this one:
+
+    def missing(x) = x == nil
+
+Check the type of the value instead, which says what is being tested:
+
+    def missing(x) = typeof(x) == Nil",
+    ),
+    (
+        "unused_this",
+        "A method never uses `this`.
+
+`yex lint` found a method that doesn't need an instance of its type:
+
+    type Point(x, y)
+      def origin(this) = new Point(0, 0)
+    end
+
+Define it as a function with `def`, outside of the type.",
+    ),
+    (
+        "constant_condition",
+        "The condition of an `if` is always true or always false.
+
+`yex lint` found an `if` whose condition is a literal, so one of its branches never runs:
+
+    if 1 then \"always\" else \"never\"
+
+Remove the `if` and keep the branch that runs. Like in every condition, `false`, `nil`, `0`,
+`0.0`, `\"\"` and `[]` are false, and every other value is true.",
+    ),
+    (
+        "unused_value",
+        "A value on the left of `>>` is discarded.
+
+`yex lint` found a value that does nothing before being thrown away:
+
+    println(\"start\") >> 42 >> println(\"end\")
+
+`>>` evaluates its left side only for what it does, like printing, and keeps the value on its
+right. Remove the left side, or use it.",
+    ),
+    (
+        "arity_mismatch",
+        "A function defined with `def` is called with too many arguments.
+
+`yex lint` found a call that would fail with `E0202` when it runs:
+
+    def area(w, h) = w * h
+    def main() = area(2, 3, 4)
+
+Pass the arguments the function expects. Calls with fewer arguments are fine, they return a
+function that takes the rest. The rule is an error unless the `.yexlint` file of
+the project changes its level.",
+    ),
+];
+
+/// Returns the explanation of an error code
+pub fn explanation(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(it, _)| it.eq_ignore_ascii_case(code))
+        .map(|(_, text)| *text)
+}
+
+/// Runs `yex explain` with the arguments that follow `explain`, returning the process exit code
+pub fn run(args: &[String]) -> i32 {
+    match args {
+        [code] => match explanation(code) {
+            Some(text) => {
+                println!("{}", text);
+                0
+            }
+            None => {
+                eprintln!("error: `{}` is not a valid error code", code);
+                1
+            }
+        },
+        [] => {
+            for (code, text) in EXPLANATIONS {
+                println!("{}: {}", code, text.lines().next().unwrap_or_default());
+            }
+            0
+        }
+        _ => {
+            eprintln!("usage: yex explain [error code]");
+            1
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! The `yex fmt` command, which formats source files in place or, with `--check`, lists the
+//! ones that aren't formatted
+use std::{fs, path::PathBuf};
+
+use vm::{Diagnostic, Symbol};
+
+use crate::diagnostic::{render_all, Sources};
+
+const USAGE: &str = "usage: yex fmt [--check] <files or directories...>";
+
+/// Runs `yex fmt` with the arguments that follow `fmt`, returning the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let check = args.iter().any(|it| it == "--check");
+    let roots = args
+        .iter()
+        .filter(|it| *it != "--check")
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    if roots.is_empty() {
+        eprintln!("{}", USAGE);
+        return 1;
+    }
+
+    let mut files = Vec::new();
+    for root in &roots {
+        if let Err(e) = crate::sources(root, &mut files) {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+
+    let mut code = 0;
+    for file in files {
+        let src = match fs::read_to_string(&file) {
+            Ok(src) => src,
+            Err(e) => {
+                eprintln!("error reading {}: {}", file.display(), e);
+                code = 1;
+                continue;
+            }
+        };
+
+        let formatted = match front::format(&src) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                let file = Symbol::new(file.display().to_string());
+                let errors = errors.into_iter().map(|it| it.with_file(file));
+                let diagnostics = errors.map(Diagnostic::from).collect::<Vec<_>>();
+                let sources = Sources::from([(file, src)]);
+                eprint!("{}", render_all(&diagnostics, &sources));
+                code = 1;
+                continue;
+            }
+        };
+
+        if formatted == src {
+            continue;
+        }
+
+        if check {
+            println!("{} is not formatted", file.display());
+            code = 1;
+        } else if let Err(e) = fs::write(&file, formatted) {
+            eprintln!("error writing {}: {}", file.display(), e);
+            code = 1;
+        }
+    }
+
+    code
+}
@@ -0,0 +1,106 @@
+//! The `yex lint` command, which reports the code of source files that runs but is probably
+//! wrong or could be simpler. The levels of the rules are read from the `.yexlint` file of the
+//! project, found in the current directory or in one of its parents
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use front::lint::Config;
+use vm::{Diagnostic, Symbol};
+
+use crate::diagnostic::{render_all, Sources};
+
+const USAGE: &str = "usage: yex lint [--config <file>] <files or directories...>";
+
+/// The name of the project file with the levels of the rules
+const PROJECT_FILE: &str = ".yexlint";
+
+/// Runs `yex lint` with the arguments that follow `lint`, returning the process exit code,
+/// which is only non-zero if an error was found, warnings don't fail the lint
+pub fn run(args: &[String]) -> i32 {
+    let mut config = None;
+    let mut roots = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            match args.next() {
+                Some(path) => config = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return 1;
+                }
+            }
+        } else {
+            roots.push(PathBuf::from(arg));
+        }
+    }
+
+    if roots.is_empty() {
+        eprintln!("{}", USAGE);
+        return 1;
+    }
+
+    let config = match config.or_else(project_file) {
+        Some(path) => match read_config(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        },
+        None => Config::default(),
+    };
+
+    let mut files = Vec::new();
+    for root in &roots {
+        if let Err(e) = crate::sources(root, &mut files) {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+
+    let mut code = 0;
+    let mut sources = Sources::new();
+    let mut diagnostics = Vec::new();
+
+    for file in files {
+        let src = match fs::read_to_string(&file) {
+            Ok(src) => src,
+            Err(e) => {
+                eprintln!("error reading {}: {}", file.display(), e);
+                code = 1;
+                continue;
+            }
+        };
+
+        let path = file.display().to_string();
+        match front::lint::lint(&path, &src, &config) {
+            Ok(found) => diagnostics.extend(found),
+            Err(errors) => diagnostics.extend(errors.into_iter().map(Diagnostic::from)),
+        }
+        sources.insert(Symbol::new(path), src);
+    }
+
+    if diagnostics.iter().any(|it| it.is_error()) {
+        code = 1;
+    }
+
+    eprint!("{}", render_all(&diagnostics, &sources));
+    code
+}
+
+/// Finds the project file in the current directory or in the closest of its parents
+fn project_file() -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+    dir.ancestors()
+        .map(|it| it.join(PROJECT_FILE))
+        .find(|it| it.is_file())
+}
+
+fn read_config(path: &Path) -> Result<Config, String> {
+    let src =
+        fs::read_to_string(path).map_err(|e| format!("error reading {}: {}", path.display(), e))?;
+    Config::parse(&src).map_err(|e| format!("error in {}: {}", path.display(), e))
+}
@@ -1,44 +1,116 @@
+mod check;
+mod diagnostic;
+mod doc;
+mod explain;
+mod fmt;
+mod lint;
+
+use diagnostic::Sources;
+use front::ParseError;
 use rustyline::Editor;
-use std::{env::args, fs, process::exit};
-use vm::VirtualMachine;
+use std::{
+    env::args,
+    fs,
+    path::{Path, PathBuf},
+    process::exit,
+};
+use vm::{Symbol, VirtualMachine};
+
+/// Collects the `.yex` files under `path`, in a stable order
+fn sources(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let dir = fs::read_dir(path).map_err(|e| format!("error reading {}: {}", path.display(), e))?;
+    let mut paths = dir
+        .filter_map(|it| it.ok().map(|it| it.path()))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            sources(&path, files)?;
+        } else if path.extension().is_some_and(|it| it == "yex") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
 
-fn eval_file(file: &str) {
-    let file = match fs::read_to_string(file) {
+/// Runs a file, returning `Err` with the process exit code if the execution should stop
+fn eval_file(path: &str, check_types: bool) -> Result<(), i32> {
+    let file = match fs::read_to_string(path) {
         Ok(file) => file,
         Err(..) => {
-            eprintln!("error reading {}", file);
-            exit(1);
+            eprintln!("error reading {}", path);
+            return Err(1);
         }
     };
 
-    let (bt, ct) = match front::parse(file) {
+    let sources = Sources::from([(Symbol::new(path), file.clone())]);
+
+    let mut vm = VirtualMachine::default();
+    vm.set_type_checks(check_types);
+
+    let (bt, ct) = match front::parse_file_with_globals(path, file, vm.globals()) {
         Ok(res) => res,
-        Err(e) => {
-            eprintln!("{}", e);
-            exit(1);
+        Err(errors) => {
+            let diagnostics = errors.iter().map(ParseError::diagnostic);
+            eprint!("{}", diagnostic::render_all(diagnostics, &sources));
+            return Err(1);
         }
     };
 
-    let mut vm = VirtualMachine::default();
-
     vm.set_consts(ct);
-    if let Err(e) = vm.run(&bt) {
-        eprintln!("{}", e);
-        exit(1);
+    match vm.run(&bt) {
+        Ok(()) => Ok(()),
+        Err(e) => match e.exit_code() {
+            Some(code) => Err(code),
+            None => {
+                eprint!("{}", diagnostic::render(&e.diagnostic(), &sources));
+                Err(1)
+            }
+        },
     }
 }
 
 fn start(args: Vec<String>) -> i32 {
     let mut repl = Editor::<()>::new();
 
-    if args.len() > 1 {
-        for args in args.iter().skip(1) {
-            eval_file(args);
+    match args.get(1).map(String::as_str) {
+        Some("check") => return check::run(&args[2..]),
+        Some("doc") => return doc::run(&args[2..]),
+        Some("fmt") => return fmt::run(&args[2..]),
+        Some("lint") => return lint::run(&args[2..]),
+        Some("explain") => return explain::run(&args[2..]),
+        _ => {}
+    }
+
+    // `--check-types` makes the virtual machine check the type annotations while running
+    let check_types = args.iter().any(|it| it == "--check-types");
+    let files = args
+        .iter()
+        .skip(1)
+        .filter(|it| *it != "--check-types")
+        .collect::<Vec<_>>();
+
+    if !files.is_empty() {
+        for file in files {
+            if let Err(code) = eval_file(file, check_types) {
+                return code;
+            }
         }
         return 0;
     }
 
     let mut vm = VirtualMachine::default();
+    vm.set_type_checks(check_types);
+
+    // doc comments entered on their own lines are attached to the next definition
+    let mut docs = String::new();
+    let mut sources = Sources::new();
 
     loop {
         let line = match repl.readline("yex> ").map(|it| it.trim().to_string()) {
@@ -52,28 +124,52 @@ fn start(args: Vec<String>) -> i32 {
             continue;
         }
 
-        if line.starts_with("def") {
-            match front::parse(line) {
-                Ok((bt, ct)) => {
-                    vm.set_consts(ct);
-                    vm.run(&bt).unwrap_or_else(|e| println!("{}", e));
-                    println!("{}", vm.pop_last());
-                }
-                Err(err) => {
-                    eprintln!("{}", err);
-                }
+        if line.starts_with("///") {
+            docs.push_str(&line);
+            docs.push('\n');
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix(":doc ") {
+            match vm.get_global(name.trim()) {
+                Some(value) => match value.doc() {
+                    Some(doc) => println!("{}", doc),
+                    None => println!("No documentation for `{}`", name.trim()),
+                },
+                None => println!("Undefined name `{}`", name.trim()),
             }
+            continue;
+        }
+
+        // every entry is a file of its own, so errors in functions defined by previous entries
+        // show the right source
+        let name = format!("<repl:{}>", sources.len() + 1);
+        let (src, result) = if front::is_declaration(&line) {
+            let src = std::mem::take(&mut docs) + &line;
+            let result = front::parse_file_with_globals(&name, src.clone(), vm.globals());
+            (src, result)
         } else {
-            match front::parse_expr(line) {
-                Ok((bt, ct)) => {
-                    vm.set_consts(ct);
-                    vm.run(&bt).unwrap_or_else(|e| println!("{}", e));
-                    println!("{}", vm.pop_last());
-                }
-                Err(err) => {
-                    eprintln!("{}", err);
+            docs.clear();
+            let result = front::parse_expr_file_with_globals(&name, line.clone(), vm.globals());
+            (line, result)
+        };
+        sources.insert(Symbol::new(name), src);
+
+        match result {
+            Ok((bt, ct)) => {
+                vm.set_consts(ct);
+                match vm.run(&bt) {
+                    Ok(()) => println!("{}", vm.pop_last()),
+                    Err(e) => match e.exit_code() {
+                        Some(code) => return code,
+                        None => print!("{}", diagnostic::render(&e.diagnostic(), &sources)),
+                    },
                 }
             }
+            Err(errors) => {
+                let diagnostics = errors.iter().map(ParseError::diagnostic);
+                eprint!("{}", diagnostic::render_all(diagnostics, &sources));
+            }
         }
         vm.reset();
     }
@@ -0,0 +1,78 @@
+//! Helpers shared by the integration tests of the `yex` command, which run it in a temporary
+//! project directory
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The outcome of running `yex`
+pub struct Output {
+    /// The process exit code
+    pub code: i32,
+    /// Everything `yex` wrote to its stdout
+    pub stdout: String,
+    /// Everything `yex` wrote to its stderr
+    pub stderr: String,
+}
+
+/// A temporary directory with the files of a project, removed when dropped
+pub struct Project {
+    dir: PathBuf,
+}
+
+impl Project {
+    /// Creates an empty project, whose directory is unique to the test
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("yex-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    /// Returns the directory of the project
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes a file of the project, creating its directories
+    pub fn file(&self, path: &str, contents: &str) -> &Self {
+        let path = self.dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        self
+    }
+
+    /// Reads a file of the project
+    pub fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.dir.join(path)).unwrap()
+    }
+
+    /// Runs `yex` with some arguments in a directory of the project
+    pub fn yex_in(&self, dir: &str, args: &[&str]) -> Output {
+        let output = Command::new(env!("CARGO_BIN_EXE_yex"))
+            .args(args)
+            .current_dir(self.dir.join(dir))
+            .output()
+            .unwrap();
+
+        Output {
+            code: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+
+    /// Runs `yex` with some arguments in the directory of the project
+    pub fn yex(&self, args: &[&str]) -> Output {
+        self.yex_in("", args)
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::Project;
+
+#[test]
+fn source_pages_never_replace_the_generated_ones() {
+    let project = Project::new("doc-names");
+    project
+        .file(
+            "src/prelude.yex",
+            "/// Uses `List.map`\ndef twice(xs) = xs.map(fn(x) = x * 2)\n",
+        )
+        .file(
+            "src/index.yex",
+            "/// Calls `twice`\ndef four() = twice([2])\n",
+        );
+
+    let out = project.yex(&["doc", "src", "-o", "docs"]);
+    assert_eq!(out.code, 0, "{}", out.stderr);
+
+    assert!(project.read("docs/prelude.md").starts_with("# Prelude\n"));
+    assert!(project
+        .read("docs/index.md")
+        .starts_with("# Documentation\n"));
+
+    let page = project.read("docs/src/prelude.md");
+    assert!(page.contains("`def twice(xs)`"), "{}", page);
+    assert!(
+        page.contains("[`List.map`](../prelude.md#List-map)"),
+        "{}",
+        page
+    );
+    let page = project.read("docs/src/index.md");
+    assert!(page.contains("[`twice`](prelude.md#twice)"), "{}", page);
+
+    let index = project.read("docs/index.md");
+    assert!(index.contains("(src/prelude.md#twice)"), "{}", index);
+}
+
+#[test]
+fn files_documented_in_the_same_page_are_errors() {
+    let project = Project::new("doc-duplicates");
+    project
+        .file("a/util.yex", "def one() = 1\n")
+        .file("b/util.yex", "def two() = 2\n");
+
+    let out = project.yex(&["doc", "a", "b", "-o", "docs"]);
+    assert_eq!(out.code, 1);
+    assert!(
+        out.stderr
+            .contains("would both be documented in src/util.md"),
+        "{}",
+        out.stderr
+    );
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::Project;
+
+const NIL_COMPARISON: &str = "def missing(x) = x == nil\n";
+
+#[test]
+fn rules_warn_without_a_project_file() {
+    let project = Project::new("lint-default");
+    project.file("main.yex", NIL_COMPARISON);
+
+    let out = project.yex(&["lint", "main.yex"]);
+    assert_eq!(out.code, 0, "{}", out.stderr);
+    assert!(
+        out.stderr.contains("warning[nil_comparison]"),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn the_project_file_is_found_in_the_parent_directories() {
+    let project = Project::new("lint-project");
+    project
+        .file(
+            ".yexlint",
+            "# comments are ignored\nnil_comparison = deny\n",
+        )
+        .file("src/main.yex", NIL_COMPARISON);
+
+    let out = project.yex(&["lint", "src/main.yex"]);
+    assert_eq!(out.code, 1);
+    assert!(
+        out.stderr.contains("error[nil_comparison]"),
+        "{}",
+        out.stderr
+    );
+
+    let out = project.yex_in("src", &["lint", "main.yex"]);
+    assert_eq!(out.code, 1);
+    assert!(
+        out.stderr.contains("error[nil_comparison]"),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn the_config_option_replaces_the_project_file() {
+    let project = Project::new("lint-config");
+    project
+        .file(".yexlint", "nil_comparison = deny\n")
+        .file("quiet", "nil_comparison = allow\n")
+        .file("main.yex", NIL_COMPARISON);
+
+    let out = project.yex(&["lint", "--config", "quiet", "main.yex"]);
+    assert_eq!(out.code, 0, "{}", out.stderr);
+    assert_eq!(out.stderr, "");
+}
+
+#[test]
+fn invalid_project_files_are_errors() {
+    let project = Project::new("lint-invalid");
+    project
+        .file(
+            ".yexlint",
+            "nil_comparison = deny\nnil_comparisons = allow\n",
+        )
+        .file("main.yex", NIL_COMPARISON);
+
+    let out = project.yex(&["lint", "main.yex"]);
+    assert_eq!(out.code, 1);
+    assert!(
+        out.stderr
+            .contains("line 2: unknown rule `nil_comparisons`"),
+        "{}",
+        out.stderr
+    );
+}